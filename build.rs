@@ -9,6 +9,8 @@ pub fn main() {
     // #[cfg(release)]
     // println!("I'm in release mode!");
     // ```
+    println!("cargo:rustc-check-cfg=cfg(debug)");
+    println!("cargo:rustc-check-cfg=cfg(release)");
     if let Ok(profile) = env::var("PROFILE") {
         println!("cargo:rustc-cfg={}", profile);
     }
@@ -0,0 +1,342 @@
+use crate::karabiner::{KBProfile, KeyKind, Modifier, ToKBKeyCode};
+use crate::state::{
+    ComplexManipulator, Condition, FromEvent, FromModifier, SimpleManipulator, StateManager,
+    ToEvent,
+};
+use crate::util::format_key;
+use std::fmt::{self, Write};
+
+const HTML_STYLE: &str = "body { font-family: sans-serif; font-size: 11pt; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 1em; }
+th, td { border: 1px solid #ccc; padding: 0.2em 0.5em; text-align: left; }
+th { background: #eee; }
+section { break-inside: avoid; }";
+
+/// A summary of what the keys of a profile do, which can be printed as Markdown
+/// or HTML.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheatSheet {
+    pub profile: String,
+    pub sections: Vec<CheatSheetSection>,
+}
+
+/// The simple modifications, `fn_function_keys` or one rule of a profile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheatSheetSection {
+    pub title: String,
+    pub entries: Vec<CheatSheetEntry>,
+}
+
+/// A key (or keys) to press and what it does.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheatSheetEntry {
+    /// Eg: "left_shift + a".
+    pub keys: String,
+    /// Eg: "escape" or "left_control; escape (alone)".
+    pub sends: String,
+    /// The conditions for the entry to apply, eg: "tab_layer = 1".
+    pub when: Vec<String>,
+    pub description: Option<String>,
+}
+
+impl CheatSheet {
+    /// Creates a cheat sheet from the manipulators of `state`, which was created
+    /// from `kb_profile`. Its rules give the sections for complex modifications
+    /// their titles.
+    pub fn new(kb_profile: &KBProfile, state: &StateManager) -> CheatSheet {
+        let mut sections = vec![
+            simple_section("Simple modifications", state.simple_manipulators()),
+            simple_section("Function keys", state.fn_function_keys()),
+        ];
+
+        // The manipulators of every rule are in order, one rule after another.
+        let mut manipulators = state.complex_manipulators().iter();
+        for (i, rule) in kb_profile.complex_modifications.rules.iter().enumerate() {
+            let title = match &rule.description {
+                Some(description) => description.clone(),
+                None => format!("Rule {}", i + 1),
+            };
+            let entries = manipulators
+                .by_ref()
+                .take(rule.manipulators.len())
+                .map(|cm| complex_entry(cm, rule.description.as_deref()))
+                .collect();
+
+            sections.push(CheatSheetSection { title, entries });
+        }
+
+        sections.retain(|section| !section.entries.is_empty());
+        CheatSheet {
+            profile: kb_profile.name.clone(),
+            sections,
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        self.write_markdown(&mut markdown)
+            .expect("failed to write markdown");
+        markdown
+    }
+
+    /// A standalone HTML page, styled to be printed.
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        self.write_html(&mut html).expect("failed to write html");
+        html
+    }
+
+    fn write_markdown(&self, f: &mut String) -> fmt::Result {
+        writeln!(f, "# {}", self.profile)?;
+        for section in &self.sections {
+            let columns = section.columns();
+            write!(f, "\n## {}\n\n", section.title)?;
+            writeln!(f, "| {} |", columns.join(" | "))?;
+            writeln!(f, "|{}", " --- |".repeat(columns.len()))?;
+            for entry in &section.entries {
+                let cells: Vec<_> = section
+                    .cells(entry)
+                    .iter()
+                    .map(|cell| cell.replace('|', "\\|"))
+                    .collect();
+                writeln!(f, "| {} |", cells.join(" | "))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_html(&self, f: &mut String) -> fmt::Result {
+        writeln!(
+            f,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">"
+        )?;
+        writeln!(f, "<title>{}</title>", escape_html(&self.profile))?;
+        writeln!(f, "<style>\n{}\n</style>\n</head>\n<body>", HTML_STYLE)?;
+        writeln!(f, "<h1>{}</h1>", escape_html(&self.profile))?;
+        for section in &self.sections {
+            writeln!(f, "<section>\n<h2>{}</h2>", escape_html(&section.title))?;
+            write!(f, "<table>\n<thead><tr>")?;
+            for column in section.columns() {
+                write!(f, "<th>{}</th>", column)?;
+            }
+
+            writeln!(f, "</tr></thead>\n<tbody>")?;
+            for entry in &section.entries {
+                write!(f, "<tr>")?;
+                for cell in section.cells(entry) {
+                    write!(f, "<td>{}</td>", escape_html(&cell))?;
+                }
+
+                writeln!(f, "</tr>")?;
+            }
+
+            writeln!(f, "</tbody>\n</table>\n</section>")?;
+        }
+
+        writeln!(f, "</body>\n</html>")
+    }
+}
+
+impl CheatSheetSection {
+    /// The "When" and "Description" columns are only shown if they're used.
+    fn columns(&self) -> Vec<&'static str> {
+        let mut columns = vec!["Keys", "Sends"];
+        if self.entries.iter().any(|e| !e.when.is_empty()) {
+            columns.push("When");
+        }
+
+        if self.entries.iter().any(|e| e.description.is_some()) {
+            columns.push("Description");
+        }
+
+        columns
+    }
+
+    fn cells(&self, entry: &CheatSheetEntry) -> Vec<String> {
+        let mut cells = vec![entry.keys.clone(), entry.sends.clone()];
+        if self.entries.iter().any(|e| !e.when.is_empty()) {
+            cells.push(entry.when.join(", "));
+        }
+
+        if self.entries.iter().any(|e| e.description.is_some()) {
+            cells.push(entry.description.clone().unwrap_or_default());
+        }
+
+        cells
+    }
+}
+
+fn simple_section(title: &str, manipulators: &[SimpleManipulator]) -> CheatSheetSection {
+    let entries = manipulators
+        .iter()
+        .map(|sm| CheatSheetEntry {
+            keys: format_key(&sm.from),
+            sends: format_key(&sm.to),
+            when: vec![],
+            description: None,
+        })
+        .collect();
+
+    CheatSheetSection {
+        title: title.to_string(),
+        entries,
+    }
+}
+
+/// `rule_description` is left out of the entry, since it's the section's title.
+fn complex_entry(cm: &ComplexManipulator, rule_description: Option<&str>) -> CheatSheetEntry {
+    let mut sends = vec![];
+    let mut push = |to_events: &[ToEvent], suffix: &str| {
+        let described: Vec<_> = to_events
+            .iter()
+            .map(describe_to_event)
+            .filter(|d| !d.is_empty())
+            .collect();
+        if !described.is_empty() {
+            sends.push(format!("{}{}", described.join(", "), suffix));
+        }
+    };
+
+    push(&cm.to_events, "");
+    push(&cm.to_if_alone, " (alone)");
+    push(&cm.to_if_held_down, " (held)");
+    push(&cm.to_after_key_up, " (after release)");
+
+    CheatSheetEntry {
+        keys: describe_from_event(&cm.from_event),
+        sends: if sends.is_empty() {
+            String::from("nothing")
+        } else {
+            sends.join("; ")
+        },
+        when: cm.conditions.iter().map(describe_condition).collect(),
+        description: cm
+            .description
+            .clone()
+            .filter(|d| Some(d.as_str()) != rule_description),
+    }
+}
+
+fn describe_from_event(from_event: &FromEvent) -> String {
+    let modifiers_with = |from_modifier: FromModifier| -> Vec<&str> {
+        Modifier::VARIANTS
+            .iter()
+            .filter(|m| from_event.modifiers.get(**m) == Some(&from_modifier))
+            .filter_map(|m| m.to_kb_key_code())
+            .collect()
+    };
+
+    let key = match (&from_event.simultaneous, &from_event.key, from_event.any) {
+        (Some(simultaneous), _, _) => {
+            let names: Vec<_> = simultaneous.keys.iter().map(format_key).collect();
+            format!("{} (simultaneously)", names.join(" + "))
+        }
+        (None, Some(key), _) => format_key(key),
+        (None, None, Some(KeyKind::KeyCode)) => String::from("any key_code"),
+        (None, None, Some(KeyKind::ConsumerKeyCode)) => String::from("any consumer_key_code"),
+        (None, None, Some(KeyKind::PointingButton)) => String::from("any pointing_button"),
+        (None, None, None) => String::from("nothing"),
+    };
+
+    let mut keys = modifiers_with(FromModifier::Mandatory);
+    keys.push(&key);
+    let described = keys.join(" + ");
+
+    let optional = modifiers_with(FromModifier::Optional);
+    if optional.is_empty() {
+        described
+    } else {
+        format!("{} (optional: {})", described, optional.join(", "))
+    }
+}
+
+fn describe_to_event(to_event: &ToEvent) -> String {
+    let mut parts = vec![];
+
+    let mut keys: Vec<_> = to_event
+        .modifiers
+        .iter()
+        .filter_map(|m| m.to_kb_key_code())
+        .map(String::from)
+        .collect();
+    if to_event.forward {
+        keys.push(String::from("the pressed key"));
+    } else if let Some(key) = &to_event.key {
+        keys.push(format_key(key));
+    }
+
+    if !keys.is_empty() {
+        parts.push(keys.join(" + "));
+    }
+
+    if let Some(shell_command) = &to_event.shell_command {
+        parts.push(format!("run \"{}\"", shell_command));
+    }
+
+    if to_event.mouse_key.is_some() {
+        parts.push(String::from("mouse_key"));
+    }
+
+    if let Some(name) = &to_event.select_profile {
+        parts.push(format!("switch to profile \"{}\"", name));
+    }
+
+    if let Some((name, value)) = &to_event.set_variable {
+        parts.push(format!("set {} = {}", name, value));
+    }
+
+    parts.join(", ")
+}
+
+fn describe_condition(condition: &Condition) -> String {
+    match condition {
+        Condition::Variable { name, value, equal } => {
+            let operator = if *equal { "=" } else { "!=" };
+            format!("{} {} {}", name, operator, value)
+        }
+        Condition::Device {
+            identifiers,
+            matching,
+        } => {
+            let devices: Vec<_> = identifiers
+                .iter()
+                .map(|i| {
+                    let mut fields = vec![];
+                    if let Some(vendor_id) = i.vendor_id {
+                        fields.push(format!("vendor_id {}", vendor_id));
+                    }
+
+                    if let Some(product_id) = i.product_id {
+                        fields.push(format!("product_id {}", product_id));
+                    }
+
+                    if fields.is_empty() {
+                        String::from("any device")
+                    } else {
+                        fields.join(" ")
+                    }
+                })
+                .collect();
+            let operator = if *matching {
+                "device is"
+            } else {
+                "device isn't"
+            };
+            format!("{} {}", operator, devices.join(" or "))
+        }
+        Condition::FrontmostApplication { matching: true } => {
+            String::from("frontmost application (never met)")
+        }
+        Condition::FrontmostApplication { matching: false } => {
+            String::from("not frontmost application (always met)")
+        }
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
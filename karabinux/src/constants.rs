@@ -2,7 +2,7 @@ use evdev_rs::enums::EV_KEY;
 
 pub const KARABINUX_DEVICE_NAME: &str = "KarabinuxDevice: ";
 
-pub const ALL_MODIFIER_KEYS: [EV_KEY; 9] = [
+pub const ALL_MODIFIER_KEYS: [EV_KEY; 10] = [
     EV_KEY::KEY_CAPSLOCK,
     EV_KEY::KEY_LEFTALT,
     EV_KEY::KEY_RIGHTALT,
@@ -12,4 +12,9 @@ pub const ALL_MODIFIER_KEYS: [EV_KEY; 9] = [
     EV_KEY::KEY_RIGHTCTRL,
     EV_KEY::KEY_LEFTSHIFT,
     EV_KEY::KEY_RIGHTSHIFT,
+    EV_KEY::KEY_FN,
 ];
+
+/// How often (in milliseconds) the state is ticked, to send events that depend on
+/// time passing (eg: `to_if_held_down` events, or moving the pointer for `mouse_key`).
+pub const TICK_INTERVAL_MS: u64 = 20;
@@ -0,0 +1,211 @@
+use crate::goku::{GokuError, ParseError};
+use crate::kmonad::KmonadError;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Errors from loading a config file, or building a state from one of its profiles.
+#[derive(Debug)]
+pub enum Error {
+    /// The config file couldn't be read.
+    Io(io::Error),
+    /// The config file isn't valid JSON, or doesn't match the config's structure.
+    Json(serde_json::Error),
+    /// The config file isn't valid YAML, or doesn't match the config's structure.
+    Yaml(serde_yaml::Error),
+    /// The config file isn't valid TOML, or doesn't match the config's structure.
+    Toml(toml::de::Error),
+    /// The config file isn't valid EDN.
+    Edn(ParseError),
+    /// The config file is valid EDN, but isn't a Goku config that can be translated.
+    Goku(GokuError),
+    /// The config file is a KMonad keymap that can't be read or translated.
+    Kmonad(KmonadError),
+    /// The config file was parsed, but some of its values are invalid.
+    Config(Vec<ConfigError>),
+    /// An error from reading one of several files.
+    File(PathBuf, Box<Error>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "failed to read config: {}", e),
+            Error::Json(e) => write!(f, "failed to parse config: {}", e),
+            Error::Yaml(e) => write!(f, "failed to parse config: {}", e),
+            Error::Toml(e) => write!(f, "failed to parse config: {}", e),
+            Error::Edn(e) => write!(f, "failed to parse config: {}", e),
+            Error::Goku(e) => write!(f, "failed to translate Goku config: {}", e),
+            Error::Kmonad(e) => write!(f, "failed to translate KMonad config: {}", e),
+            Error::File(path, e) => write!(f, "{}: {}", path.display(), e),
+            Error::Config(errors) => {
+                write!(f, "found {} problem(s) in config:", errors.len())?;
+                for error in errors {
+                    write!(f, "\n  {}", error)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Error {
+        Error::Json(e)
+    }
+}
+
+impl From<serde_yaml::Error> for Error {
+    fn from(e: serde_yaml::Error) -> Error {
+        Error::Yaml(e)
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(e: toml::de::Error) -> Error {
+        Error::Toml(e)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Error {
+        Error::Edn(e)
+    }
+}
+
+impl From<GokuError> for Error {
+    fn from(e: GokuError) -> Error {
+        Error::Goku(e)
+    }
+}
+
+impl From<KmonadError> for Error {
+    fn from(e: KmonadError) -> Error {
+        Error::Kmonad(e)
+    }
+}
+
+/// A problem with a single value in a config file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigProblem {
+    UnknownKeyCode(String),
+    UnknownModifier(String),
+    /// A simple modification without a `key_code` or `consumer_key_code`, or a key in
+    /// `from.simultaneous` without a `key_code`.
+    MissingKeyCode,
+    /// A title in `complex_modifications.assets` that isn't in the assets directory.
+    UnknownAsset(String),
+    /// A `variable_if` or `variable_unless` condition without a `name` or `value`.
+    MissingVariable,
+    /// A file or glob in `includes` that doesn't match any files.
+    UnknownInclude(String),
+    /// An included file that includes itself, along with the files in between.
+    IncludeCycle(Vec<PathBuf>),
+    /// A key in a test that isn't a `key_code` followed by `↓` or `↑` (or a wait,
+    /// eg: `300ms`, in its input).
+    InvalidKeyStroke(String),
+    /// A profile a test file names that isn't in its config, or `None` if it doesn't
+    /// name one and no profile is selected.
+    UnknownProfile(Option<String>),
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigProblem::UnknownKeyCode(name) => write!(f, "unknown key_code \"{}\"", name),
+            ConfigProblem::UnknownModifier(name) => write!(f, "unknown modifier \"{}\"", name),
+            ConfigProblem::MissingKeyCode => write!(f, "missing key_code"),
+            ConfigProblem::UnknownAsset(title) => write!(f, "unknown asset \"{}\"", title),
+            ConfigProblem::MissingVariable => write!(f, "missing variable name or value"),
+            ConfigProblem::UnknownInclude(pattern) => {
+                write!(f, "no files match include \"{}\"", pattern)
+            }
+            ConfigProblem::IncludeCycle(paths) => {
+                let paths: Vec<_> = paths.iter().map(|p| p.display().to_string()).collect();
+                write!(f, "include cycle: {}", paths.join(" -> "))
+            }
+            ConfigProblem::InvalidKeyStroke(key_stroke) => write!(
+                f,
+                "invalid key \"{}\", expected a key_code followed by ↓ or ↑",
+                key_stroke
+            ),
+            ConfigProblem::UnknownProfile(Some(name)) => write!(f, "unknown profile \"{}\"", name),
+            ConfigProblem::UnknownProfile(None) => write!(f, "no profile is selected"),
+        }
+    }
+}
+
+/// A `ConfigProblem`, along with where it was found in the config.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    pub profile: Option<String>,
+    /// The included file the problem is in, if it's not in the config file itself.
+    pub file: Option<PathBuf>,
+    /// Eg: "simple_modifications[0]" or "complex_modifications.rules[1].manipulators[0]".
+    pub path: String,
+    pub rule: Option<usize>,
+    pub description: Option<String>,
+    pub problem: ConfigProblem,
+}
+
+impl ConfigError {
+    pub fn new(path: String, problem: ConfigProblem) -> ConfigError {
+        ConfigError {
+            profile: None,
+            file: None,
+            path,
+            rule: None,
+            description: None,
+            problem,
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(profile) = &self.profile {
+            write!(f, "profile \"{}\": ", profile)?;
+        }
+
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file.display())?;
+        }
+
+        write!(f, "{}", self.path)?;
+        if let Some(description) = &self.description {
+            write!(f, " (\"{}\")", description)?;
+        }
+
+        write!(f, ": {}", self.problem)
+    }
+}
+
+/// Collects the values from `results`, or every error if any of them failed.
+pub fn collect_results<T, E, I>(results: I) -> Result<Vec<T>, Vec<E>>
+where
+    I: IntoIterator<Item = Result<T, Vec<E>>>,
+{
+    let mut values = vec![];
+    let mut errors = vec![];
+    for result in results {
+        match result {
+            Ok(value) => values.push(value),
+            Err(e) => errors.extend(e),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(values)
+    } else {
+        Err(errors)
+    }
+}
@@ -4,4 +4,8 @@ use evdev_rs::InputEvent;
 pub enum Event {
     Timeout,
     KeyEvent(InputEvent),
+    /// Switch to the profile with the given name.
+    SelectProfile(String),
+    /// The config file was changed, and should be reloaded.
+    ConfigChanged,
 }
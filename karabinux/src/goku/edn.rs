@@ -0,0 +1,461 @@
+use std::fmt;
+use std::iter::Peekable;
+use std::str::{Chars, FromStr};
+
+/// A value read from an EDN document.
+///
+/// https://github.com/edn-format/edn
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edn {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Char(char),
+    /// A keyword, without its leading `:`.
+    Keyword(String),
+    Symbol(String),
+    List(Vec<Edn>),
+    Vector(Vec<Edn>),
+    /// Entries are kept in the order they were written.
+    Map(Vec<(Edn, Edn)>),
+    Set(Vec<Edn>),
+}
+
+impl FromStr for Edn {
+    type Err = ParseError;
+
+    /// Reads a single value from `input`, which may be surrounded by whitespace
+    /// and comments.
+    fn from_str(input: &str) -> Result<Edn, ParseError> {
+        let mut reader = Reader::new(input);
+        let value = match reader.read()? {
+            Some(value) => value,
+            None => return Err(reader.error("expected a value")),
+        };
+
+        match reader.read()? {
+            Some(_) => Err(reader.error("expected a single value")),
+            None => Ok(value),
+        }
+    }
+}
+
+impl Edn {
+    pub fn as_keyword(&self) -> Option<&str> {
+        match self {
+            Edn::Keyword(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Returns the items of a vector or list.
+    pub fn as_seq(&self) -> Option<&[Edn]> {
+        match self {
+            Edn::Vector(items) | Edn::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_map(&self) -> Option<&[(Edn, Edn)]> {
+        match self {
+            Edn::Map(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    /// Returns the value for the keyword `key`, if this is a map.
+    pub fn get(&self, key: &str) -> Option<&Edn> {
+        self.as_map()?
+            .iter()
+            .find(|(k, _)| k.as_keyword() == Some(key))
+            .map(|(_, v)| v)
+    }
+}
+
+impl fmt::Display for Edn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn write_items(f: &mut fmt::Formatter, items: &[Edn]) -> fmt::Result {
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " ")?;
+                }
+                write!(f, "{}", item)?;
+            }
+
+            Ok(())
+        }
+
+        match self {
+            Edn::Nil => write!(f, "nil"),
+            Edn::Bool(b) => write!(f, "{}", b),
+            Edn::Int(i) => write!(f, "{}", i),
+            Edn::Float(x) => write!(f, "{:?}", x),
+            Edn::String(s) => write!(f, "{:?}", s),
+            Edn::Char(c) => write!(f, "\\{}", c),
+            Edn::Keyword(name) => write!(f, ":{}", name),
+            Edn::Symbol(name) => write!(f, "{}", name),
+            Edn::List(items) => {
+                write!(f, "(")?;
+                write_items(f, items)?;
+                write!(f, ")")
+            }
+            Edn::Vector(items) => {
+                write!(f, "[")?;
+                write_items(f, items)?;
+                write!(f, "]")
+            }
+            Edn::Set(items) => {
+                write!(f, "#{{")?;
+                write_items(f, items)?;
+                write!(f, "}}")
+            }
+            Edn::Map(entries) => {
+                write!(f, "{{")?;
+                for (i, (k, v)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{} {}", k, v)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// An EDN document that couldn't be read, and where the problem was found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {} column {}",
+            self.message, self.line, self.column
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+struct Reader<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(input: &'a str) -> Reader<'a> {
+        Reader {
+            chars: input.chars().peekable(),
+            line: 1,
+            column: 0,
+        }
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: self.column + 1,
+            message: message.to_string(),
+        }
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 0;
+        } else {
+            self.column += 1;
+        }
+
+        Some(c)
+    }
+
+    /// Skips whitespace (commas are whitespace in EDN), comments, and values
+    /// discarded with `#_`.
+    fn skip_ignored(&mut self) -> Result<(), ParseError> {
+        while let Some(&c) = self.chars.peek() {
+            if c.is_whitespace() || c == ',' {
+                self.next_char();
+            } else if c == ';' {
+                while let Some(c) = self.next_char() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            } else if c == '#' && self.chars.clone().nth(1) == Some('_') {
+                self.next_char();
+                self.next_char();
+                if self.read()?.is_none() {
+                    return Err(self.error("expected a value to discard"));
+                }
+            } else {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the next value, or `None` at the end of the input.
+    fn read(&mut self) -> Result<Option<Edn>, ParseError> {
+        self.skip_ignored()?;
+        let c = match self.chars.peek() {
+            Some(&c) => c,
+            None => return Ok(None),
+        };
+
+        let value = match c {
+            '(' => {
+                self.next_char();
+                Edn::List(self.read_until(')')?)
+            }
+            '[' => {
+                self.next_char();
+                Edn::Vector(self.read_until(']')?)
+            }
+            '{' => {
+                self.next_char();
+                self.read_map()?
+            }
+            ')' | ']' | '}' => return Err(self.error(&format!("unexpected '{}'", c))),
+            '"' => {
+                self.next_char();
+                Edn::String(self.read_string()?)
+            }
+            '\\' => {
+                self.next_char();
+                self.read_char()?
+            }
+            '#' => {
+                self.next_char();
+                match self.chars.peek() {
+                    Some('{') => {
+                        self.next_char();
+                        Edn::Set(self.read_until('}')?)
+                    }
+                    _ => return Err(self.error("tagged values aren't supported")),
+                }
+            }
+            _ => self.read_atom()?,
+        };
+
+        Ok(Some(value))
+    }
+
+    fn read_until(&mut self, close: char) -> Result<Vec<Edn>, ParseError> {
+        let mut items = vec![];
+        loop {
+            self.skip_ignored()?;
+            match self.chars.peek() {
+                Some(&c) if c == close => {
+                    self.next_char();
+                    return Ok(items);
+                }
+                Some(_) => match self.read()? {
+                    Some(item) => items.push(item),
+                    None => break,
+                },
+                None => break,
+            }
+        }
+
+        Err(self.error(&format!("expected '{}'", close)))
+    }
+
+    fn read_map(&mut self) -> Result<Edn, ParseError> {
+        let items = self.read_until('}')?;
+        if items.len() % 2 != 0 {
+            return Err(self.error("map has a key without a value"));
+        }
+
+        let mut entries = vec![];
+        let mut items = items.into_iter();
+        while let (Some(k), Some(v)) = (items.next(), items.next()) {
+            entries.push((k, v));
+        }
+
+        Ok(Edn::Map(entries))
+    }
+
+    fn read_string(&mut self) -> Result<String, ParseError> {
+        let mut s = String::new();
+        loop {
+            match self.next_char() {
+                Some('"') => return Ok(s),
+                Some('\\') => match self.next_char() {
+                    Some('n') => s.push('\n'),
+                    Some('t') => s.push('\t'),
+                    Some('r') => s.push('\r'),
+                    Some('"') => s.push('"'),
+                    Some('\\') => s.push('\\'),
+                    Some(c) => return Err(self.error(&format!("unknown escape '\\{}'", c))),
+                    None => break,
+                },
+                Some(c) => s.push(c),
+                None => break,
+            }
+        }
+
+        Err(self.error("unterminated string"))
+    }
+
+    fn read_char(&mut self) -> Result<Edn, ParseError> {
+        let token = self.read_token();
+        let c = match token.as_str() {
+            "newline" => '\n',
+            "space" => ' ',
+            "tab" => '\t',
+            "return" => '\r',
+            _ => {
+                let mut chars = token.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => c,
+                    // Delimiters can be characters too, eg: `\(`.
+                    (None, _) => match self.next_char() {
+                        Some(c) => c,
+                        None => return Err(self.error("expected a character")),
+                    },
+                    _ => return Err(self.error(&format!("unknown character \\{}", token))),
+                }
+            }
+        };
+
+        Ok(Edn::Char(c))
+    }
+
+    /// Reads characters up to the next delimiter.
+    fn read_token(&mut self) -> String {
+        let mut token = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c.is_whitespace() || "()[]{}\",;".contains(c) {
+                break;
+            }
+
+            token.push(c);
+            self.next_char();
+        }
+
+        token
+    }
+
+    fn read_atom(&mut self) -> Result<Edn, ParseError> {
+        let token = self.read_token();
+        if let Some(name) = token.strip_prefix(':') {
+            if name.is_empty() {
+                return Err(self.error("expected a keyword name"));
+            }
+
+            return Ok(Edn::Keyword(name.to_string()));
+        }
+
+        match token.as_str() {
+            "nil" => return Ok(Edn::Nil),
+            "true" => return Ok(Edn::Bool(true)),
+            "false" => return Ok(Edn::Bool(false)),
+            _ => {}
+        }
+
+        let unsigned = token.trim_start_matches(['+', '-']);
+        if unsigned.starts_with(|c: char| c.is_ascii_digit()) {
+            return parse_number(&token)
+                .ok_or_else(|| self.error(&format!("invalid number \"{}\"", token)));
+        }
+
+        Ok(Edn::Symbol(token))
+    }
+}
+
+fn parse_number(token: &str) -> Option<Edn> {
+    // `N` and `M` mark arbitrary precision numbers, which are read as normal ones.
+    if let Some(int) = token.strip_suffix('N') {
+        return int.parse().ok().map(Edn::Int);
+    }
+
+    let token = token.strip_suffix('M').unwrap_or(token);
+    match token.parse() {
+        Ok(int) => Some(Edn::Int(int)),
+        Err(_) => token.parse().ok().map(Edn::Float),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Edn, ParseError};
+    use pretty_assertions::assert_eq;
+
+    fn parse(input: &str) -> Result<Edn, ParseError> {
+        input.parse()
+    }
+
+    fn keyword(name: &str) -> Edn {
+        Edn::Keyword(name.to_string())
+    }
+
+    #[test]
+    fn reads_scalars() {
+        assert_eq!(parse("nil"), Ok(Edn::Nil));
+        assert_eq!(parse("true"), Ok(Edn::Bool(true)));
+        assert_eq!(parse("-42"), Ok(Edn::Int(-42)));
+        assert_eq!(parse("1.5"), Ok(Edn::Float(1.5)));
+        assert_eq!(
+            parse(r#""say \"hi\"\n""#),
+            Ok(Edn::String(String::from("say \"hi\"\n")))
+        );
+        assert_eq!(parse(r"\a"), Ok(Edn::Char('a')));
+        assert_eq!(parse(":!CTOleft_shift"), Ok(keyword("!CTOleft_shift")));
+        assert_eq!(parse(":##a"), Ok(keyword("##a")));
+        assert_eq!(
+            parse("my-symbol"),
+            Ok(Edn::Symbol(String::from("my-symbol")))
+        );
+    }
+
+    #[test]
+    fn reads_collections_with_comments() {
+        let input = r#"
+            ; a comment
+            {:a [1, 2 #_3]
+             :b (nil) ; another comment
+             "c" #{:d}}
+        "#;
+
+        assert_eq!(
+            parse(input),
+            Ok(Edn::Map(vec![
+                (keyword("a"), Edn::Vector(vec![Edn::Int(1), Edn::Int(2)])),
+                (keyword("b"), Edn::List(vec![Edn::Nil])),
+                (Edn::String(String::from("c")), Edn::Set(vec![keyword("d")])),
+            ]))
+        );
+    }
+
+    #[test]
+    fn reports_error_location() {
+        let error = parse("{:a 1\n :b [2 3}").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 9));
+        assert_eq!(error.message, "unexpected '}'");
+
+        let error = parse("{:a}").unwrap_err();
+        assert_eq!(error.message, "map has a key without a value");
+
+        let error = parse("[1 2").unwrap_err();
+        assert_eq!(error.message, "expected ']'");
+    }
+
+    #[test]
+    fn get_finds_keyword_entries() {
+        let map = parse("{:a 1 :b 2}").unwrap();
+        assert_eq!(map.get("b"), Some(&Edn::Int(2)));
+        assert_eq!(map.get("c"), None);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod edn;
+pub mod translate;
+
+pub use edn::*;
+pub use translate::*;
@@ -0,0 +1,863 @@
+use crate::error::Error;
+use crate::goku::Edn;
+use crate::karabiner::KBConfig;
+use serde_json::{json, Map, Value};
+use std::fmt;
+
+/// Goku waits longer for simlayers than for other simultaneous keys, so that
+/// typing quickly doesn't activate them.
+const SIMLAYER_THRESHOLD_MILLISECONDS: i64 = 250;
+
+/// The sections of a Goku config that can be translated.
+const SECTIONS: &[&str] = &[
+    "profiles",
+    "templates",
+    "devices",
+    "applications",
+    "layers",
+    "simlayers",
+    "simlayer-threshold",
+    "main",
+];
+
+/// A Goku config that was read, but can't be translated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GokuError {
+    /// Eg: "main[0].rules[1]" or "layers.tab-mode".
+    pub path: String,
+    pub message: String,
+}
+
+impl GokuError {
+    fn new(path: &str, message: String) -> GokuError {
+        GokuError {
+            path: path.to_string(),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for GokuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for GokuError {}
+
+/// Reads a Goku `karabiner.edn` config, and translates it to a `KBConfig`.
+pub fn config_from_str(input: &str) -> Result<KBConfig, Error> {
+    let edn = input.parse::<Edn>()?;
+    Ok(config_from_goku(&edn)?)
+}
+
+/// Translates a Goku config to a `KBConfig`. Every profile gets the rules in `:main`.
+///
+/// https://github.com/yqrashawn/GokuRakuJoudo/blob/master/tutorial.md
+pub fn config_from_goku(edn: &Edn) -> Result<KBConfig, GokuError> {
+    let entries = edn
+        .as_map()
+        .ok_or_else(|| GokuError::new("", format!("expected a map, got {}", edn)))?;
+
+    for (key, _) in entries {
+        match key.as_keyword() {
+            Some(name) if SECTIONS.contains(&name) => {}
+            _ => return Err(GokuError::new("", format!("unsupported section {}", key))),
+        }
+    }
+
+    let goku = Goku::new(edn)?;
+    let rules = goku.rules(edn.get("main"))?;
+    let profiles = goku.profiles(edn.get("profiles"), &rules)?;
+
+    serde_json::from_value(json!({ "profiles": profiles }))
+        .map_err(|e| GokuError::new("", format!("invalid config: {}", e)))
+}
+
+/// A key that sets a variable while it's held, see `:layers` and `:simlayers`.
+struct Layer {
+    name: String,
+    key: Edn,
+    alone: Option<Edn>,
+    conditions: Vec<Edn>,
+}
+
+/// The named things that rules can refer to.
+struct Goku<'a> {
+    templates: Vec<(String, String)>,
+    applications: Vec<(String, &'a Edn)>,
+    devices: Vec<(String, &'a Edn)>,
+    layers: Vec<Layer>,
+    simlayers: Vec<Layer>,
+    simlayer_threshold: i64,
+}
+
+impl<'a> Goku<'a> {
+    fn new(edn: &'a Edn) -> Result<Goku<'a>, GokuError> {
+        let templates = named(edn, "templates")?
+            .into_iter()
+            .map(|(name, value)| match value {
+                Edn::String(template) => Ok((name, template.clone())),
+                _ => Err(GokuError::new(
+                    &format!("templates.{}", name),
+                    format!("expected a string, got {}", value),
+                )),
+            })
+            .collect::<Result<_, _>>()?;
+
+        let layers = layers_from(edn, "layers")?;
+        let simlayers = layers_from(edn, "simlayers")?;
+        let simlayer_threshold = match edn.get("simlayer-threshold") {
+            Some(Edn::Int(ms)) => *ms,
+            Some(value) => {
+                return Err(GokuError::new(
+                    "simlayer-threshold",
+                    format!("expected a number, got {}", value),
+                ))
+            }
+            None => SIMLAYER_THRESHOLD_MILLISECONDS,
+        };
+
+        Ok(Goku {
+            templates,
+            applications: named(edn, "applications")?,
+            devices: named(edn, "devices")?,
+            layers,
+            simlayers,
+            simlayer_threshold,
+        })
+    }
+
+    fn profiles(&self, edn: Option<&Edn>, rules: &[Value]) -> Result<Vec<Value>, GokuError> {
+        let entries = match edn {
+            Some(edn) => edn.as_map().ok_or_else(|| {
+                GokuError::new("profiles", format!("expected a map, got {}", edn))
+            })?,
+            None => &[],
+        };
+
+        let mut profiles = vec![];
+        for (key, options) in entries {
+            let name = key_name(key);
+            let path = format!("profiles.{}", name);
+
+            let mut parameters = Map::new();
+            for (option, parameter) in &[
+                ("sim", "basic.simultaneous_threshold_milliseconds"),
+                ("delay", "basic.to_delayed_action_delay_milliseconds"),
+                ("alone", "basic.to_if_alone_timeout_milliseconds"),
+                ("held", "basic.to_if_held_down_threshold_milliseconds"),
+            ] {
+                match options.get(option) {
+                    Some(Edn::Int(ms)) => {
+                        parameters.insert(parameter.to_string(), json!(ms));
+                    }
+                    Some(value) => {
+                        return Err(GokuError::new(
+                            &path,
+                            format!(":{} should be a number, got {}", option, value),
+                        ))
+                    }
+                    None => {}
+                }
+            }
+
+            let mut complex_modifications = json!({ "rules": rules });
+            if !parameters.is_empty() {
+                complex_modifications["parameters"] = Value::Object(parameters);
+            }
+
+            profiles.push(json!({
+                "name": name,
+                "selected": options.get("default") == Some(&Edn::Bool(true)),
+                "simple_modifications": [],
+                "complex_modifications": complex_modifications,
+            }));
+        }
+
+        if profiles.is_empty() {
+            profiles.push(json!({
+                "name": "Default",
+                "selected": true,
+                "simple_modifications": [],
+                "complex_modifications": { "rules": rules },
+            }));
+        }
+
+        // Goku uses the first profile if none of them are the default.
+        if !profiles.iter().any(|p| p["selected"] == Value::Bool(true)) {
+            profiles[0]["selected"] = Value::Bool(true);
+        }
+
+        Ok(profiles)
+    }
+
+    /// Translates each `{:des "..." :rules [...]}` in `:main` to a rule.
+    fn rules(&self, main: Option<&Edn>) -> Result<Vec<Value>, GokuError> {
+        let blocks = match main {
+            Some(main) => main.as_seq().ok_or_else(|| {
+                GokuError::new("main", format!("expected a vector, got {}", main))
+            })?,
+            None => &[],
+        };
+
+        let mut activated_layers = vec![];
+        let mut rules = vec![];
+        for (i, block) in blocks.iter().enumerate() {
+            let path = format!("main[{}]", i);
+            let description = match block.get("des") {
+                Some(Edn::String(des)) => Some(des.clone()),
+                Some(des) => {
+                    return Err(GokuError::new(
+                        &path,
+                        format!(":des should be a string, got {}", des),
+                    ))
+                }
+                None => None,
+            };
+
+            let items = block
+                .get("rules")
+                .and_then(Edn::as_seq)
+                .ok_or_else(|| GokuError::new(&path, String::from("expected :rules vector")))?;
+
+            let mut layer_manipulators = vec![];
+            let mut manipulators = vec![];
+            let mut block_conditions: Vec<Edn> = vec![];
+            for (j, item) in items.iter().enumerate() {
+                let path = format!("{}.rules[{}]", path, j);
+                let to_error = |message| GokuError::new(&path, message);
+
+                // A keyword (or `[:condi ...]`) sets the conditions of the rules after it.
+                let rule = match item {
+                    Edn::Keyword(_) => {
+                        block_conditions = vec![item.clone()];
+                        continue;
+                    }
+                    Edn::Vector(items) if items.first() == Some(&keyword("condi")) => {
+                        block_conditions = items[1..].to_vec();
+                        continue;
+                    }
+                    Edn::Vector(rule) => rule,
+                    _ => return Err(to_error(format!("expected a rule, got {}", item))),
+                };
+
+                let rule_manipulators = self.rule(rule, &block_conditions).map_err(to_error)?;
+
+                // Layers are activated by a manipulator before the first rule that uses them.
+                for layer in self.used_layers(rule, &block_conditions) {
+                    if !activated_layers.contains(&layer.name) {
+                        activated_layers.push(layer.name.clone());
+                        layer_manipulators.push(self.layer_manipulator(layer).map_err(
+                            |message| GokuError::new(&format!("layers.{}", layer.name), message),
+                        )?);
+                    }
+                }
+
+                manipulators.extend(rule_manipulators);
+            }
+
+            let mut rule = json!({
+                "manipulators": layer_manipulators
+                    .into_iter()
+                    .chain(manipulators)
+                    .collect::<Vec<_>>(),
+            });
+            if let Some(description) = description {
+                rule["description"] = Value::String(description);
+            }
+
+            rules.push(rule);
+        }
+
+        Ok(rules)
+    }
+
+    /// Translates a `[from to conditions options]` rule to its manipulators.
+    fn rule(&self, rule: &[Edn], block_conditions: &[Edn]) -> Result<Vec<Value>, String> {
+        if rule.len() < 2 || rule.len() > 4 {
+            return Err(format!(
+                "expected [from to conditions options], got {}",
+                Edn::Vector(rule.to_vec())
+            ));
+        }
+
+        let from = self.from(&rule[0])?;
+        let to = self.to_list(&rule[1])?;
+        let conditions = rule_conditions(rule, block_conditions);
+
+        let mut manipulator = json!({ "type": "basic", "from": from });
+        if !to.is_empty() {
+            manipulator["to"] = json!(to);
+        }
+
+        let kb_conditions = conditions
+            .iter()
+            .map(|c| self.condition(c))
+            .collect::<Result<Vec<_>, _>>()?;
+        if !kb_conditions.is_empty() {
+            manipulator["conditions"] = json!(kb_conditions);
+        }
+
+        if let Some(options) = rule.get(3) {
+            self.options(options, &mut manipulator)?;
+        }
+
+        let mut manipulators = vec![manipulator.clone()];
+
+        // Simlayers are also activated by pressing the layer's key along with this rule's.
+        for (i, condition) in conditions.iter().enumerate() {
+            let simlayer = match condition.as_keyword().and_then(|n| self.simlayer(n)) {
+                Some(simlayer) => simlayer,
+                None => continue,
+            };
+
+            let from_key = match from.get("key_code") {
+                Some(key_code) => key_code.clone(),
+                None => continue,
+            };
+
+            let (layer_key, _, _) = parse_key(&key_name(&simlayer.key))?;
+            let set_variable =
+                |value| json!({ "set_variable": { "name": simlayer.name, "value": value } });
+
+            let mut simultaneous = manipulator.clone();
+            let mut from = json!({
+                "simultaneous": [{ "key_code": layer_key }, { "key_code": from_key }],
+                "simultaneous_options": {
+                    "detect_key_down_uninterruptedly": true,
+                    "key_down_order": "strict",
+                    "key_up_order": "strict_inverse",
+                    "key_up_when": "any",
+                    "to_after_key_up": [set_variable(0)],
+                },
+            });
+            if let Some(modifiers) = manipulator["from"].get("modifiers") {
+                from["modifiers"] = modifiers.clone();
+            }
+
+            simultaneous["from"] = from;
+            simultaneous["to"] = json!(std::iter::once(set_variable(1))
+                .chain(to.iter().cloned())
+                .collect::<Vec<_>>());
+            simultaneous["parameters"] = json!({
+                "basic.simultaneous_threshold_milliseconds": self.simlayer_threshold,
+            });
+
+            let other_conditions = kb_conditions
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, c)| c.clone())
+                .collect::<Vec<_>>();
+            if other_conditions.is_empty() {
+                simultaneous.as_object_mut().unwrap().remove("conditions");
+            } else {
+                simultaneous["conditions"] = json!(other_conditions);
+            }
+
+            manipulators.push(simultaneous);
+        }
+
+        Ok(manipulators)
+    }
+
+    /// The layers (not simlayers) that a rule's conditions refer to.
+    fn used_layers(&self, rule: &[Edn], block_conditions: &[Edn]) -> Vec<&Layer> {
+        rule_conditions(rule, block_conditions)
+            .iter()
+            .filter_map(|c| c.as_keyword())
+            .filter_map(|name| self.layers.iter().find(|l| l.name == name))
+            .collect()
+    }
+
+    fn simlayer(&self, name: &str) -> Option<&Layer> {
+        self.simlayers.iter().find(|l| l.name == name)
+    }
+
+    /// The manipulator that sets a layer's variable while its key is held.
+    fn layer_manipulator(&self, layer: &Layer) -> Result<Value, String> {
+        let set_variable =
+            |value| json!({ "set_variable": { "name": layer.name, "value": value } });
+
+        let to_if_alone = match &layer.alone {
+            Some(alone) => self.to_list(alone)?,
+            None => self.to_list(&layer.key)?,
+        };
+
+        let mut manipulator = json!({
+            "type": "basic",
+            "from": self.from(&layer.key)?,
+            "to": [set_variable(1)],
+            "to_after_key_up": [set_variable(0)],
+            "to_if_alone": to_if_alone,
+        });
+
+        let conditions = layer
+            .conditions
+            .iter()
+            .map(|c| self.condition(c))
+            .collect::<Result<Vec<_>, _>>()?;
+        if !conditions.is_empty() {
+            manipulator["conditions"] = json!(conditions);
+        }
+
+        Ok(manipulator)
+    }
+
+    /// Translates a from-event, eg: `:!Ca` or `[:j :k]` (pressed simultaneously).
+    fn from(&self, edn: &Edn) -> Result<Value, String> {
+        match edn {
+            Edn::Keyword(name) => {
+                let (key, mandatory, optional) = parse_key(name)?;
+                let mut from = json!({ "key_code": key });
+                if let Some(modifiers) = from_modifiers(mandatory, optional) {
+                    from["modifiers"] = modifiers;
+                }
+
+                Ok(from)
+            }
+            Edn::Vector(keys) if !keys.is_empty() => {
+                let mut simultaneous = vec![];
+                let mut all_mandatory = vec![];
+                let mut all_optional = vec![];
+                for key in keys {
+                    let name = key
+                        .as_keyword()
+                        .ok_or_else(|| format!("expected a key, got {}", key))?;
+                    let (key, mandatory, optional) = parse_key(name)?;
+                    simultaneous.push(json!({ "key_code": key }));
+                    all_mandatory.extend(mandatory);
+                    all_optional.extend(optional);
+                }
+
+                let mut from = json!({ "simultaneous": simultaneous });
+                if let Some(modifiers) = from_modifiers(all_mandatory, all_optional) {
+                    from["modifiers"] = modifiers;
+                }
+
+                Ok(from)
+            }
+            _ => Err(format!("unsupported from {}", edn)),
+        }
+    }
+
+    /// Translates the to-events of a rule, which may be a single event or a vector of them.
+    fn to_list(&self, edn: &Edn) -> Result<Vec<Value>, String> {
+        match edn {
+            Edn::Nil => Ok(vec![]),
+            Edn::Keyword(name) => {
+                let (key, mandatory, _) = parse_key(name)?;
+                let mut to = json!({ "key_code": key });
+                if !mandatory.is_empty() {
+                    to["modifiers"] = json!(mandatory);
+                }
+
+                Ok(vec![to])
+            }
+            Edn::String(command) => Ok(vec![json!({ "shell_command": command })]),
+            Edn::Vector(items) => match items.as_slice() {
+                // `["name" value]` sets a variable.
+                [Edn::String(name), value] => Ok(vec![json!({
+                    "set_variable": { "name": name, "value": edn_to_json(value) },
+                })]),
+
+                // `[:template args...]` runs a shell command from `:templates`.
+                [Edn::Keyword(name), args @ ..] if self.template(name).is_some() => {
+                    let template = self.template(name).unwrap();
+                    Ok(vec![
+                        json!({ "shell_command": format_template(template, args) }),
+                    ])
+                }
+
+                _ => {
+                    let mut to = vec![];
+                    for item in items {
+                        to.extend(self.to_list(item)?);
+                    }
+
+                    Ok(to)
+                }
+            },
+            Edn::Map(_) => Ok(vec![self.to_map(edn)?]),
+            _ => Err(format!("unsupported to {}", edn)),
+        }
+    }
+
+    /// Translates a to-event written as a map, eg: `{:mkey {:x 100}}` or `{:key :a :repeat false}`.
+    fn to_map(&self, edn: &Edn) -> Result<Value, String> {
+        let mut to = Map::new();
+        for (key, value) in edn.as_map().unwrap_or_default() {
+            match key.as_keyword() {
+                Some("key") => {
+                    let name = value
+                        .as_keyword()
+                        .ok_or_else(|| format!(":key should be a keyword, got {}", value))?;
+                    let (key, mandatory, _) = parse_key(name)?;
+                    to.insert(String::from("key_code"), json!(key));
+                    if !mandatory.is_empty() {
+                        to.insert(String::from("modifiers"), json!(mandatory));
+                    }
+                }
+                Some("modi") => {
+                    let modifiers = match value {
+                        Edn::Keyword(name) => vec![json!(name)],
+                        Edn::Vector(names) => names.iter().map(edn_to_json).collect(),
+                        _ => return Err(format!(":modi should be a keyword, got {}", value)),
+                    };
+                    to.insert(String::from("modifiers"), json!(modifiers));
+                }
+                Some("mkey") => {
+                    to.insert(String::from("mouse_key"), edn_to_json(value));
+                }
+                Some("repeat") => {
+                    to.insert(String::from("repeat"), edn_to_json(value));
+                }
+                _ => return Err(format!("unsupported to {}", edn)),
+            }
+        }
+
+        Ok(Value::Object(to))
+    }
+
+    fn template(&self, name: &str) -> Option<&str> {
+        self.templates
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, t)| t.as_str())
+    }
+
+    /// Translates the options map of a rule, eg: `{:alone :escape :params {:alone 300}}`.
+    fn options(&self, edn: &Edn, manipulator: &mut Value) -> Result<(), String> {
+        let entries = edn
+            .as_map()
+            .ok_or_else(|| format!("options should be a map, got {}", edn))?;
+
+        for (key, value) in entries {
+            match key.as_keyword() {
+                Some("alone") => manipulator["to_if_alone"] = json!(self.to_list(value)?),
+                Some("held") => manipulator["to_if_held_down"] = json!(self.to_list(value)?),
+                Some("afterup") => manipulator["to_after_key_up"] = json!(self.to_list(value)?),
+                Some("params") => {
+                    let mut parameters = Map::new();
+                    for (param, ms) in value.as_map().unwrap_or_default() {
+                        let name = match param.as_keyword() {
+                            Some("alone") => "basic.to_if_alone_timeout_milliseconds",
+                            Some("held") => "basic.to_if_held_down_threshold_milliseconds",
+                            Some("delay") => "basic.to_delayed_action_delay_milliseconds",
+                            Some("sim") => "basic.simultaneous_threshold_milliseconds",
+                            _ => return Err(format!("unsupported parameter {}", param)),
+                        };
+                        parameters.insert(name.to_string(), edn_to_json(ms));
+                    }
+
+                    manipulator["parameters"] = Value::Object(parameters);
+                }
+                _ => return Err(format!("unsupported option {}", key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Translates a condition, eg: `:chrome` or `:!vi-mode` (the `!` negates it),
+    /// or `["variable" value]`.
+    fn condition(&self, edn: &Edn) -> Result<Value, String> {
+        let name = match edn {
+            Edn::Keyword(name) => name.as_str(),
+            Edn::Vector(items) => match items.as_slice() {
+                [Edn::String(name), value] => {
+                    return Ok(json!({
+                        "type": "variable_if",
+                        "name": name,
+                        "value": edn_to_json(value),
+                    }))
+                }
+                _ => return Err(format!("unsupported condition {}", edn)),
+            },
+            _ => return Err(format!("unsupported condition {}", edn)),
+        };
+
+        let (name, suffix) = match name.strip_prefix('!') {
+            Some(name) => (name, "unless"),
+            None => (name, "if"),
+        };
+
+        if let Some((_, bundle_identifiers)) = self.applications.iter().find(|(n, _)| n == name) {
+            return Ok(json!({
+                "type": format!("frontmost_application_{}", suffix),
+                "bundle_identifiers": edn_to_json(bundle_identifiers),
+            }));
+        }
+
+        if let Some((_, identifiers)) = self.devices.iter().find(|(n, _)| n == name) {
+            return Ok(json!({
+                "type": format!("device_{}", suffix),
+                "identifiers": edn_to_json(identifiers),
+            }));
+        }
+
+        // Layers, and anything else, are variables which are set to 1 when active.
+        Ok(json!({
+            "type": format!("variable_{}", suffix),
+            "name": name,
+            "value": 1,
+        }))
+    }
+}
+
+/// The conditions for a rule: those set before it in `:rules`, and its own.
+fn rule_conditions(rule: &[Edn], block_conditions: &[Edn]) -> Vec<Edn> {
+    let mut conditions = block_conditions.to_vec();
+    match rule.get(2) {
+        None | Some(Edn::Nil) => {}
+        Some(condition @ Edn::Keyword(_)) => conditions.push(condition.clone()),
+        Some(condition @ Edn::Vector(items)) => match items.as_slice() {
+            [Edn::String(_), _] => conditions.push(condition.clone()),
+            _ => conditions.extend(items.iter().cloned()),
+        },
+        Some(condition) => conditions.push(condition.clone()),
+    }
+
+    conditions
+}
+
+/// Returns the entries of a section which maps names to values, eg: `:applications`.
+fn named<'a>(edn: &'a Edn, section: &str) -> Result<Vec<(String, &'a Edn)>, GokuError> {
+    match edn.get(section) {
+        Some(value) => match value.as_map() {
+            Some(entries) => Ok(entries.iter().map(|(k, v)| (key_name(k), v)).collect()),
+            None => Err(GokuError::new(
+                section,
+                format!("expected a map, got {}", value),
+            )),
+        },
+        None => Ok(vec![]),
+    }
+}
+
+/// Reads `:layers` or `:simlayers`, eg: `{:tab-mode {:key :tab}}` or `{:tab-mode :tab}`.
+fn layers_from(edn: &Edn, section: &str) -> Result<Vec<Layer>, GokuError> {
+    named(edn, section)?
+        .into_iter()
+        .map(|(name, value)| {
+            let (key, alone, conditions) = match value {
+                Edn::Keyword(_) => (value.clone(), None, vec![]),
+                Edn::Map(_) => {
+                    let key = value.get("key").cloned().ok_or_else(|| {
+                        GokuError::new(
+                            &format!("{}.{}", section, name),
+                            String::from("missing :key"),
+                        )
+                    })?;
+                    let conditions = match value.get("condi") {
+                        Some(Edn::Vector(conditions)) => conditions.clone(),
+                        Some(condition) => vec![condition.clone()],
+                        None => vec![],
+                    };
+
+                    (key, value.get("alone").cloned(), conditions)
+                }
+                _ => {
+                    return Err(GokuError::new(
+                        &format!("{}.{}", section, name),
+                        format!("expected a key or a map, got {}", value),
+                    ))
+                }
+            };
+
+            Ok(Layer {
+                name,
+                key,
+                alone,
+                conditions,
+            })
+        })
+        .collect()
+}
+
+/// Splits Goku's modifier prefixes from a key, eg: `!CSa` is `a` with mandatory
+/// `left_command` and `left_shift`. `!!` is mandatory command, control, option
+/// and shift, and `##` is optional any.
+fn parse_key(name: &str) -> Result<(String, Vec<String>, Vec<String>), String> {
+    let mut mandatory = vec![];
+    let mut optional = vec![];
+    let mut rest = name;
+
+    loop {
+        if let Some(after) = rest.strip_prefix("!!") {
+            mandatory.extend(
+                ["left_command", "left_control", "left_option", "left_shift"]
+                    .iter()
+                    .map(|m| m.to_string()),
+            );
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("##") {
+            optional.push(String::from("any"));
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('!') {
+            rest = take_modifiers(after, &mut mandatory)?;
+        } else if let Some(after) = rest.strip_prefix('#') {
+            rest = take_modifiers(after, &mut optional)?;
+        } else {
+            break;
+        }
+    }
+
+    if rest.is_empty() {
+        return Err(format!("missing key in :{}", name));
+    }
+
+    Ok((rest.to_string(), mandatory, optional))
+}
+
+/// Reads modifier letters from the start of `s` (key codes are lower case).
+fn take_modifiers<'a>(s: &'a str, modifiers: &mut Vec<String>) -> Result<&'a str, String> {
+    let end = s.find(|c: char| !c.is_ascii_uppercase()).unwrap_or(s.len());
+
+    for letter in s[..end].chars() {
+        let modifier = match letter {
+            'C' => "left_command",
+            'T' => "left_control",
+            'O' => "left_option",
+            'S' => "left_shift",
+            'F' => "fn",
+            'Q' => "right_command",
+            'W' => "right_control",
+            'E' => "right_option",
+            'R' => "right_shift",
+            'P' => "caps_lock",
+            _ => return Err(format!("unknown modifier {}", letter)),
+        };
+        modifiers.push(modifier.to_string());
+    }
+
+    Ok(&s[end..])
+}
+
+fn from_modifiers(mandatory: Vec<String>, optional: Vec<String>) -> Option<Value> {
+    let mut modifiers = Map::new();
+    if !mandatory.is_empty() {
+        modifiers.insert(String::from("mandatory"), json!(mandatory));
+    }
+    if !optional.is_empty() {
+        modifiers.insert(String::from("optional"), json!(optional));
+    }
+
+    if modifiers.is_empty() {
+        None
+    } else {
+        Some(Value::Object(modifiers))
+    }
+}
+
+/// Replaces each `%s` in `template` with the next argument.
+fn format_template(template: &str, args: &[Edn]) -> String {
+    let mut args = args.iter().map(|arg| match arg {
+        Edn::String(s) => s.clone(),
+        arg => arg.to_string(),
+    });
+
+    let mut parts = template.split("%s");
+    let mut command = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        command.push_str(&args.next().unwrap_or_default());
+        command.push_str(part);
+    }
+
+    command
+}
+
+fn keyword(name: &str) -> Edn {
+    Edn::Keyword(name.to_string())
+}
+
+/// Names keys in sections like `:profiles` and `:layers` (usually keywords).
+fn key_name(edn: &Edn) -> String {
+    match edn {
+        Edn::Keyword(name) | Edn::Symbol(name) | Edn::String(name) => name.clone(),
+        edn => edn.to_string(),
+    }
+}
+
+/// Converts a value for the JSON config, keywords become strings.
+fn edn_to_json(edn: &Edn) -> Value {
+    match edn {
+        Edn::Nil => Value::Null,
+        Edn::Bool(b) => json!(b),
+        Edn::Int(i) => json!(i),
+        Edn::Float(f) => json!(f),
+        Edn::String(s) | Edn::Keyword(s) | Edn::Symbol(s) => json!(s),
+        Edn::Char(c) => json!(c.to_string()),
+        Edn::List(items) | Edn::Vector(items) | Edn::Set(items) => {
+            Value::Array(items.iter().map(edn_to_json).collect())
+        }
+        Edn::Map(entries) => Value::Object(
+            entries
+                .iter()
+                .map(|(k, v)| (key_name(k), edn_to_json(v)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_template, parse_key};
+    use crate::goku::Edn;
+    use pretty_assertions::assert_eq;
+
+    fn strings(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_modifier_prefixes() {
+        assert_eq!(parse_key("a"), Ok((String::from("a"), vec![], vec![])));
+        assert_eq!(
+            parse_key("!CSa"),
+            Ok((
+                String::from("a"),
+                strings(&["left_command", "left_shift"]),
+                vec![]
+            ))
+        );
+        assert_eq!(
+            parse_key("!T#Sleft_arrow"),
+            Ok((
+                String::from("left_arrow"),
+                strings(&["left_control"]),
+                strings(&["left_shift"])
+            ))
+        );
+        assert_eq!(
+            parse_key("##caps_lock"),
+            Ok((String::from("caps_lock"), vec![], strings(&["any"])))
+        );
+        assert_eq!(
+            parse_key("!!h"),
+            Ok((
+                String::from("h"),
+                strings(&["left_command", "left_control", "left_option", "left_shift"]),
+                vec![]
+            ))
+        );
+        assert_eq!(parse_key("!Xa"), Err(String::from("unknown modifier X")));
+        assert_eq!(parse_key("!C"), Err(String::from("missing key in :!C")));
+    }
+
+    #[test]
+    fn formats_templates() {
+        assert_eq!(
+            format_template(
+                "open -a '%s' --args %s",
+                &[Edn::String(String::from("Firefox")), Edn::Int(1)]
+            ),
+            "open -a 'Firefox' --args 1"
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use crate::error::Error;
+use crate::karabiner::KBComplexRule;
+use glob::Pattern;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs::{self, File};
+use std::path::Path;
+
+/// A file of complex modification rules, which Karabiner keeps in its
+/// `assets/complex_modifications` directory.
+///
+/// https://pqrs.org/osx/karabiner/json.html#typical-complex_modifications-examples
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KBAsset {
+    pub title: String,
+    pub rules: Vec<KBComplexRule>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl KBAsset {
+    pub fn from_path<T: AsRef<Path>>(path: T) -> Result<KBAsset, Error> {
+        let mut file = File::open(path)?;
+        Ok(serde_json::from_reader(&mut file)?)
+    }
+}
+
+/// Every asset file in a directory, sorted by file name.
+#[derive(Debug, Clone, Default)]
+pub struct KBAssets {
+    pub assets: Vec<KBAsset>,
+}
+
+impl KBAssets {
+    /// Loads every `.json` file in `dir`.
+    pub fn from_dir<T: AsRef<Path>>(dir: T) -> Result<KBAssets, Error> {
+        let dir = dir.as_ref();
+        let in_dir = |e| Error::File(dir.to_path_buf(), Box::new(Error::Io(e)));
+
+        let mut paths = vec![];
+        for entry in fs::read_dir(dir).map_err(in_dir)? {
+            let path = entry.map_err(in_dir)?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                paths.push(path);
+            }
+        }
+
+        paths.sort();
+        let assets = paths
+            .into_iter()
+            .map(|path| KBAsset::from_path(&path).map_err(|e| Error::File(path, Box::new(e))))
+            .collect::<Result<_, _>>()?;
+
+        Ok(KBAssets { assets })
+    }
+
+    /// A glob matching the asset files in `dir`.
+    pub fn glob<T: AsRef<Path>>(dir: T) -> String {
+        let dir = Pattern::escape(&dir.as_ref().to_string_lossy());
+        Path::new(&dir)
+            .join("*.json")
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Returns the title of every asset file.
+    pub fn titles(&self) -> Vec<&str> {
+        self.assets.iter().map(|a| a.title.as_str()).collect()
+    }
+
+    /// Returns every rule, along with the title of the file it's in.
+    pub fn rules(&self) -> Vec<(&str, &KBComplexRule)> {
+        self.assets
+            .iter()
+            .flat_map(|a| a.rules.iter().map(move |r| (a.title.as_str(), r)))
+            .collect()
+    }
+
+    pub fn find(&self, title: &str) -> Option<&KBAsset> {
+        self.assets.iter().find(|a| a.title == title)
+    }
+}
@@ -1,105 +1,496 @@
+use crate::error::{ConfigError, ConfigProblem, Error};
+use crate::goku;
+use crate::karabiner::{KBAssets, KBRuleSource, KeyCodeSchema, KeyKind, ModifierSchema};
+use crate::kmonad;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::Serialize as _;
 use serde_derive::{Deserialize, Serialize};
-use serde_json::Value;
-use std::collections::HashMap;
-use std::fs::File;
+use serde_json::ser::PrettyFormatter;
+use serde_json::{Map, Serializer, Value};
+use std::fs::{self, File};
+use std::io::{BufWriter, Read, Write};
 use std::path::Path;
 
 /// Karabiner configuration file.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Every config struct keeps the fields it doesn't model in `extra`, so a config
+/// can be saved again without losing anything.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct KBConfig {
     pub profiles: Vec<KBProfile>,
+    /// Not part of Karabiner: globs matching the files that were read for
+    /// `includes` and `assets`, so they can be watched for changes.
+    #[serde(skip)]
+    pub source_globs: Vec<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct KBProfile {
     pub name: String,
     pub selected: bool,
     pub simple_modifications: Vec<KBSimpleModification>,
     pub complex_modifications: KBComplexModifications,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fn_function_keys: Option<Vec<KBSimpleModification>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub devices: Option<Vec<KBDevice>>,
+    /// Not part of Karabiner: files (or globs) of rules which are appended to
+    /// `complex_modifications.rules` by `KBConfig::resolve_includes`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub includes: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Settings for the devices matching `identifiers`, which apply on top of the
+/// profile's settings.
+///
+/// https://pqrs.org/osx/karabiner/json.html#devices
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct KBDevice {
+    pub identifiers: KBDeviceIdentifiers,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ignore: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disable_built_in_keyboard_if_exists: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub simple_modifications: Option<Vec<KBSimpleModification>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fn_function_keys: Option<Vec<KBSimpleModification>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Identifiers that aren't set match any device.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct KBDeviceIdentifiers {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vendor_id: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub product_id: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_keyboard: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_pointing_device: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct KBSimpleModification {
     pub from: KBSimpleRule,
     pub to: KBSimpleRule,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Media keys are usually given as a `consumer_key_code`, eg: in `fn_function_keys`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct KBSimpleRule {
-    pub key_code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<KeyCodeSchema>")]
+    pub key_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<KeyCodeSchema>")]
+    pub consumer_key_code: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl KBSimpleRule {
+    pub fn key_code(&self) -> Option<&str> {
+        self.key_code
+            .as_ref()
+            .or(self.consumer_key_code.as_ref())
+            .map(String::as_str)
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct KBComplexModifications {
-    pub parameters: Option<HashMap<String, Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<KBParameters>,
     pub rules: Vec<KBComplexRule>,
+    /// Not part of Karabiner: titles of files in the assets directory, whose
+    /// rules are appended to `rules` by `KBConfig::resolve_assets`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assets: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct KBComplexRule {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Can be left out of rules which only have `includes`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub manipulators: Vec<KBManipulator>,
+    /// Not part of Karabiner: files (or globs) of rules which are inserted after
+    /// this one by `KBConfig::resolve_includes`, relative to the file it's in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub includes: Option<Vec<String>>,
+    /// Not part of Karabiner: the file this rule was included from.
+    #[serde(skip)]
+    pub source: Option<KBRuleSource>,
+    /// Not part of Karabiner: checks that the profile behaves as this rule intends,
+    /// which are run by `karabinux test`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tests: Option<Vec<KBRuleTest>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Not part of Karabiner: keys to press, and the keys the profile should send in
+/// response. Keys are written as a `key_code` followed by `↓` (pressed) or `↑`
+/// (released), eg: `caps_lock↓`, and the input can wait between them, eg: `300ms`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct KBRuleTest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub input: Vec<String>,
+    pub output: Vec<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct KBManipulator {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     #[serde(rename = "type")]
     pub kind: KBManipulatorKind,
     pub from: KBFromDefinition,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub to: Option<Vec<KBToDefinition>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_if_alone: Option<Vec<KBToDefinition>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_if_held_down: Option<Vec<KBToDefinition>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_after_key_up: Option<Vec<KBToDefinition>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub conditions: Option<Vec<KBCondition>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<KBParameters>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Timing parameters, which can be set for the whole profile (in
+/// `complex_modifications.parameters`) or for a single manipulator.
+///
+/// https://pqrs.org/osx/karabiner/json.html#complex_modifications-parameters
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct KBParameters {
+    #[serde(rename = "basic.to_if_alone_timeout_milliseconds")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_if_alone_timeout_milliseconds: Option<u64>,
+    #[serde(rename = "basic.to_if_held_down_threshold_milliseconds")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_if_held_down_threshold_milliseconds: Option<u64>,
+    /// Not supported by karabinux: there's no `to_delayed_action`, so this is ignored (and
+    /// left out of the schema). `karabinux lint` warns when it's set.
+    #[serde(rename = "basic.to_delayed_action_delay_milliseconds")]
+    #[schemars(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_delayed_action_delay_milliseconds: Option<u64>,
+    #[serde(rename = "basic.simultaneous_threshold_milliseconds")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub simultaneous_threshold_milliseconds: Option<u64>,
+    /// Not supported by karabinux: there's no `mouse_motion_to_scroll`, so this is ignored
+    /// (and left out of the schema). `karabinux lint` warns when it's set.
+    #[serde(rename = "mouse_motion_to_scroll.speed")]
+    #[schemars(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mouse_motion_to_scroll_speed: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum KBManipulatorKind {
     Basic,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct KBFromDefinition {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<KeyCodeSchema>")]
     pub key_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub any: Option<KeyKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub modifiers: Option<KBFromModifiers>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub simultaneous: Option<Vec<KBFromDefinition>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub simultaneous_options: Option<KBSimultaneousOptions>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct KBToDefinition {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<KeyCodeSchema>")]
     pub key_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<Vec<ModifierSchema>>")]
     pub modifiers: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub shell_command: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mouse_key: Option<KBMouseKey>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub repeat: Option<bool>,
+    /// Not part of Karabiner: emits the key that was matched by `from`, which
+    /// is useful alongside `from.any`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forward: Option<bool>,
+    /// Not part of Karabiner: switches to the profile with this name, like
+    /// `karabiner_cli --select-profile` does.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub select_profile: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub set_variable: Option<KBSetVariable>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// https://pqrs.org/osx/karabiner/json.html#to-event-definition-set-variable
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct KBSetVariable {
+    pub name: String,
+    pub value: Value,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct KBMouseKey {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vertical_wheel: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub horizontal_wheel: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speed_multiplier: Option<f64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct KBCondition {
     #[serde(rename = "type")]
     pub kind: KBConditionKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub bundle_identifiers: Option<Vec<String>>, // regexp
+    /// The variable's name, for `variable_if` and `variable_unless`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The variable's value, for `variable_if` and `variable_unless`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    /// The devices for `device_if` and `device_unless`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identifiers: Option<Vec<KBDeviceIdentifiers>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// https://pqrs.org/osx/karabiner/json.html#condition-definition
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum KBConditionKind {
     FrontmostApplicationIf,
     FrontmostApplicationUnless,
+    VariableIf,
+    VariableUnless,
+    DeviceIf,
+    DeviceUnless,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct KBFromModifiers {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<Vec<ModifierSchema>>")]
     pub mandatory: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<Vec<ModifierSchema>>")]
     pub optional: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// https://pqrs.org/osx/karabiner/json.html#simultaneous_options
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct KBSimultaneousOptions {
+    /// The order the keys must be pressed in, relative to `from.simultaneous`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_down_order: Option<KBKeyOrder>,
+    /// The order the keys must be released in for `to_after_key_up` to be sent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_up_order: Option<KBKeyOrder>,
+    /// Whether the `to` events are released when any of the keys are, or when all of them are.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_up_when: Option<KBKeyUpWhen>,
+    /// Sent once all of the keys are released.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub to_after_key_up: Option<Vec<KBToDefinition>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum KBKeyOrder {
+    Insensitive,
+    Strict,
+    StrictInverse,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum KBKeyUpWhen {
+    Any,
+    All,
+}
+
+/// Reads a file, parsed as YAML for `.yaml`/`.yml` files, TOML for `.toml` files,
+/// and JSON for anything else.
+pub(crate) fn deserialize_file<T: DeserializeOwned>(path: &Path) -> Result<T, Error> {
+    let mut file = File::open(path)?;
+    let extension = path.extension().and_then(|ext| ext.to_str());
+    match extension {
+        Some("yaml") | Some("yml") => Ok(serde_yaml::from_reader(&mut file)?),
+        Some("toml") => {
+            let mut contents = String::new();
+            file.read_to_string(&mut contents)?;
+            Ok(toml::from_str(&contents)?)
+        }
+        _ => Ok(serde_json::from_reader(&mut file)?),
+    }
 }
 
 impl KBConfig {
-    pub fn from_path<T: AsRef<Path>>(path: T) -> serde_json::Result<KBConfig> {
-        let mut file = File::open(path).expect("failed to open config file");
-        serde_json::from_reader(&mut file)
+    /// A config with just these profiles, eg: for profiles imported from other tools.
+    pub fn from_profiles(profiles: Vec<KBProfile>) -> KBConfig {
+        KBConfig {
+            profiles,
+            source_globs: vec![],
+            extra: Map::new(),
+        }
+    }
+
+    /// Reads a config file, parsed as YAML for `.yaml`/`.yml` files, TOML for
+    /// `.toml` files, a Goku config for `.edn` files, a KMonad keymap for `.kbd`
+    /// files, and JSON for anything else.
+    pub fn from_path<T: AsRef<Path>>(path: T) -> Result<KBConfig, Error> {
+        let path = path.as_ref();
+
+        // Not part of Karabiner: configs can also be written in YAML, TOML, Goku's EDN
+        // or KMonad's `.kbd` format.
+        let extension = path.extension().and_then(|ext| ext.to_str());
+        match extension {
+            Some("edn") => goku::config_from_str(&fs::read_to_string(path)?),
+            Some("kbd") => kmonad::config_from_str(&fs::read_to_string(path)?),
+            _ => deserialize_file(path),
+        }
+    }
+
+    /// Reads a config file, along with any files it includes and the assets its
+    /// profiles use, which are in `assets_dir` (or `assets/complex_modifications`
+    /// next to the config if it isn't given).
+    pub fn load<T: AsRef<Path>>(path: T, assets_dir: Option<&Path>) -> Result<KBConfig, Error> {
+        let path = path.as_ref();
+        let mut kb_config = KBConfig::from_path(path)?;
+        kb_config.resolve_includes(path)?;
+
+        let uses_assets = kb_config
+            .profiles
+            .iter()
+            .any(|p| p.complex_modifications.assets.is_some());
+        if uses_assets {
+            let dir = match assets_dir {
+                Some(dir) => dir.to_path_buf(),
+                None => path.with_file_name("assets").join("complex_modifications"),
+            };
+
+            let assets = KBAssets::from_dir(&dir)?;
+            kb_config.resolve_assets(&assets)?;
+            kb_config.source_globs.push(KBAssets::glob(&dir));
+        }
+
+        Ok(kb_config)
+    }
+
+    /// Writes the config as JSON, indented like Karabiner's own config files.
+    pub fn to_writer<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let formatter = PrettyFormatter::with_indent(b"    ");
+        let mut serializer = Serializer::with_formatter(writer, formatter);
+        self.serialize(&mut serializer)?;
+        Ok(())
+    }
+
+    /// Writes the config to the file at `path`, replacing it if it exists.
+    pub fn save<T: AsRef<Path>>(&self, path: T) -> Result<(), Error> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.to_writer(&mut file)?;
+        file.write_all(b"\n")?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Appends the rules from `assets` that each profile references by title.
+    /// Nothing is changed if any of the titles can't be found.
+    pub fn resolve_assets(&mut self, assets: &KBAssets) -> Result<(), Error> {
+        let mut errors = vec![];
+        for profile in &self.profiles {
+            let titles = profile.complex_modifications.assets.iter().flatten();
+            for (i, title) in titles.enumerate() {
+                if assets.find(title).is_none() {
+                    errors.push(ConfigError {
+                        profile: Some(profile.name.clone()),
+                        ..ConfigError::new(
+                            format!("complex_modifications.assets[{}]", i),
+                            ConfigProblem::UnknownAsset(title.clone()),
+                        )
+                    });
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(Error::Config(errors));
+        }
+
+        for profile in &mut self.profiles {
+            let complex_modifications = &mut profile.complex_modifications;
+            for title in complex_modifications.assets.take().into_iter().flatten() {
+                let asset = assets.find(&title).expect("asset was checked above");
+                complex_modifications
+                    .rules
+                    .extend(asset.rules.iter().cloned());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finds the profile with the given name, or the selected profile if no name
+    /// is given.
+    pub fn find_profile(&self, name: Option<&str>) -> Option<&KBProfile> {
+        match name {
+            Some(name) => self.profiles.iter().find(|p| p.name == name),
+            None => self.profiles.iter().find(|p| p.selected),
+        }
     }
 }
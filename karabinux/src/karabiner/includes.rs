@@ -0,0 +1,179 @@
+use crate::error::{ConfigError, ConfigProblem, Error};
+use crate::karabiner::{deserialize_file, KBComplexRule, KBConfig};
+use glob::Pattern;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Not part of Karabiner: a file of rules included by a profile or rule. This has
+/// the same structure as an asset file, so those can be included too.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KBIncludeFile {
+    #[serde(default)]
+    pub rules: Vec<KBComplexRule>,
+    /// Files (or globs) of rules which are appended to `rules`, relative to this file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub includes: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Where an included rule came from, so problems with it can be reported there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KBRuleSource {
+    pub path: PathBuf,
+    /// The index of the rule in the file's `rules`.
+    pub index: usize,
+}
+
+impl KBConfig {
+    /// Replaces the `includes` of each profile and rule with the rules from the
+    /// files they name, which are found relative to `config_path` (or the included
+    /// file they're in). Globs match files in order of their paths.
+    ///
+    /// Included rules come after the rules that include them, so a profile's own
+    /// rules take precedence over the rules it includes. The globs are added to
+    /// `source_globs`.
+    pub fn resolve_includes<T: AsRef<Path>>(&mut self, config_path: T) -> Result<(), Error> {
+        let config_path = config_path.as_ref();
+        let mut globs = vec![];
+        for profile in &mut self.profiles {
+            let mut resolver = Resolver {
+                config_path,
+                profile: &profile.name,
+                stack: vec![canonical_path(config_path)?],
+                globs: &mut globs,
+            };
+
+            let rules = std::mem::take(&mut profile.complex_modifications.rules);
+            let mut rules =
+                resolver.expand_rules(rules, config_path, "complex_modifications.", false)?;
+            for (i, pattern) in profile.includes.take().iter().flatten().enumerate() {
+                rules.extend(resolver.include(pattern, config_path, format!("includes[{}]", i))?);
+            }
+
+            profile.complex_modifications.rules = rules;
+        }
+
+        for glob in globs {
+            if !self.source_globs.contains(&glob) {
+                self.source_globs.push(glob);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct Resolver<'a> {
+    config_path: &'a Path,
+    profile: &'a str,
+    /// The canonical paths of the files being included, to detect cycles.
+    stack: Vec<PathBuf>,
+    /// The globs of every file included so far.
+    globs: &'a mut Vec<String>,
+}
+
+impl<'a> Resolver<'a> {
+    /// Returns `rules` with the rules they include inserted after each of them.
+    fn expand_rules(
+        &mut self,
+        rules: Vec<KBComplexRule>,
+        file: &Path,
+        prefix: &str,
+        is_included: bool,
+    ) -> Result<Vec<KBComplexRule>, Error> {
+        let mut expanded = vec![];
+        for (i, mut rule) in rules.into_iter().enumerate() {
+            if is_included {
+                rule.source = Some(KBRuleSource {
+                    path: file.to_path_buf(),
+                    index: i,
+                });
+            }
+
+            // Rules that only include other rules are replaced by them.
+            let includes = rule.includes.take();
+            if includes.is_none() || !rule.manipulators.is_empty() {
+                expanded.push(rule);
+            }
+
+            for (j, pattern) in includes.iter().flatten().enumerate() {
+                let path = format!("{}rules[{}].includes[{}]", prefix, i, j);
+                expanded.extend(self.include(pattern, file, path)?);
+            }
+        }
+
+        Ok(expanded)
+    }
+
+    /// Returns the rules from every file matching `pattern`, which was found at
+    /// `path` in `file`.
+    fn include(
+        &mut self,
+        pattern: &str,
+        file: &Path,
+        path: String,
+    ) -> Result<Vec<KBComplexRule>, Error> {
+        let dir = file.parent().unwrap_or_else(|| Path::new(""));
+        let full_pattern = Path::new(&Pattern::escape(&dir.to_string_lossy())).join(pattern);
+
+        let full_pattern = full_pattern.to_string_lossy().into_owned();
+        let mut included_paths = match glob::glob(&full_pattern) {
+            Ok(paths) => paths
+                .filter_map(Result::ok)
+                .filter(|p| p.is_file())
+                .collect::<Vec<_>>(),
+            Err(_) => vec![],
+        };
+        included_paths.sort();
+        self.globs.push(full_pattern);
+        if included_paths.is_empty() {
+            let problem = ConfigProblem::UnknownInclude(pattern.to_string());
+            return Err(self.error(file, path, problem));
+        }
+
+        let mut rules = vec![];
+        for included_path in included_paths {
+            let canonical = canonical_path(&included_path)?;
+            if let Some(start) = self.stack.iter().position(|p| *p == canonical) {
+                let mut cycle = self.stack[start..].to_vec();
+                cycle.push(canonical);
+                return Err(self.error(file, path, ConfigProblem::IncludeCycle(cycle)));
+            }
+
+            let in_file = |e| Error::File(included_path.clone(), Box::new(e));
+            let include_file: KBIncludeFile = deserialize_file(&included_path).map_err(in_file)?;
+
+            self.stack.push(canonical);
+            rules.extend(self.expand_rules(include_file.rules, &included_path, "", true)?);
+            for (i, pattern) in include_file.includes.iter().flatten().enumerate() {
+                rules.extend(self.include(pattern, &included_path, format!("includes[{}]", i))?);
+            }
+            self.stack.pop();
+        }
+
+        Ok(rules)
+    }
+
+    /// Problems in the config file are reported like other config errors, and
+    /// problems in included files are reported in the file they came from.
+    fn error(&self, file: &Path, path: String, problem: ConfigProblem) -> Error {
+        let file = if file == self.config_path {
+            None
+        } else {
+            Some(file.to_path_buf())
+        };
+
+        Error::Config(vec![ConfigError {
+            profile: Some(self.profile.to_string()),
+            file,
+            ..ConfigError::new(path, problem)
+        }])
+    }
+}
+
+fn canonical_path(path: &Path) -> Result<PathBuf, Error> {
+    fs::canonicalize(path).map_err(|e| Error::File(path.to_path_buf(), Box::new(Error::Io(e))))
+}
@@ -1,4 +1,13 @@
-use evdev_rs::enums::EV_KEY;
+use evdev_rs::enums::{int_to_ev_key, EventCode, EventType, EV_KEY};
+
+/// Karabiner's `vk_none` key, which means "emit nothing".
+/// This is never sent by the kernel, so it's safe to reuse `KEY_RESERVED` for it.
+pub const VK_NONE: EV_KEY = EV_KEY::KEY_RESERVED;
+
+/// Prefix for keys given by their Linux name or code rather than a Karabiner
+/// name, eg: `linux:KEY_PROG1` or `linux:0x1d2`. This allows any key the
+/// kernel knows about to be used, even if Karabiner has no name for it.
+pub const LINUX_KEY_CODE_PREFIX: &str = "linux:";
 
 pub trait FromKBKeyCode {
     fn from_kb_key_code(key_code: &str) -> Option<Self>
@@ -6,221 +15,362 @@ pub trait FromKBKeyCode {
         Self: Sized;
 }
 
+pub trait ToKBKeyCode {
+    fn to_kb_key_code(&self) -> Option<&'static str>;
+}
+
+/// Every Karabiner `key_code` and the Linux key it maps to. Where several names
+/// map to the same key, the first one is the name used for reverse lookups.
+///
+/// Karabiner keys without a Linux equivalent are left out, so configs using
+/// them are rejected as unknown key codes: `international7` to `international9`,
+/// `lang6` to `lang9`, `prior`, `return`, `separator`, `out`, `oper`,
+/// `clear_or_again` and `ex_sel`.
+///
+/// https://github.com/tekezo/Karabiner-Elements/blob/master/src/share/types.hpp
+pub const KB_KEY_CODES: &[(&str, EV_KEY)] = &[
+    ("caps_lock", EV_KEY::KEY_CAPSLOCK),
+    ("left_control", EV_KEY::KEY_LEFTCTRL),
+    ("left_shift", EV_KEY::KEY_LEFTSHIFT),
+    ("left_option", EV_KEY::KEY_LEFTALT),   // Alias for alt
+    ("left_command", EV_KEY::KEY_LEFTMETA), // Alias for meta
+    ("right_control", EV_KEY::KEY_RIGHTCTRL),
+    ("right_shift", EV_KEY::KEY_RIGHTSHIFT),
+    ("right_option", EV_KEY::KEY_RIGHTALT),   // Alias for alt
+    ("right_command", EV_KEY::KEY_RIGHTMETA), // Alias for meta
+    ("fn", EV_KEY::KEY_FN),
+    ("return_or_enter", EV_KEY::KEY_ENTER),
+    ("escape", EV_KEY::KEY_ESC),
+    ("delete_or_backspace", EV_KEY::KEY_BACKSPACE),
+    ("delete_forward", EV_KEY::KEY_DELETE),
+    ("tab", EV_KEY::KEY_TAB),
+    ("spacebar", EV_KEY::KEY_SPACE),
+    ("hyphen", EV_KEY::KEY_MINUS),
+    ("equal_sign", EV_KEY::KEY_EQUAL),
+    ("open_bracket", EV_KEY::KEY_LEFTBRACE),
+    ("close_bracket", EV_KEY::KEY_RIGHTBRACE),
+    ("backslash", EV_KEY::KEY_BACKSLASH),
+    ("non_us_pound", EV_KEY::KEY_BACKSLASH), // Linux reports this as backslash
+    ("semicolon", EV_KEY::KEY_SEMICOLON),
+    ("quote", EV_KEY::KEY_APOSTROPHE),
+    ("grave_accent_and_tilde", EV_KEY::KEY_GRAVE),
+    ("comma", EV_KEY::KEY_COMMA),
+    ("period", EV_KEY::KEY_DOT),
+    ("slash", EV_KEY::KEY_SLASH),
+    ("non_us_backslash", EV_KEY::KEY_102ND),
+    ("up_arrow", EV_KEY::KEY_UP),
+    ("down_arrow", EV_KEY::KEY_DOWN),
+    ("left_arrow", EV_KEY::KEY_LEFT),
+    ("right_arrow", EV_KEY::KEY_RIGHT),
+    ("page_up", EV_KEY::KEY_PAGEUP),
+    ("page_down", EV_KEY::KEY_PAGEDOWN),
+    ("home", EV_KEY::KEY_HOME),
+    ("end", EV_KEY::KEY_END),
+    ("a", EV_KEY::KEY_A),
+    ("b", EV_KEY::KEY_B),
+    ("c", EV_KEY::KEY_C),
+    ("d", EV_KEY::KEY_D),
+    ("e", EV_KEY::KEY_E),
+    ("f", EV_KEY::KEY_F),
+    ("g", EV_KEY::KEY_G),
+    ("h", EV_KEY::KEY_H),
+    ("i", EV_KEY::KEY_I),
+    ("j", EV_KEY::KEY_J),
+    ("k", EV_KEY::KEY_K),
+    ("l", EV_KEY::KEY_L),
+    ("m", EV_KEY::KEY_M),
+    ("n", EV_KEY::KEY_N),
+    ("o", EV_KEY::KEY_O),
+    ("p", EV_KEY::KEY_P),
+    ("q", EV_KEY::KEY_Q),
+    ("r", EV_KEY::KEY_R),
+    ("s", EV_KEY::KEY_S),
+    ("t", EV_KEY::KEY_T),
+    ("u", EV_KEY::KEY_U),
+    ("v", EV_KEY::KEY_V),
+    ("w", EV_KEY::KEY_W),
+    ("x", EV_KEY::KEY_X),
+    ("y", EV_KEY::KEY_Y),
+    ("z", EV_KEY::KEY_Z),
+    ("1", EV_KEY::KEY_1),
+    ("2", EV_KEY::KEY_2),
+    ("3", EV_KEY::KEY_3),
+    ("4", EV_KEY::KEY_4),
+    ("5", EV_KEY::KEY_5),
+    ("6", EV_KEY::KEY_6),
+    ("7", EV_KEY::KEY_7),
+    ("8", EV_KEY::KEY_8),
+    ("9", EV_KEY::KEY_9),
+    ("0", EV_KEY::KEY_0),
+    ("f1", EV_KEY::KEY_F1),
+    ("f2", EV_KEY::KEY_F2),
+    ("f3", EV_KEY::KEY_F3),
+    ("f4", EV_KEY::KEY_F4),
+    ("f5", EV_KEY::KEY_F5),
+    ("f6", EV_KEY::KEY_F6),
+    ("f7", EV_KEY::KEY_F7),
+    ("f8", EV_KEY::KEY_F8),
+    ("f9", EV_KEY::KEY_F9),
+    ("f10", EV_KEY::KEY_F10),
+    ("f11", EV_KEY::KEY_F11),
+    ("f12", EV_KEY::KEY_F12),
+    ("f13", EV_KEY::KEY_F13),
+    ("f14", EV_KEY::KEY_F14),
+    ("f15", EV_KEY::KEY_F15),
+    ("f16", EV_KEY::KEY_F16),
+    ("f17", EV_KEY::KEY_F17),
+    ("f18", EV_KEY::KEY_F18),
+    ("f19", EV_KEY::KEY_F19),
+    ("f20", EV_KEY::KEY_F20),
+    ("f21", EV_KEY::KEY_F21),
+    ("f22", EV_KEY::KEY_F22),
+    ("f23", EV_KEY::KEY_F23),
+    ("f24", EV_KEY::KEY_F24),
+    ("display_brightness_decrement", EV_KEY::KEY_BRIGHTNESSDOWN),
+    ("display_brightness_increment", EV_KEY::KEY_BRIGHTNESSUP),
+    ("mission_control", EV_KEY::KEY_SCALE),
+    ("launchpad", EV_KEY::KEY_DASHBOARD),
+    ("dashboard", EV_KEY::KEY_DASHBOARD),
+    ("illumination_decrement", EV_KEY::KEY_KBDILLUMDOWN),
+    ("illumination_increment", EV_KEY::KEY_KBDILLUMUP),
+    ("rewind", EV_KEY::KEY_REWIND),
+    ("play_or_pause", EV_KEY::KEY_PLAYPAUSE),
+    ("fastforward", EV_KEY::KEY_FASTFORWARD),
+    ("mute", EV_KEY::KEY_MUTE),
+    ("volume_decrement", EV_KEY::KEY_VOLUMEDOWN),
+    ("volume_increment", EV_KEY::KEY_VOLUMEUP),
+    ("eject", EV_KEY::KEY_EJECTCD),
+    (
+        "apple_display_brightness_decrement",
+        EV_KEY::KEY_BRIGHTNESSDOWN,
+    ),
+    (
+        "apple_display_brightness_increment",
+        EV_KEY::KEY_BRIGHTNESSUP,
+    ),
+    (
+        "apple_top_case_display_brightness_decrement",
+        EV_KEY::KEY_BRIGHTNESSDOWN,
+    ),
+    (
+        "apple_top_case_display_brightness_increment",
+        EV_KEY::KEY_BRIGHTNESSUP,
+    ),
+    ("keypad_num_lock", EV_KEY::KEY_NUMLOCK),
+    ("keypad_slash", EV_KEY::KEY_KPSLASH),
+    ("keypad_asterisk", EV_KEY::KEY_KPASTERISK),
+    ("keypad_hyphen", EV_KEY::KEY_KPMINUS),
+    ("keypad_plus", EV_KEY::KEY_KPPLUS),
+    ("keypad_enter", EV_KEY::KEY_KPENTER),
+    ("keypad_1", EV_KEY::KEY_KP1),
+    ("keypad_2", EV_KEY::KEY_KP2),
+    ("keypad_3", EV_KEY::KEY_KP3),
+    ("keypad_4", EV_KEY::KEY_KP4),
+    ("keypad_5", EV_KEY::KEY_KP5),
+    ("keypad_6", EV_KEY::KEY_KP6),
+    ("keypad_7", EV_KEY::KEY_KP7),
+    ("keypad_8", EV_KEY::KEY_KP8),
+    ("keypad_9", EV_KEY::KEY_KP9),
+    ("keypad_0", EV_KEY::KEY_KP0),
+    ("keypad_period", EV_KEY::KEY_KPDOT),
+    ("keypad_equal_sign", EV_KEY::KEY_KPEQUAL),
+    ("keypad_comma", EV_KEY::KEY_KPCOMMA),
+    ("vk_none", VK_NONE),
+    ("print_screen", EV_KEY::KEY_PRINT),
+    ("scroll_lock", EV_KEY::KEY_SCROLLLOCK),
+    ("pause", EV_KEY::KEY_PAUSE),
+    ("insert", EV_KEY::KEY_INSERT),
+    ("application", EV_KEY::KEY_COMPOSE),
+    ("help", EV_KEY::KEY_HELP),
+    ("power", EV_KEY::KEY_POWER),
+    ("execute", EV_KEY::KEY_OPEN),
+    ("menu", EV_KEY::KEY_MENU),
+    ("select", EV_KEY::KEY_SELECT),
+    ("stop", EV_KEY::KEY_STOP),
+    ("again", EV_KEY::KEY_AGAIN),
+    ("undo", EV_KEY::KEY_UNDO),
+    ("cut", EV_KEY::KEY_CUT),
+    ("copy", EV_KEY::KEY_COPY),
+    ("paste", EV_KEY::KEY_PASTE),
+    ("find", EV_KEY::KEY_FIND),
+    ("international1", EV_KEY::KEY_RO),
+    ("international2", EV_KEY::KEY_KATAKANAHIRAGANA),
+    ("international3", EV_KEY::KEY_YEN),
+    ("international4", EV_KEY::KEY_HENKAN),
+    ("international5", EV_KEY::KEY_MUHENKAN),
+    ("international6", EV_KEY::KEY_KPJPCOMMA),
+    ("lang1", EV_KEY::KEY_HANGEUL),
+    ("lang2", EV_KEY::KEY_HANJA),
+    ("lang3", EV_KEY::KEY_KATAKANA),
+    ("lang4", EV_KEY::KEY_HIRAGANA),
+    ("lang5", EV_KEY::KEY_ZENKAKUHANKAKU),
+    ("japanese_eisuu", EV_KEY::KEY_HANJA),  // Alias for lang2
+    ("japanese_kana", EV_KEY::KEY_HANGEUL), // Alias for lang1
+    ("japanese_pc_nfer", EV_KEY::KEY_MUHENKAN), // Alias for international5
+    ("japanese_pc_xfer", EV_KEY::KEY_HENKAN), // Alias for international4
+    ("japanese_pc_katakana", EV_KEY::KEY_KATAKANAHIRAGANA), // Alias for international2
+    ("keypad_equal_sign_as400", EV_KEY::KEY_KPEQUAL),
+    ("locking_caps_lock", EV_KEY::KEY_CAPSLOCK),
+    ("locking_num_lock", EV_KEY::KEY_NUMLOCK),
+    ("locking_scroll_lock", EV_KEY::KEY_SCROLLLOCK),
+    ("alternate_erase", EV_KEY::KEY_ALTERASE),
+    ("sys_req_or_attention", EV_KEY::KEY_SYSRQ),
+    ("cancel", EV_KEY::KEY_CANCEL),
+    ("clear", EV_KEY::KEY_CLEAR),
+    ("cr_sel_or_props", EV_KEY::KEY_PROPS),
+    ("left_alt", EV_KEY::KEY_LEFTALT),
+    ("left_gui", EV_KEY::KEY_LEFTMETA), // Alias for meta
+    ("right_alt", EV_KEY::KEY_RIGHTALT),
+    ("right_gui", EV_KEY::KEY_RIGHTMETA), // Alias for meta
+    ("vk_consumer_brightness_down", EV_KEY::KEY_BRIGHTNESSDOWN),
+    ("vk_consumer_brightness_up", EV_KEY::KEY_BRIGHTNESSUP),
+    ("vk_mission_control", EV_KEY::KEY_SCALE),
+    ("vk_launchpad", EV_KEY::KEY_DASHBOARD),
+    ("vk_dashboard", EV_KEY::KEY_DASHBOARD),
+    ("vk_consumer_illumination_down", EV_KEY::KEY_KBDILLUMDOWN),
+    ("vk_consumer_illumination_up", EV_KEY::KEY_KBDILLUMUP),
+    ("vk_consumer_previous", EV_KEY::KEY_PREVIOUSSONG),
+    ("vk_consumer_play", EV_KEY::KEY_PLAYPAUSE),
+    ("vk_consumer_next", EV_KEY::KEY_NEXTSONG),
+    ("volume_down", EV_KEY::KEY_VOLUMEDOWN),
+    ("volume_up", EV_KEY::KEY_VOLUMEUP),
+];
+
 impl FromKBKeyCode for EV_KEY {
     fn from_kb_key_code(key_code: &str) -> Option<EV_KEY> {
-        match key_code {
-            "caps_lock" => Some(EV_KEY::KEY_CAPSLOCK),
-            "left_control" => Some(EV_KEY::KEY_LEFTCTRL),
-            "left_shift" => Some(EV_KEY::KEY_LEFTSHIFT),
-            "left_option" => Some(EV_KEY::KEY_LEFTALT), // Alias for alt
-            "left_command" => Some(EV_KEY::KEY_LEFTMETA), // Alias for meta
-            "right_control" => Some(EV_KEY::KEY_RIGHTCTRL),
-            "right_shift" => Some(EV_KEY::KEY_RIGHTSHIFT),
-            "right_option" => Some(EV_KEY::KEY_RIGHTALT), // Alias for alt
-            "right_command" => Some(EV_KEY::KEY_RIGHTMETA), // Alias for meta
-            "fn" => Some(EV_KEY::KEY_FN),
-            "return_or_enter" => Some(EV_KEY::KEY_ENTER),
-            "escape" => Some(EV_KEY::KEY_ESC),
-            "delete_or_backspace" => Some(EV_KEY::KEY_BACKSPACE),
-            "delete_forward" => Some(EV_KEY::KEY_DELETE),
-            "tab" => Some(EV_KEY::KEY_TAB),
-            "spacebar" => Some(EV_KEY::KEY_SPACE),
-            "hyphen" => Some(EV_KEY::KEY_MINUS),
-            "equal_sign" => Some(EV_KEY::KEY_EQUAL),
-            "open_bracket" => Some(EV_KEY::KEY_LEFTBRACE),
-            "close_bracket" => Some(EV_KEY::KEY_RIGHTBRACE),
-            "backslash" => Some(EV_KEY::KEY_BACKSLASH),
-            // "non_us_pound" => Some(EV_KEY::KEY),
-            "semicolon" => Some(EV_KEY::KEY_SEMICOLON),
-            "quote" => Some(EV_KEY::KEY_APOSTROPHE),
-            "grave_accent_and_tilde" => Some(EV_KEY::KEY_GRAVE),
-            "comma" => Some(EV_KEY::KEY_COMMA),
-            "period" => Some(EV_KEY::KEY_DOT),
-            "slash" => Some(EV_KEY::KEY_SLASH),
-            // "non_us_backslash" => Some(EV_KEY::KEY),
-            "up_arrow" => Some(EV_KEY::KEY_UP),
-            "down_arrow" => Some(EV_KEY::KEY_DOWN),
-            "left_arrow" => Some(EV_KEY::KEY_LEFT),
-            "right_arrow" => Some(EV_KEY::KEY_RIGHT),
-            "page_up" => Some(EV_KEY::KEY_PAGEUP),
-            "page_down" => Some(EV_KEY::KEY_PAGEDOWN),
-            "home" => Some(EV_KEY::KEY_HOME),
-            "end" => Some(EV_KEY::KEY_END),
-            "a" => Some(EV_KEY::KEY_A),
-            "b" => Some(EV_KEY::KEY_B),
-            "c" => Some(EV_KEY::KEY_C),
-            "d" => Some(EV_KEY::KEY_D),
-            "e" => Some(EV_KEY::KEY_E),
-            "f" => Some(EV_KEY::KEY_F),
-            "g" => Some(EV_KEY::KEY_G),
-            "h" => Some(EV_KEY::KEY_H),
-            "i" => Some(EV_KEY::KEY_I),
-            "j" => Some(EV_KEY::KEY_J),
-            "k" => Some(EV_KEY::KEY_K),
-            "l" => Some(EV_KEY::KEY_L),
-            "m" => Some(EV_KEY::KEY_M),
-            "n" => Some(EV_KEY::KEY_N),
-            "o" => Some(EV_KEY::KEY_O),
-            "p" => Some(EV_KEY::KEY_P),
-            "q" => Some(EV_KEY::KEY_Q),
-            "r" => Some(EV_KEY::KEY_R),
-            "s" => Some(EV_KEY::KEY_S),
-            "t" => Some(EV_KEY::KEY_T),
-            "u" => Some(EV_KEY::KEY_U),
-            "v" => Some(EV_KEY::KEY_V),
-            "w" => Some(EV_KEY::KEY_W),
-            "x" => Some(EV_KEY::KEY_X),
-            "y" => Some(EV_KEY::KEY_Y),
-            "z" => Some(EV_KEY::KEY_Z),
-            "1" => Some(EV_KEY::KEY_1),
-            "2" => Some(EV_KEY::KEY_2),
-            "3" => Some(EV_KEY::KEY_3),
-            "4" => Some(EV_KEY::KEY_4),
-            "5" => Some(EV_KEY::KEY_5),
-            "6" => Some(EV_KEY::KEY_6),
-            "7" => Some(EV_KEY::KEY_7),
-            "8" => Some(EV_KEY::KEY_8),
-            "9" => Some(EV_KEY::KEY_9),
-            "0" => Some(EV_KEY::KEY_0),
-            "f1" => Some(EV_KEY::KEY_F1),
-            "f2" => Some(EV_KEY::KEY_F2),
-            "f3" => Some(EV_KEY::KEY_F3),
-            "f4" => Some(EV_KEY::KEY_F4),
-            "f5" => Some(EV_KEY::KEY_F5),
-            "f6" => Some(EV_KEY::KEY_F6),
-            "f7" => Some(EV_KEY::KEY_F7),
-            "f8" => Some(EV_KEY::KEY_F8),
-            "f9" => Some(EV_KEY::KEY_F9),
-            "f10" => Some(EV_KEY::KEY_F10),
-            "f11" => Some(EV_KEY::KEY_F11),
-            "f12" => Some(EV_KEY::KEY_F12),
-            "f13" => Some(EV_KEY::KEY_F13),
-            "f14" => Some(EV_KEY::KEY_F14),
-            "f15" => Some(EV_KEY::KEY_F15),
-            "f16" => Some(EV_KEY::KEY_F16),
-            "f17" => Some(EV_KEY::KEY_F17),
-            "f18" => Some(EV_KEY::KEY_F18),
-            "f19" => Some(EV_KEY::KEY_F19),
-            "f20" => Some(EV_KEY::KEY_F20),
-            "f21" => Some(EV_KEY::KEY_F21),
-            "f22" => Some(EV_KEY::KEY_F22),
-            "f23" => Some(EV_KEY::KEY_F23),
-            "f24" => Some(EV_KEY::KEY_F24),
-            "display_brightness_decrement" => Some(EV_KEY::KEY_BRIGHTNESSDOWN),
-            "display_brightness_increment" => Some(EV_KEY::KEY_BRIGHTNESSUP),
-            // "mission_control" => Some(EV_KEY::KEY),
-            // "launchpad" => Some(EV_KEY::KEY),
-            // "dashboard" => Some(EV_KEY::KEY),
-            "illumination_decrement" => Some(EV_KEY::KEY_VOLUMEDOWN),
-            "illumination_increment" => Some(EV_KEY::KEY_VOLUMEUP),
-            "rewind" => Some(EV_KEY::KEY_REWIND),
-            "play_or_pause" => Some(EV_KEY::KEY_PLAYPAUSE),
-            "fastforward" => Some(EV_KEY::KEY_FASTFORWARD),
-            "mute" => Some(EV_KEY::KEY_MUTE),
-            "volume_decrement" => Some(EV_KEY::KEY_VOLUMEDOWN),
-            "volume_increment" => Some(EV_KEY::KEY_VOLUMEUP),
-            "eject" => Some(EV_KEY::KEY_EJECTCD),
-            // "apple_display_brightness_decrement" => Some(EV_KEY::KEY),
-            // "apple_display_brightness_increment" => Some(EV_KEY::KEY),
-            // "apple_top_case_display_brightness_decrement" => Some(EV_KEY::KEY),
-            // "apple_top_case_display_brightness_increment" => Some(EV_KEY::KEY),
-            "keypad_num_lock" => Some(EV_KEY::KEY_NUMLOCK),
-            "keypad_slash" => Some(EV_KEY::KEY_KPSLASH),
-            "keypad_asterisk" => Some(EV_KEY::KEY_KPASTERISK),
-            "keypad_hyphen" => Some(EV_KEY::KEY_KPMINUS),
-            "keypad_plus" => Some(EV_KEY::KEY_KPPLUS),
-            "keypad_enter" => Some(EV_KEY::KEY_KPENTER),
-            "keypad_1" => Some(EV_KEY::KEY_KP1),
-            "keypad_2" => Some(EV_KEY::KEY_KP2),
-            "keypad_3" => Some(EV_KEY::KEY_KP3),
-            "keypad_4" => Some(EV_KEY::KEY_KP4),
-            "keypad_5" => Some(EV_KEY::KEY_KP5),
-            "keypad_6" => Some(EV_KEY::KEY_KP6),
-            "keypad_7" => Some(EV_KEY::KEY_KP7),
-            "keypad_8" => Some(EV_KEY::KEY_KP8),
-            "keypad_9" => Some(EV_KEY::KEY_KP9),
-            "keypad_0" => Some(EV_KEY::KEY_KP0),
-            "keypad_period" => Some(EV_KEY::KEY_KPDOT),
-            "keypad_equal_sign" => Some(EV_KEY::KEY_KPEQUAL),
-            "keypad_comma" => Some(EV_KEY::KEY_KPCOMMA),
-            // "vk_none" => Some(EV_KEY::KEY),
-            "print_screen" => Some(EV_KEY::KEY_PRINT),
-            "scroll_lock" => Some(EV_KEY::KEY_SCROLLLOCK),
-            "pause" => Some(EV_KEY::KEY_PAUSE),
-            "insert" => Some(EV_KEY::KEY_INSERT),
-            // "application" => Some(EV_KEY::KEY),
-            "help" => Some(EV_KEY::KEY_HELP),
-            "power" => Some(EV_KEY::KEY_POWER),
-            // "execute" => Some(EV_KEY::KEY),
-            "menu" => Some(EV_KEY::KEY_MENU),
-            "select" => Some(EV_KEY::KEY_SELECT),
-            "stop" => Some(EV_KEY::KEY_STOP),
-            "again" => Some(EV_KEY::KEY_AGAIN),
-            "undo" => Some(EV_KEY::KEY_UNDO),
-            "cut" => Some(EV_KEY::KEY_CUT),
-            "copy" => Some(EV_KEY::KEY_COPY),
-            "paste" => Some(EV_KEY::KEY_PASTE),
-            "find" => Some(EV_KEY::KEY_FIND),
-            // "international1" => Some(EV_KEY::KEY),
-            // "international2" => Some(EV_KEY::KEY),
-            // "international3" => Some(EV_KEY::KEY),
-            // "international4" => Some(EV_KEY::KEY),
-            // "international5" => Some(EV_KEY::KEY),
-            // "international6" => Some(EV_KEY::KEY),
-            // "international7" => Some(EV_KEY::KEY),
-            // "international8" => Some(EV_KEY::KEY),
-            // "international9" => Some(EV_KEY::KEY),
-            // "lang1" => Some(EV_KEY::KEY),
-            // "lang2" => Some(EV_KEY::KEY),
-            // "lang3" => Some(EV_KEY::KEY),
-            // "lang4" => Some(EV_KEY::KEY),
-            // "lang5" => Some(EV_KEY::KEY),
-            // "lang6" => Some(EV_KEY::KEY),
-            // "lang7" => Some(EV_KEY::KEY),
-            // "lang8" => Some(EV_KEY::KEY),
-            // "lang9" => Some(EV_KEY::KEY),
-            // "japanese_eisuu" => Some(EV_KEY::KEY),
-            // "japanese_kana" => Some(EV_KEY::KEY),
-            // "japanese_pc_nfer" => Some(EV_KEY::KEY),
-            // "japanese_pc_xfer" => Some(EV_KEY::KEY),
-            // "japanese_pc_katakana" => Some(EV_KEY::KEY),
-            // "keypad_equal_sign_as400" => Some(EV_KEY::KEY),
-            // "locking_caps_lock" => Some(EV_KEY::KEY),
-            // "locking_num_lock" => Some(EV_KEY::KEY),
-            // "locking_scroll_lock" => Some(EV_KEY::KEY),
-            "alternate_erase" => Some(EV_KEY::KEY_ALTERASE),
-            // "sys_req_or_attention" => Some(EV_KEY::KEY),
-            "cancel" => Some(EV_KEY::KEY_CANCEL),
-            "clear" => Some(EV_KEY::KEY_CLEAR),
-            // "prior" => Some(EV_KEY::KEY),
-            // "return" => Some(EV_KEY::KEY),
-            // "separator" => Some(EV_KEY::KEY),
-            // "out" => Some(EV_KEY::KEY),
-            // "oper" => Some(EV_KEY::KEY),
-            // "clear_or_again" => Some(EV_KEY::KEY),
-            // "cr_sel_or_props" => Some(EV_KEY::KEY),
-            // "ex_sel" => Some(EV_KEY::KEY),
-            "left_alt" => Some(EV_KEY::KEY_LEFTALT),
-            "left_gui" => Some(EV_KEY::KEY_LEFTMETA), // Alias for meta
-            "right_alt" => Some(EV_KEY::KEY_RIGHTALT),
-            "right_gui" => Some(EV_KEY::KEY_RIGHTMETA), // Alias for meta
-            // "vk_consumer_brightness_down" => Some(EV_KEY::KEY),
-            // "vk_consumer_brightness_up" => Some(EV_KEY::KEY),
-            // "vk_mission_control" => Some(EV_KEY::KEY),
-            // "vk_launchpad" => Some(EV_KEY::KEY),
-            // "vk_dashboard" => Some(EV_KEY::KEY),
-            // "vk_consumer_illumination_down" => Some(EV_KEY::KEY),
-            // "vk_consumer_illumination_up" => Some(EV_KEY::KEY),
-            // "vk_consumer_previous" => Some(EV_KEY::KEY),
-            // "vk_consumer_play" => Some(EV_KEY::KEY),
-            // "vk_consumer_next" => Some(EV_KEY::KEY),
-            "volume_down" => Some(EV_KEY::KEY_VOLUMEDOWN),
-            "volume_up" => Some(EV_KEY::KEY_VOLUMEUP),
-            _ => {
-                eprintln!("Failed to decode key_code: {}", key_code);
-
-                None
-            }
+        match key_code.strip_prefix(LINUX_KEY_CODE_PREFIX) {
+            Some(linux_key_code) => from_linux_key_code(linux_key_code),
+            None => KB_KEY_CODES
+                .iter()
+                .find(|(name, _)| *name == key_code)
+                .map(|(_, key)| key.clone()),
+        }
+    }
+}
+
+/// Decodes a Linux key name (eg: `KEY_PROG1`) or a decimal or hexadecimal key
+/// code (eg: `466` or `0x1d2`).
+fn from_linux_key_code(key_code: &str) -> Option<EV_KEY> {
+    let code = if let Some(hex) = key_code.strip_prefix("0x") {
+        u32::from_str_radix(hex, 16).ok()
+    } else if let Ok(code) = key_code.parse::<u32>() {
+        Some(code)
+    } else if !key_code.contains('\0') {
+        match EventCode::from_str(&EventType::EV_KEY, key_code) {
+            Some(EventCode::EV_KEY(key)) => return Some(key),
+            _ => None,
+        }
+    } else {
+        None
+    };
+
+    code.and_then(int_to_ev_key)
+}
+
+/// Returns the `key_code` for a Linux key name (eg: `escape` for `KEY_ESC`), or
+/// a `linux:` key code if Karabiner has no name for it. Used by config importers.
+pub fn kb_key_code_from_linux_name(linux_name: &str) -> Option<String> {
+    if linux_name.parse::<u32>().is_ok() || linux_name.starts_with("0x") {
+        return None;
+    }
+
+    let key = from_linux_key_code(linux_name)?;
+    Some(match key.to_kb_key_code() {
+        Some(key_code) => key_code.to_string(),
+        None => format!("{}{}", LINUX_KEY_CODE_PREFIX, linux_name),
+    })
+}
+
+impl ToKBKeyCode for EV_KEY {
+    fn to_kb_key_code(&self) -> Option<&'static str> {
+        KB_KEY_CODES
+            .iter()
+            .find(|(_, key)| key == self)
+            .map(|(name, _)| *name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::karabiner::{FromKBKeyCode, ToKBKeyCode, KB_KEY_CODES, VK_NONE};
+    use evdev_rs::enums::{int_to_ev_key, EV_KEY};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn key_codes_round_trip() {
+        for (key_code, key) in KB_KEY_CODES {
+            assert_eq!(EV_KEY::from_kb_key_code(key_code).as_ref(), Some(key));
+
+            // Aliases decode to the same key, even if they're not the name used for it.
+            let name = key.to_kb_key_code().expect("failed to encode key");
+            assert_eq!(EV_KEY::from_kb_key_code(name).as_ref(), Some(key));
+
+            // Check that the key is one that the kernel knows about.
+            assert_eq!(int_to_ev_key(key.clone() as u32).as_ref(), Some(key));
         }
     }
+
+    #[test]
+    fn to_kb_key_code_prefers_karabiner_names() {
+        assert_eq!(EV_KEY::KEY_LEFTBRACE.to_kb_key_code(), Some("open_bracket"));
+        assert_eq!(EV_KEY::KEY_LEFTALT.to_kb_key_code(), Some("left_option"));
+        assert_eq!(EV_KEY::KEY_BACKSLASH.to_kb_key_code(), Some("backslash"));
+        assert_eq!(VK_NONE.to_kb_key_code(), Some("vk_none"));
+    }
+
+    #[test]
+    fn to_kb_key_code_returns_none_for_unknown_keys() {
+        assert_eq!(EV_KEY::KEY_PROG1.to_kb_key_code(), None);
+    }
+
+    #[test]
+    fn from_kb_key_code_decodes_vk_none() {
+        assert_eq!(EV_KEY::from_kb_key_code("vk_none"), Some(VK_NONE));
+    }
+
+    #[test]
+    fn from_kb_key_code_rejects_unknown_key_codes() {
+        assert_eq!(EV_KEY::from_kb_key_code("not_a_key"), None);
+    }
+
+    #[test]
+    fn key_codes_without_a_linux_key_are_unknown() {
+        for key_code in ["international7", "lang9", "prior", "return", "ex_sel"] {
+            assert_eq!(EV_KEY::from_kb_key_code(key_code), None, "{}", key_code);
+        }
+        let vk_none: Vec<_> = KB_KEY_CODES
+            .iter()
+            .filter(|(_, key)| *key == VK_NONE)
+            .map(|(name, _)| *name)
+            .collect();
+        assert_eq!(vk_none, vec!["vk_none"]);
+    }
+
+    #[test]
+    fn from_kb_key_code_decodes_linux_names() {
+        assert_eq!(
+            EV_KEY::from_kb_key_code("linux:KEY_PROG1"),
+            Some(EV_KEY::KEY_PROG1)
+        );
+        assert_eq!(
+            EV_KEY::from_kb_key_code("linux:BTN_LEFT"),
+            Some(EV_KEY::BTN_LEFT)
+        );
+        assert_eq!(EV_KEY::from_kb_key_code("linux:KEY_NOT_A_KEY"), None);
+        assert_eq!(EV_KEY::from_kb_key_code("KEY_PROG1"), None);
+    }
+
+    #[test]
+    fn from_kb_key_code_decodes_linux_codes() {
+        assert_eq!(
+            EV_KEY::from_kb_key_code("linux:0x1d2"),
+            Some(EV_KEY::KEY_FN_F1)
+        );
+        assert_eq!(
+            EV_KEY::from_kb_key_code("linux:466"),
+            Some(EV_KEY::KEY_FN_F1)
+        );
+        assert_eq!(EV_KEY::from_kb_key_code("linux:0xffff"), None);
+        assert_eq!(EV_KEY::from_kb_key_code("linux:0xzz"), None);
+    }
 }
@@ -0,0 +1,49 @@
+use evdev_rs::enums::EV_KEY;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+
+/// Karabiner splits keys into three kinds, which can be matched with `from.any`.
+/// Linux reports all of these as `EV_KEY` events, so they're classified by code:
+///
+/// * "pointing_button": mouse buttons (`BTN_LEFT`..`BTN_TASK`)
+/// * "consumer_key_code": media and brightness keys
+/// * "key_code": everything else
+///
+/// https://pqrs.org/osx/karabiner/json.html#from-event-definition-any
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyKind {
+    KeyCode,
+    ConsumerKeyCode,
+    PointingButton,
+}
+
+impl KeyKind {
+    pub fn from_key(key: &EV_KEY) -> KeyKind {
+        let code = key.clone() as u32;
+        if code >= EV_KEY::BTN_LEFT as u32 && code <= EV_KEY::BTN_TASK as u32 {
+            return KeyKind::PointingButton;
+        }
+
+        match key {
+            EV_KEY::KEY_MUTE
+            | EV_KEY::KEY_VOLUMEDOWN
+            | EV_KEY::KEY_VOLUMEUP
+            | EV_KEY::KEY_PLAYPAUSE
+            | EV_KEY::KEY_PLAYCD
+            | EV_KEY::KEY_PAUSECD
+            | EV_KEY::KEY_STOPCD
+            | EV_KEY::KEY_NEXTSONG
+            | EV_KEY::KEY_PREVIOUSSONG
+            | EV_KEY::KEY_REWIND
+            | EV_KEY::KEY_FASTFORWARD
+            | EV_KEY::KEY_EJECTCD
+            | EV_KEY::KEY_BRIGHTNESSDOWN
+            | EV_KEY::KEY_BRIGHTNESSUP
+            | EV_KEY::KEY_KBDILLUMDOWN
+            | EV_KEY::KEY_KBDILLUMUP
+            | EV_KEY::KEY_KBDILLUMTOGGLE => KeyKind::ConsumerKeyCode,
+            _ => KeyKind::KeyCode,
+        }
+    }
+}
@@ -1,7 +1,15 @@
+pub mod assets;
 pub mod config;
+pub mod includes;
 pub mod key_code;
+pub mod key_kind;
 pub mod modifiers;
+pub mod schema;
 
+pub use assets::*;
 pub use config::*;
+pub use includes::*;
 pub use key_code::*;
+pub use key_kind::*;
 pub use modifiers::*;
+pub use schema::*;
@@ -1,4 +1,4 @@
-use crate::karabiner::FromKBKeyCode;
+use crate::karabiner::{FromKBKeyCode, ToKBKeyCode};
 use evdev_rs::enums::EV_KEY;
 
 /// Karabiner has some special modifiers:
@@ -8,6 +8,7 @@ use evdev_rs::enums::EV_KEY;
 /// * "command": either the left or right meta keys
 /// * "shift": either the left or right shift keys
 /// * "control": either the left or right control keys
+/// * "fn": the fn key, which also toggles `fn_function_keys`
 ///
 /// https://pqrs.org/osx/karabiner/json.html#from-event-definition-modifiers-list
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -27,10 +28,11 @@ pub enum Modifier {
     RightShift,
     LeftControl,
     RightControl,
+    Fn,
 }
 
 impl Modifier {
-    pub const VARIANTS: [Modifier; 14] = [
+    pub const VARIANTS: [Modifier; 15] = [
         Modifier::Any,
         Modifier::Alt,
         Modifier::Meta,
@@ -45,6 +47,7 @@ impl Modifier {
         Modifier::RightShift,
         Modifier::LeftControl,
         Modifier::RightControl,
+        Modifier::Fn,
     ];
 
     pub fn from_key(key: &EV_KEY) -> Option<Modifier> {
@@ -58,6 +61,7 @@ impl Modifier {
             EV_KEY::KEY_RIGHTSHIFT => Some(Modifier::RightShift),
             EV_KEY::KEY_LEFTCTRL => Some(Modifier::LeftControl),
             EV_KEY::KEY_RIGHTCTRL => Some(Modifier::RightControl),
+            EV_KEY::KEY_FN => Some(Modifier::Fn),
             _ => None,
         }
     }
@@ -73,39 +77,66 @@ impl Modifier {
             Modifier::RightShift => Some(EV_KEY::KEY_RIGHTSHIFT),
             Modifier::LeftControl => Some(EV_KEY::KEY_LEFTCTRL),
             Modifier::RightControl => Some(EV_KEY::KEY_RIGHTCTRL),
+            Modifier::Fn => Some(EV_KEY::KEY_FN),
             _ => None,
         }
     }
 }
 
+/// Every Karabiner modifier name and the `Modifier` it maps to. Where several
+/// names map to the same modifier, the first one is used for reverse lookups.
+pub const KB_MODIFIERS: &[(&str, Modifier)] = &[
+    ("any", Modifier::Any),
+    ("shift", Modifier::Shift),
+    ("option", Modifier::Alt),
+    ("command", Modifier::Meta),
+    ("control", Modifier::Control),
+    ("caps_lock", Modifier::Capslock),
+    ("left_option", Modifier::LeftAlt),
+    ("left_alt", Modifier::LeftAlt),
+    ("right_option", Modifier::RightAlt),
+    ("right_alt", Modifier::RightAlt),
+    ("left_command", Modifier::LeftMeta),
+    ("left_gui", Modifier::LeftMeta),
+    ("right_command", Modifier::RightMeta),
+    ("right_gui", Modifier::RightMeta),
+    ("left_shift", Modifier::LeftShift),
+    ("right_shift", Modifier::RightShift),
+    ("left_control", Modifier::LeftControl),
+    ("right_control", Modifier::RightControl),
+    ("fn", Modifier::Fn),
+];
+
 impl FromKBKeyCode for Modifier {
     fn from_kb_key_code(key_code: &str) -> Option<Modifier> {
-        match key_code {
-            "any" => Some(Modifier::Any),
-            "shift" => Some(Modifier::Shift),
-            "option" => Some(Modifier::Alt),
-            "command" => Some(Modifier::Meta),
-            "control" => Some(Modifier::Control),
-
-            "caps_lock" => Some(Modifier::Capslock),
-
-            "left_alt" => Some(Modifier::LeftAlt),
-            "left_option" => Some(Modifier::LeftAlt),
-            "right_alt" => Some(Modifier::RightAlt),
-            "right_option" => Some(Modifier::RightAlt),
-
-            "left_gui" => Some(Modifier::LeftMeta),
-            "left_command" => Some(Modifier::LeftMeta),
-            "right_gui" => Some(Modifier::RightMeta),
-            "right_command" => Some(Modifier::RightMeta),
+        KB_MODIFIERS
+            .iter()
+            .find(|(name, _)| *name == key_code)
+            .map(|(_, modifier)| *modifier)
+    }
+}
 
-            "left_shift" => Some(Modifier::LeftShift),
-            "right_shift" => Some(Modifier::RightShift),
+impl ToKBKeyCode for Modifier {
+    fn to_kb_key_code(&self) -> Option<&'static str> {
+        KB_MODIFIERS
+            .iter()
+            .find(|(_, modifier)| modifier == self)
+            .map(|(name, _)| *name)
+    }
+}
 
-            "left_control" => Some(Modifier::LeftControl),
-            "right_control" => Some(Modifier::RightControl),
+#[cfg(test)]
+mod tests {
+    use crate::karabiner::{FromKBKeyCode, Modifier, ToKBKeyCode};
+    use pretty_assertions::assert_eq;
 
-            _ => None,
+    #[test]
+    fn modifiers_round_trip() {
+        for modifier in Modifier::VARIANTS.iter() {
+            let name = modifier
+                .to_kb_key_code()
+                .expect("failed to encode modifier");
+            assert_eq!(Modifier::from_kb_key_code(name), Some(*modifier));
         }
     }
 }
@@ -0,0 +1,65 @@
+use crate::karabiner::{KBConfig, KB_KEY_CODES, KB_MODIFIERS, LINUX_KEY_CODE_PREFIX};
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject, SubschemaValidation};
+use schemars::{schema_for, JsonSchema};
+use serde_json::{json, Value};
+
+/// Returns a JSON Schema for config files, which editors can use to validate and
+/// complete them. Fields karabinux doesn't use are allowed, and kept when saving.
+pub fn config_schema() -> Value {
+    let mut schema = serde_json::to_value(schema_for!(KBConfig)).expect("schema is valid JSON");
+    schema["title"] = json!("karabinux config");
+    schema
+}
+
+/// The schema for a `key_code` (or `consumer_key_code`), which is any name in
+/// `KB_KEY_CODES` or a `linux:` key code.
+pub struct KeyCodeSchema;
+
+impl JsonSchema for KeyCodeSchema {
+    fn schema_name() -> String {
+        String::from("KeyCode")
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        let names = KB_KEY_CODES.iter().map(|(name, _)| json!(name)).collect();
+        let named = SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            enum_values: Some(names),
+            ..SchemaObject::default()
+        };
+        let mut linux = SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            ..SchemaObject::default()
+        };
+        linux.string().pattern = Some(format!("^{}", LINUX_KEY_CODE_PREFIX));
+        linux.metadata().description = Some(String::from(
+            "Not part of Karabiner: a Linux key name or code, eg: `linux:KEY_PROG1` or `linux:0x1d2`.",
+        ));
+
+        Schema::Object(SchemaObject {
+            subschemas: Some(Box::new(SubschemaValidation {
+                any_of: Some(vec![named.into(), linux.into()]),
+                ..SubschemaValidation::default()
+            })),
+            ..SchemaObject::default()
+        })
+    }
+}
+
+/// The schema for a modifier, which is any name in `KB_MODIFIERS`.
+pub struct ModifierSchema;
+
+impl JsonSchema for ModifierSchema {
+    fn schema_name() -> String {
+        String::from("Modifier")
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        Schema::Object(SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            enum_values: Some(KB_MODIFIERS.iter().map(|(name, _)| json!(name)).collect()),
+            ..SchemaObject::default()
+        })
+    }
+}
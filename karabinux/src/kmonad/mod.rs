@@ -0,0 +1,5 @@
+pub mod sexpr;
+pub mod translate;
+
+pub use sexpr::*;
+pub use translate::*;
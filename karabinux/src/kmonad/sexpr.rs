@@ -0,0 +1,253 @@
+use crate::kmonad::KmonadError;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// A value read from a KMonad `.kbd` file, which is made of s-expressions.
+///
+/// https://github.com/kmonad/kmonad/blob/master/keymap/tutorial.kbd
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sexpr {
+    /// Anything that isn't a string or a list, eg: `esc`, `@alias` or `200`.
+    Symbol(String),
+    String(String),
+    List(Vec<Sexpr>),
+}
+
+impl Sexpr {
+    /// Reads every s-expression in `input`, which may be separated by whitespace
+    /// and comments.
+    pub fn parse_all(input: &str) -> Result<Vec<Sexpr>, KmonadError> {
+        let mut reader = Reader::new(input);
+        let mut values = vec![];
+        while let Some(value) = reader.read()? {
+            values.push(value);
+        }
+
+        Ok(values)
+    }
+
+    pub fn as_symbol(&self) -> Option<&str> {
+        match self {
+            Sexpr::Symbol(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Sexpr]> {
+        match self {
+            Sexpr::List(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Sexpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Sexpr::Symbol(name) => write!(f, "{}", name),
+            Sexpr::String(s) => write!(f, "{:?}", s),
+            Sexpr::List(items) => {
+                write!(f, "(")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(input: &'a str) -> Reader<'a> {
+        Reader {
+            chars: input.chars().peekable(),
+            line: 1,
+            column: 0,
+        }
+    }
+
+    fn error(&self, message: &str) -> KmonadError {
+        KmonadError {
+            path: String::new(),
+            message: format!(
+                "{} at line {} column {}",
+                message,
+                self.line,
+                self.column + 1
+            ),
+        }
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 0;
+        } else {
+            self.column += 1;
+        }
+
+        Some(c)
+    }
+
+    fn peek_second(&self) -> Option<char> {
+        self.chars.clone().nth(1)
+    }
+
+    /// Skips whitespace, `;;` line comments and `#| ... |#` block comments.
+    fn skip_ignored(&mut self) -> Result<(), KmonadError> {
+        while let Some(&c) = self.chars.peek() {
+            if c.is_whitespace() {
+                self.next_char();
+            } else if c == ';' && self.peek_second() == Some(';') {
+                while let Some(c) = self.next_char() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            } else if c == '#' && self.peek_second() == Some('|') {
+                self.next_char();
+                self.next_char();
+                loop {
+                    match self.next_char() {
+                        Some('|') if self.chars.peek() == Some(&'#') => {
+                            self.next_char();
+                            break;
+                        }
+                        Some(_) => {}
+                        None => return Err(self.error("unterminated block comment")),
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the next value, or `None` at the end of the input.
+    fn read(&mut self) -> Result<Option<Sexpr>, KmonadError> {
+        self.skip_ignored()?;
+        let c = match self.chars.peek() {
+            Some(&c) => c,
+            None => return Ok(None),
+        };
+
+        match c {
+            '(' => {
+                self.next_char();
+                let mut items = vec![];
+                loop {
+                    self.skip_ignored()?;
+                    match self.chars.peek() {
+                        Some(')') => {
+                            self.next_char();
+                            break;
+                        }
+                        Some(_) => items.extend(self.read()?),
+                        None => return Err(self.error("unterminated list")),
+                    }
+                }
+
+                Ok(Some(Sexpr::List(items)))
+            }
+            ')' => Err(self.error("unexpected ')'")),
+            '"' => {
+                self.next_char();
+                let mut s = String::new();
+                loop {
+                    match self.next_char() {
+                        Some('"') => break,
+                        Some('\\') => match self.next_char() {
+                            Some('n') => s.push('\n'),
+                            Some('t') => s.push('\t'),
+                            Some(c) => s.push(c),
+                            None => return Err(self.error("unterminated string")),
+                        },
+                        Some(c) => s.push(c),
+                        None => return Err(self.error("unterminated string")),
+                    }
+                }
+
+                Ok(Some(Sexpr::String(s)))
+            }
+            _ => {
+                let mut name = String::new();
+                while let Some(&c) = self.chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+
+                    // A backslash escapes characters which would end the symbol, eg: `\(`.
+                    self.next_char();
+                    if c == '\\' {
+                        if let Some(escaped) = self.next_char() {
+                            name.push(escaped);
+                            continue;
+                        }
+                    }
+                    name.push(c);
+                }
+
+                Ok(Some(Sexpr::Symbol(name)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sexpr;
+    use pretty_assertions::assert_eq;
+
+    fn symbol(name: &str) -> Sexpr {
+        Sexpr::Symbol(name.to_string())
+    }
+
+    #[test]
+    fn reads_lists_strings_and_symbols() {
+        let values =
+            Sexpr::parse_all("(defsrc esc a) ;; comment\n(cmd-button \"echo \\\"hi\\\"\")")
+                .unwrap();
+        assert_eq!(
+            values,
+            vec![
+                Sexpr::List(vec![symbol("defsrc"), symbol("esc"), symbol("a")]),
+                Sexpr::List(vec![
+                    symbol("cmd-button"),
+                    Sexpr::String(String::from("echo \"hi\"")),
+                ]),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_block_comments() {
+        let values = Sexpr::parse_all("#| (deflayer\n nope) |# (defalias a @b)").unwrap();
+        assert_eq!(
+            values,
+            vec![Sexpr::List(vec![
+                symbol("defalias"),
+                symbol("a"),
+                symbol("@b")
+            ])]
+        );
+    }
+
+    #[test]
+    fn reports_error_location() {
+        let error = Sexpr::parse_all("(defsrc a)\n  )").unwrap_err();
+        assert_eq!(error.to_string(), "unexpected ')' at line 2 column 3");
+    }
+}
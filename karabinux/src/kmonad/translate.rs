@@ -0,0 +1,607 @@
+use crate::error::Error;
+use crate::karabiner::{kb_key_code_from_linux_name, KBConfig, KBProfile};
+use crate::kmonad::Sexpr;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The name of the profile in configs read from `.kbd` files.
+pub const KMONAD_PROFILE_NAME: &str = "KMonad";
+
+/// How deeply aliases can refer to other aliases, so cycles are reported.
+const MAX_ALIAS_DEPTH: usize = 16;
+
+/// KMonad's names for keys which aren't the same as their Linux names.
+const KEY_ALIASES: &[(&str, &str)] = &[
+    ("ret", "KEY_ENTER"),
+    ("return", "KEY_ENTER"),
+    ("ent", "KEY_ENTER"),
+    ("spc", "KEY_SPACE"),
+    ("bspc", "KEY_BACKSPACE"),
+    ("del", "KEY_DELETE"),
+    ("ins", "KEY_INSERT"),
+    ("caps", "KEY_CAPSLOCK"),
+    ("lsft", "KEY_LEFTSHIFT"),
+    ("lshift", "KEY_LEFTSHIFT"),
+    ("rsft", "KEY_RIGHTSHIFT"),
+    ("rshift", "KEY_RIGHTSHIFT"),
+    ("lctl", "KEY_LEFTCTRL"),
+    ("lctrl", "KEY_LEFTCTRL"),
+    ("rctl", "KEY_RIGHTCTRL"),
+    ("rctrl", "KEY_RIGHTCTRL"),
+    ("lalt", "KEY_LEFTALT"),
+    ("ralt", "KEY_RIGHTALT"),
+    ("lmet", "KEY_LEFTMETA"),
+    ("lmeta", "KEY_LEFTMETA"),
+    ("rmet", "KEY_RIGHTMETA"),
+    ("rmeta", "KEY_RIGHTMETA"),
+    ("cmp", "KEY_COMPOSE"),
+    ("menu", "KEY_COMPOSE"),
+    ("grv", "KEY_GRAVE"),
+    ("`", "KEY_GRAVE"),
+    ("min", "KEY_MINUS"),
+    ("-", "KEY_MINUS"),
+    ("eql", "KEY_EQUAL"),
+    ("=", "KEY_EQUAL"),
+    ("lbrc", "KEY_LEFTBRACE"),
+    ("[", "KEY_LEFTBRACE"),
+    ("rbrc", "KEY_RIGHTBRACE"),
+    ("]", "KEY_RIGHTBRACE"),
+    ("bksl", "KEY_BACKSLASH"),
+    ("\\", "KEY_BACKSLASH"),
+    ("scln", "KEY_SEMICOLON"),
+    (";", "KEY_SEMICOLON"),
+    ("apos", "KEY_APOSTROPHE"),
+    ("'", "KEY_APOSTROPHE"),
+    ("comm", "KEY_COMMA"),
+    (",", "KEY_COMMA"),
+    (".", "KEY_DOT"),
+    ("slsh", "KEY_SLASH"),
+    ("/", "KEY_SLASH"),
+    ("pgup", "KEY_PAGEUP"),
+    ("pgdn", "KEY_PAGEDOWN"),
+    ("prnt", "KEY_SYSRQ"),
+    ("ssrq", "KEY_SYSRQ"),
+    ("slck", "KEY_SCROLLLOCK"),
+    ("nlck", "KEY_NUMLOCK"),
+];
+
+/// KMonad's shifted characters, and the keys they're typed with.
+const SHIFTED_KEYS: &[(&str, &str)] = &[
+    ("~", "grv"),
+    ("!", "1"),
+    ("#", "3"),
+    ("$", "4"),
+    ("%", "5"),
+    ("^", "6"),
+    ("&", "7"),
+    ("*", "8"),
+    ("(", "9"),
+    (")", "0"),
+    ("+", "eql"),
+    ("{", "lbrc"),
+    ("}", "rbrc"),
+    ("|", "bksl"),
+    (":", "scln"),
+    ("\"", "apos"),
+    ("<", "comm"),
+    (">", "dot"),
+    ("?", "slsh"),
+];
+
+/// KMonad's modifier prefixes (eg: `C-a`), and the modifiers they send.
+const MODIFIER_PREFIXES: &[(&str, &str)] = &[
+    ("C-", "left_control"),
+    ("S-", "left_shift"),
+    ("A-", "left_option"),
+    ("M-", "left_command"),
+    ("RC-", "right_control"),
+    ("RS-", "right_shift"),
+    ("RA-", "right_option"),
+    ("RM-", "right_command"),
+];
+
+/// A KMonad config that was read, but can't be translated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KmonadError {
+    /// Eg: "deflayer nav.caps" or "defalias esc-ctl".
+    pub path: String,
+    pub message: String,
+}
+
+impl KmonadError {
+    fn new(path: &str, message: String) -> KmonadError {
+        KmonadError {
+            path: path.to_string(),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for KmonadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for KmonadError {}
+
+/// Reads a KMonad `.kbd` file, and translates it to a `KBConfig` with a single
+/// selected profile.
+pub fn config_from_str(input: &str) -> Result<KBConfig, Error> {
+    let mut profile = profile_from_str(input, KMONAD_PROFILE_NAME)?;
+    profile.selected = true;
+    Ok(KBConfig::from_profiles(vec![profile]))
+}
+
+/// Reads a KMonad `.kbd` file, and translates it to a profile named `name`.
+pub fn profile_from_str(input: &str, name: &str) -> Result<KBProfile, Error> {
+    let forms = Sexpr::parse_all(input)?;
+    Ok(profile_from_kmonad(&forms, name)?)
+}
+
+/// Translates a KMonad keymap to a profile of complex modifications.
+///
+/// The first `deflayer` is the base layer. Every other layer has a variable of the
+/// same name, which is set while the layer is active, and its buttons only apply
+/// while it's set. `_` in a layer falls through to the layers below it.
+///
+/// https://github.com/kmonad/kmonad/blob/master/keymap/tutorial.kbd
+pub fn profile_from_kmonad(forms: &[Sexpr], name: &str) -> Result<KBProfile, KmonadError> {
+    let kmonad = Kmonad::new(forms)?;
+    let (base, layers) = kmonad
+        .layers
+        .split_first()
+        .ok_or_else(|| KmonadError::new("", String::from("missing deflayer")))?;
+
+    // Layers that were declared later take priority, like KMonad's layer stack.
+    let mut rules = vec![];
+    for (layer_name, buttons) in layers.iter().rev() {
+        let conditions = [json!({ "type": "variable_if", "name": layer_name, "value": 1 })];
+        let manipulators = kmonad.manipulators(layer_name, buttons, &conditions, false)?;
+        if !manipulators.is_empty() {
+            rules.push(json!({
+                "description": format!("KMonad layer {}", layer_name),
+                "manipulators": manipulators,
+            }));
+        }
+    }
+
+    let (base_name, base_buttons) = base;
+    let manipulators = kmonad.manipulators(base_name, base_buttons, &[], true)?;
+    if !manipulators.is_empty() {
+        rules.push(json!({
+            "description": format!("KMonad layer {}", base_name),
+            "manipulators": manipulators,
+        }));
+    }
+
+    serde_json::from_value(json!({
+        "name": name,
+        "selected": false,
+        "simple_modifications": [],
+        "complex_modifications": { "rules": rules },
+    }))
+    .map_err(|e| KmonadError::new("", format!("invalid config: {}", e)))
+}
+
+/// What a button in a layer does.
+#[derive(Debug, Clone)]
+enum Button {
+    /// `_`: the button from the layer below is used.
+    Transparent,
+    /// `XX`: nothing is sent.
+    Block,
+    /// Keys (possibly with modifiers) and commands, as to-events.
+    Keys(Vec<Value>),
+    /// `(layer-toggle name)` or `(layer-while-held name)`.
+    LayerWhileHeld(String),
+    /// `(layer-switch name)`: replaces the active layers with `name`.
+    LayerSwitch(String),
+    /// `(layer-add name)` or `(layer-rem name)`.
+    SetLayer(String, bool),
+    /// `(tap-hold ms tap hold)` decides by how long the button is held for, while
+    /// `(tap-next tap hold)` and `(tap-hold-next ms tap hold)` also hold when
+    /// another key is pressed (`next`).
+    TapHold {
+        timeout: Option<u64>,
+        next: bool,
+        tap: Box<Button>,
+        hold: Box<Button>,
+    },
+}
+
+struct Kmonad<'a> {
+    /// The Karabiner key codes of the keys in `defsrc`.
+    source: Vec<(&'a str, String)>,
+    aliases: HashMap<&'a str, &'a Sexpr>,
+    layers: Vec<(&'a str, &'a [Sexpr])>,
+}
+
+impl<'a> Kmonad<'a> {
+    fn new(forms: &'a [Sexpr]) -> Result<Kmonad<'a>, KmonadError> {
+        let mut source = None;
+        let mut aliases = HashMap::new();
+        let mut layers = vec![];
+
+        for form in forms {
+            let items = form.as_list().unwrap_or_default();
+            let (head, args) = match items.split_first() {
+                Some((Sexpr::Symbol(head), args)) => (head.as_str(), args),
+                _ => return Err(KmonadError::new("", format!("unexpected {}", form))),
+            };
+
+            match head {
+                // Settings for KMonad's input and output devices, which karabinux chooses itself.
+                "defcfg" => {}
+                "defsrc" => {
+                    let keys = args
+                        .iter()
+                        .map(|key| {
+                            let name = key.as_symbol().unwrap_or_default();
+                            key_code(name)
+                                .map(|key_code| (name, key_code))
+                                .ok_or_else(|| {
+                                    KmonadError::new("defsrc", format!("unknown key {}", key))
+                                })
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    source = Some(keys);
+                }
+                "defalias" => {
+                    for pair in args.chunks(2) {
+                        match pair {
+                            [Sexpr::Symbol(name), button] => {
+                                aliases.insert(name.as_str(), button);
+                            }
+                            _ => {
+                                return Err(KmonadError::new(
+                                    "defalias",
+                                    format!("expected a name and a button, got {}", pair[0]),
+                                ))
+                            }
+                        }
+                    }
+                }
+                "deflayer" => match args.split_first() {
+                    Some((Sexpr::Symbol(name), buttons)) => layers.push((name.as_str(), buttons)),
+                    _ => {
+                        return Err(KmonadError::new(
+                            "deflayer",
+                            String::from("missing layer name"),
+                        ))
+                    }
+                },
+                _ => return Err(KmonadError::new("", format!("unsupported form ({})", head))),
+            }
+        }
+
+        let source = source.ok_or_else(|| KmonadError::new("", String::from("missing defsrc")))?;
+        for (name, buttons) in &layers {
+            if buttons.len() != source.len() {
+                return Err(KmonadError::new(
+                    &format!("deflayer {}", name),
+                    format!(
+                        "has {} buttons, but defsrc has {} keys",
+                        buttons.len(),
+                        source.len()
+                    ),
+                ));
+            }
+        }
+
+        Ok(Kmonad {
+            source,
+            aliases,
+            layers,
+        })
+    }
+
+    /// Returns a manipulator for every button in a layer that isn't transparent.
+    /// Buttons in the base layer that send their own key are skipped too.
+    fn manipulators(
+        &self,
+        layer_name: &str,
+        buttons: &[Sexpr],
+        conditions: &[Value],
+        is_base: bool,
+    ) -> Result<Vec<Value>, KmonadError> {
+        let mut manipulators = vec![];
+        for ((source_name, from_key), button) in self.source.iter().zip(buttons) {
+            let path = format!("deflayer {}.{}", layer_name, source_name);
+            let button = self.button(button, &path, 0)?;
+            if let Button::Keys(to) = &button {
+                let is_unchanged = to.len() == 1 && to[0] == json!({ "key_code": from_key });
+                if is_base && is_unchanged {
+                    continue;
+                }
+            }
+
+            if let Some(manipulator) = self.manipulator(from_key, &button, conditions, &path)? {
+                manipulators.push(manipulator);
+            }
+        }
+
+        Ok(manipulators)
+    }
+
+    fn manipulator(
+        &self,
+        from_key: &str,
+        button: &Button,
+        conditions: &[Value],
+        path: &str,
+    ) -> Result<Option<Value>, KmonadError> {
+        let mut manipulator = json!({
+            "type": "basic",
+            "from": { "key_code": from_key, "modifiers": { "optional": ["any"] } },
+        });
+        if !conditions.is_empty() {
+            manipulator["conditions"] = json!(conditions);
+        }
+
+        match button {
+            Button::Transparent => return Ok(None),
+            Button::Block => manipulator["to"] = json!([{ "key_code": "vk_none" }]),
+            Button::Keys(to) => manipulator["to"] = json!(to),
+            Button::LayerWhileHeld(layer) => {
+                manipulator["to"] = json!([set_variable(layer, 1)]);
+                manipulator["to_after_key_up"] = json!([set_variable(layer, 0)]);
+            }
+            Button::LayerSwitch(layer) => {
+                let to: Vec<_> = self.layers[1..]
+                    .iter()
+                    .map(|(name, _)| set_variable(name, (name == layer) as i32))
+                    .collect();
+                manipulator["to"] = json!(to);
+            }
+            Button::SetLayer(layer, active) => {
+                manipulator["to"] = json!([set_variable(layer, *active as i32)]);
+            }
+            Button::TapHold {
+                timeout,
+                next,
+                tap,
+                hold,
+            } => {
+                let tap = match tap.as_ref() {
+                    Button::Keys(to) => to.clone(),
+                    Button::Block => vec![json!({ "key_code": "vk_none" })],
+                    _ => {
+                        return Err(KmonadError::new(
+                            path,
+                            String::from("the tap button must send keys"),
+                        ))
+                    }
+                };
+
+                let (hold, after_key_up) = match hold.as_ref() {
+                    Button::Keys(to) => (to.clone(), vec![]),
+                    Button::LayerWhileHeld(layer) => {
+                        (vec![set_variable(layer, 1)], vec![set_variable(layer, 0)])
+                    }
+                    _ => {
+                        return Err(KmonadError::new(
+                            path,
+                            String::from("the hold button must send keys or hold a layer"),
+                        ))
+                    }
+                };
+
+                // Holding until another key is pressed is the same as holding straight away,
+                // and sending the tap if it's released alone.
+                let hold_field = if *next { "to" } else { "to_if_held_down" };
+                manipulator[hold_field] = json!(hold);
+                manipulator["to_if_alone"] = json!(tap);
+                if !after_key_up.is_empty() {
+                    manipulator["to_after_key_up"] = json!(after_key_up);
+                }
+
+                if let Some(timeout) = timeout {
+                    let mut parameters =
+                        json!({ "basic.to_if_alone_timeout_milliseconds": timeout });
+                    if !next {
+                        parameters["basic.to_if_held_down_threshold_milliseconds"] = json!(timeout);
+                    }
+                    manipulator["parameters"] = parameters;
+                }
+            }
+        }
+
+        Ok(Some(manipulator))
+    }
+
+    fn button(&self, sexpr: &Sexpr, path: &str, depth: usize) -> Result<Button, KmonadError> {
+        let items = match sexpr {
+            Sexpr::Symbol(name) => return self.symbol_button(name, path, depth),
+            Sexpr::String(_) => {
+                return Err(KmonadError::new(path, format!("unexpected {}", sexpr)))
+            }
+            Sexpr::List(items) => items,
+        };
+
+        let head = items
+            .first()
+            .and_then(|h| h.as_symbol())
+            .unwrap_or_default();
+        let args = items.get(1..).unwrap_or_default();
+        let layer_name = || match args {
+            [Sexpr::Symbol(layer)] if self.layers.iter().any(|(name, _)| name == layer) => {
+                Ok(layer.clone())
+            }
+            _ => Err(KmonadError::new(
+                path,
+                format!("expected the name of a layer in {}", sexpr),
+            )),
+        };
+        let timeout = |arg: &Sexpr| {
+            arg.as_symbol()
+                .and_then(|ms| ms.parse::<u64>().ok())
+                .ok_or_else(|| KmonadError::new(path, format!("expected a timeout, got {}", arg)))
+        };
+
+        match (head, args) {
+            ("layer-toggle", _) | ("layer-while-held", _) => {
+                Ok(Button::LayerWhileHeld(layer_name()?))
+            }
+            ("layer-switch", _) => Ok(Button::LayerSwitch(layer_name()?)),
+            ("layer-add", _) => Ok(Button::SetLayer(layer_name()?, true)),
+            ("layer-rem", _) => Ok(Button::SetLayer(layer_name()?, false)),
+            ("tap-next", [tap, hold]) => Ok(Button::TapHold {
+                timeout: None,
+                next: true,
+                tap: Box::new(self.button(tap, path, depth)?),
+                hold: Box::new(self.button(hold, path, depth)?),
+            }),
+            ("tap-hold", [ms, tap, hold])
+            | ("tap-hold-next", [ms, tap, hold])
+            | ("tap-hold-next-release", [ms, tap, hold]) => Ok(Button::TapHold {
+                timeout: Some(timeout(ms)?),
+                next: head != "tap-hold",
+                tap: Box::new(self.button(tap, path, depth)?),
+                hold: Box::new(self.button(hold, path, depth)?),
+            }),
+            ("around", [outer, inner]) => {
+                let outer = self.keys(outer, path, depth)?;
+                let mut inner = self.keys(inner, path, depth)?;
+                let modifier = match outer.as_slice() {
+                    [key] if key.get("modifiers").is_none() => key["key_code"].clone(),
+                    _ => {
+                        return Err(KmonadError::new(
+                            path,
+                            format!("expected a modifier key in {}", sexpr),
+                        ))
+                    }
+                };
+                for to in &mut inner {
+                    let modifiers = to
+                        .as_object_mut()
+                        .unwrap()
+                        .entry("modifiers")
+                        .or_insert_with(|| json!([]));
+                    modifiers
+                        .as_array_mut()
+                        .unwrap()
+                        .insert(0, modifier.clone());
+                }
+
+                Ok(Button::Keys(inner))
+            }
+            ("cmd-button", [Sexpr::String(command)]) => {
+                Ok(Button::Keys(vec![json!({ "shell_command": command })]))
+            }
+            _ => Err(KmonadError::new(
+                path,
+                format!("unsupported button {}", sexpr),
+            )),
+        }
+    }
+
+    fn symbol_button(&self, name: &str, path: &str, depth: usize) -> Result<Button, KmonadError> {
+        match name {
+            "_" => return Ok(Button::Transparent),
+            "XX" => return Ok(Button::Block),
+            _ => {}
+        }
+
+        if let Some(alias) = name.strip_prefix('@') {
+            if depth >= MAX_ALIAS_DEPTH {
+                return Err(KmonadError::new(
+                    path,
+                    format!("alias @{} refers to itself", alias),
+                ));
+            }
+
+            return match self.aliases.get(alias) {
+                Some(button) => self.button(button, path, depth + 1),
+                None => Err(KmonadError::new(path, format!("unknown alias @{}", alias))),
+            };
+        }
+
+        key_press(name)
+            .map(|to| Button::Keys(vec![to]))
+            .ok_or_else(|| KmonadError::new(path, format!("unknown key {}", name)))
+    }
+
+    /// Returns the to-events for a button that must send keys, eg: in `around`.
+    fn keys(&self, sexpr: &Sexpr, path: &str, depth: usize) -> Result<Vec<Value>, KmonadError> {
+        match self.button(sexpr, path, depth)? {
+            Button::Keys(to) => Ok(to),
+            _ => Err(KmonadError::new(
+                path,
+                format!("expected keys, got {}", sexpr),
+            )),
+        }
+    }
+}
+
+fn set_variable(name: &str, value: i32) -> Value {
+    json!({ "set_variable": { "name": name, "value": value } })
+}
+
+/// Returns the to-event for a key with any modifier prefixes, eg: `C-S-a` or `!`.
+fn key_press(name: &str) -> Option<Value> {
+    let mut modifiers = vec![];
+    let mut rest = name;
+    'prefixes: while rest.len() > 2 {
+        for (prefix, modifier) in MODIFIER_PREFIXES {
+            if let Some(key) = rest.strip_prefix(prefix) {
+                modifiers.push(*modifier);
+                rest = key;
+                continue 'prefixes;
+            }
+        }
+
+        break;
+    }
+
+    if let Some((_, key)) = SHIFTED_KEYS.iter().find(|(shifted, _)| *shifted == rest) {
+        modifiers.push("left_shift");
+        rest = key;
+    }
+
+    let mut to = json!({ "key_code": key_code(rest)? });
+    if !modifiers.is_empty() {
+        to["modifiers"] = json!(modifiers);
+    }
+
+    Some(to)
+}
+
+/// Returns the Karabiner key code for a KMonad key name, eg: `esc` or `lctl`.
+fn key_code(name: &str) -> Option<String> {
+    let linux_name = match KEY_ALIASES.iter().find(|(alias, _)| *alias == name) {
+        Some((_, linux_name)) => linux_name.to_string(),
+        None => format!("KEY_{}", name.to_uppercase()),
+    };
+
+    kb_key_code_from_linux_name(&linux_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::key_press;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn key_names_are_translated() {
+        assert_eq!(key_press("caps"), Some(json!({ "key_code": "caps_lock" })));
+        assert_eq!(key_press("a"), Some(json!({ "key_code": "a" })));
+        assert_eq!(key_press("f12"), Some(json!({ "key_code": "f12" })));
+        assert_eq!(
+            key_press("C-S-tab"),
+            Some(json!({ "key_code": "tab", "modifiers": ["left_control", "left_shift"] }))
+        );
+        assert_eq!(
+            key_press("!"),
+            Some(json!({ "key_code": "1", "modifiers": ["left_shift"] }))
+        );
+        assert_eq!(key_press("C-"), None);
+        assert_eq!(key_press("nope"), None);
+    }
+}
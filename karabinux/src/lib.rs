@@ -1,6 +1,15 @@
+pub mod cheat_sheet;
 pub mod constants;
+pub mod error;
 pub mod event;
+pub mod goku;
 pub mod karabiner;
 pub mod key_state;
+pub mod kmonad;
+pub mod lint;
 pub mod state;
+pub mod testing;
 pub mod util;
+pub mod xremap;
+
+pub use error::Error;
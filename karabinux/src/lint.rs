@@ -0,0 +1,360 @@
+use crate::error::Error;
+use crate::karabiner::{KBParameters, KBProfile, KBSimpleModification, Modifier};
+use crate::state::complex_manipulator::manipulator_location;
+use crate::state::{
+    ComplexManipulator, Condition, FromEvent, ModifierState, SimpleManipulator, StateManager,
+    ToEvent,
+};
+use crate::util::format_key;
+use evdev_rs::enums::EV_KEY;
+use std::fmt;
+use std::path::PathBuf;
+
+/// The modifiers that can actually be held, as opposed to eg: `Modifier::Shift`
+/// which is matched by either shift key.
+const HELD_MODIFIERS: [Modifier; 10] = [
+    Modifier::Capslock,
+    Modifier::LeftAlt,
+    Modifier::RightAlt,
+    Modifier::LeftMeta,
+    Modifier::RightMeta,
+    Modifier::LeftShift,
+    Modifier::RightShift,
+    Modifier::LeftControl,
+    Modifier::RightControl,
+    Modifier::Fn,
+];
+
+/// Something in a profile which is valid, but probably doesn't do what was intended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LintProblem {
+    /// A manipulator that an earlier manipulator (at the given location) always
+    /// matches first, since only the first matching manipulator applies.
+    Unreachable(String),
+    /// A simple modification for a key that an earlier one (at the given path)
+    /// already changes.
+    DuplicateSimpleModification(String),
+    /// A simple modification to a key that another one (at the given path) changes,
+    /// which won't happen since simple modifications are only applied once.
+    SimpleModificationChain(String),
+    /// A key which is sent, but isn't supported by the output device.
+    UnsupportedKey(String),
+    /// A parameter which is set, but which karabinux ignores.
+    UnsupportedParameter(String),
+}
+
+impl fmt::Display for LintProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LintProblem::Unreachable(location) => {
+                write!(f, "never applies, since {} always matches first", location)
+            }
+            LintProblem::DuplicateSimpleModification(path) => {
+                write!(f, "never applies, since {} changes the same key", path)
+            }
+            LintProblem::SimpleModificationChain(path) => write!(
+                f,
+                "sends a key changed by {}, which isn't applied to it",
+                path
+            ),
+            LintProblem::UnsupportedKey(name) => {
+                write!(f, "sends \"{}\", which the output device can't send", name)
+            }
+            LintProblem::UnsupportedParameter(name) => {
+                write!(f, "sets \"{}\", which karabinux ignores", name)
+            }
+        }
+    }
+}
+
+/// A `LintProblem`, along with where it was found in the config.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintWarning {
+    pub profile: String,
+    /// The included file the warning is in, if it's not in the config file itself.
+    pub file: Option<PathBuf>,
+    /// Eg: "simple_modifications[0]" or "complex_modifications.rules[1].manipulators[0]".
+    pub path: String,
+    pub description: Option<String>,
+    pub problem: LintProblem,
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "profile \"{}\": ", self.profile)?;
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file.display())?;
+        }
+
+        write!(f, "{}", self.path)?;
+        if let Some(description) = &self.description {
+            write!(f, " (\"{}\")", description)?;
+        }
+
+        write!(f, ": {}", self.problem)
+    }
+}
+
+/// Finds the parts of a profile which can never apply or don't do what they look
+/// like they do, including parameters karabinux doesn't support. If `output_keys` are given, keys that are sent but aren't in them
+/// are reported too.
+///
+/// Returns the profile's config errors if it can't be used at all.
+pub fn lint_profile(
+    kb_profile: &KBProfile,
+    output_keys: Option<&[EV_KEY]>,
+) -> Result<Vec<LintWarning>, Error> {
+    // Checks every section, so problems are reported the same way as when running.
+    StateManager::from_profile(kb_profile)?;
+
+    let mut linter = Linter {
+        profile: &kb_profile.name,
+        output_keys,
+        warnings: vec![],
+    };
+
+    linter.lint_complex_modifications(kb_profile)?;
+    linter.lint_simple_modifications(&kb_profile.simple_modifications, "simple_modifications");
+    linter.lint_simple_modifications(
+        kb_profile.fn_function_keys.as_deref().unwrap_or_default(),
+        "fn_function_keys",
+    );
+    for (i, kb_device) in kb_profile.devices.iter().flatten().enumerate() {
+        linter.lint_simple_modifications(
+            kb_device
+                .simple_modifications
+                .as_deref()
+                .unwrap_or_default(),
+            &format!("devices[{}].simple_modifications", i),
+        );
+        linter.lint_simple_modifications(
+            kb_device.fn_function_keys.as_deref().unwrap_or_default(),
+            &format!("devices[{}].fn_function_keys", i),
+        );
+    }
+
+    Ok(linter.warnings)
+}
+
+struct Linter<'a> {
+    profile: &'a str,
+    output_keys: Option<&'a [EV_KEY]>,
+    warnings: Vec<LintWarning>,
+}
+
+impl<'a> Linter<'a> {
+    fn lint_complex_modifications(&mut self, kb_profile: &KBProfile) -> Result<(), Error> {
+        let manipulators =
+            ComplexManipulator::manipulators_from_config(&kb_profile.complex_modifications)
+                .map_err(Error::Config)?;
+        let locations: Vec<_> = kb_profile
+            .complex_modifications
+            .rules
+            .iter()
+            .enumerate()
+            .flat_map(|(i, rule)| {
+                (0..rule.manipulators.len()).map(move |j| manipulator_location(i, rule, j))
+            })
+            .collect();
+        let kb_manipulators: Vec<_> = kb_profile
+            .complex_modifications
+            .rules
+            .iter()
+            .flat_map(|rule| &rule.manipulators)
+            .collect();
+
+        if let Some(kb_parameters) = &kb_profile.complex_modifications.parameters {
+            self.lint_parameters(
+                &None,
+                "complex_modifications.parameters",
+                &None,
+                kb_parameters,
+            );
+        }
+
+        let modifier_states = held_modifier_states();
+        for (i, cm) in manipulators.iter().enumerate() {
+            let (file, path) = &locations[i];
+            let shadowed_by = manipulators[..i]
+                .iter()
+                .position(|earlier| shadows(earlier, cm, &modifier_states));
+            if let Some(j) = shadowed_by {
+                let location = match &locations[j] {
+                    (Some(file), path) => format!("{}: {}", file.display(), path),
+                    (None, path) => path.clone(),
+                };
+                self.warn(
+                    file,
+                    path,
+                    &cm.description,
+                    LintProblem::Unreachable(location),
+                );
+            }
+
+            let to_events = cm
+                .to_events
+                .iter()
+                .chain(&cm.to_if_alone)
+                .chain(&cm.to_if_held_down)
+                .chain(&cm.to_after_key_up);
+            for key in to_events.flat_map(sent_keys) {
+                self.check_output_key(file, path, &cm.description, &key);
+            }
+
+            if let Some(kb_parameters) = &kb_manipulators[i].parameters {
+                let path = format!("{}.parameters", path);
+                self.lint_parameters(file, &path, &cm.description, kb_parameters);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn lint_parameters(
+        &mut self,
+        file: &Option<PathBuf>,
+        path: &str,
+        description: &Option<String>,
+        kb_parameters: &KBParameters,
+    ) {
+        let unsupported = [
+            (
+                "basic.to_delayed_action_delay_milliseconds",
+                kb_parameters.to_delayed_action_delay_milliseconds,
+            ),
+            (
+                "mouse_motion_to_scroll.speed",
+                kb_parameters.mouse_motion_to_scroll_speed,
+            ),
+        ];
+
+        for (name, value) in unsupported {
+            if value.is_some() {
+                let problem = LintProblem::UnsupportedParameter(name.to_string());
+                self.warn(file, path, description, problem);
+            }
+        }
+    }
+
+    fn lint_simple_modifications(
+        &mut self,
+        kb_simple_modifications: &[KBSimpleModification],
+        section: &str,
+    ) {
+        // Invalid simple modifications were already reported by the state.
+        let manipulators: Vec<_> = kb_simple_modifications
+            .iter()
+            .map(|sm| SimpleManipulator::from_config(sm).ok())
+            .collect();
+        let path = |i: usize| format!("{}[{}]", section, i);
+
+        for (i, sm) in manipulators.iter().enumerate() {
+            let sm = match sm {
+                Some(sm) => sm,
+                None => continue,
+            };
+
+            let first = manipulators[..i]
+                .iter()
+                .position(|earlier| earlier.as_ref().is_some_and(|e| e.from == sm.from));
+            if let Some(j) = first {
+                let problem = LintProblem::DuplicateSimpleModification(path(j));
+                self.warn(&None, &path(i), &None, problem);
+                continue;
+            }
+
+            // Keys can be swapped, so `a -> b` and `b -> a` isn't a chain.
+            let next = manipulators.iter().position(|other| {
+                other
+                    .as_ref()
+                    .is_some_and(|o| o.from == sm.to && o.from != sm.from && o.to != sm.from)
+            });
+            if let Some(j) = next {
+                let problem = LintProblem::SimpleModificationChain(path(j));
+                self.warn(&None, &path(i), &None, problem);
+            }
+
+            self.check_output_key(&None, &path(i), &None, &sm.to);
+        }
+    }
+
+    fn check_output_key(
+        &mut self,
+        file: &Option<PathBuf>,
+        path: &str,
+        description: &Option<String>,
+        key: &EV_KEY,
+    ) {
+        if let Some(output_keys) = self.output_keys {
+            if !output_keys.contains(key) {
+                let problem = LintProblem::UnsupportedKey(format_key(key));
+                self.warn(file, path, description, problem);
+            }
+        }
+    }
+
+    fn warn(
+        &mut self,
+        file: &Option<PathBuf>,
+        path: &str,
+        description: &Option<String>,
+        problem: LintProblem,
+    ) {
+        self.warnings.push(LintWarning {
+            profile: self.profile.to_string(),
+            file: file.clone(),
+            path: path.to_string(),
+            description: description.clone(),
+            problem,
+        });
+    }
+}
+
+/// Whether `earlier` matches every event that `later` does, so `later` never applies.
+fn shadows(
+    earlier: &ComplexManipulator,
+    later: &ComplexManipulator,
+    modifier_states: &[ModifierState],
+) -> bool {
+    // `frontmost_application_unless` conditions are always met.
+    let conditions_met = earlier.conditions.iter().all(|condition| {
+        *condition == Condition::FrontmostApplication { matching: false }
+            || later.conditions.contains(condition)
+    });
+
+    conditions_met
+        && matches_key_of(&earlier.from_event, &later.from_event)
+        && modifier_states.iter().all(|state| {
+            !state.matches(&later.from_event.modifiers)
+                || state.matches(&earlier.from_event.modifiers)
+        })
+}
+
+/// Whether `from` matches every key that `other` does.
+fn matches_key_of(from: &FromEvent, other: &FromEvent) -> bool {
+    match (&other.key, other.any) {
+        (Some(key), _) => from.matches_key(key),
+        (None, Some(any)) => from.key.is_none() && from.any == Some(any),
+        (None, None) => false,
+    }
+}
+
+/// Every combination of modifiers that can be held at once.
+fn held_modifier_states() -> Vec<ModifierState> {
+    (0..1 << HELD_MODIFIERS.len())
+        .map(|bits: u32| {
+            HELD_MODIFIERS
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| bits & (1 << i) != 0)
+                .map(|(_, modifier)| *modifier)
+                .collect()
+        })
+        .collect()
+}
+
+/// The keys a to-event sends, including the keys of its modifiers.
+fn sent_keys(to_event: &ToEvent) -> Vec<EV_KEY> {
+    let modifier_keys = to_event.modifiers.iter().filter_map(|m| m.as_key());
+    to_event.key.iter().cloned().chain(modifier_keys).collect()
+}
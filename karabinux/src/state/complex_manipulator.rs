@@ -1,48 +1,142 @@
-use crate::karabiner::{KBComplexModifications, KBManipulator, KBManipulatorKind};
+use crate::error::{collect_results, ConfigError, ConfigProblem};
+use crate::karabiner::{
+    KBComplexModifications, KBComplexRule, KBManipulator, KBManipulatorKind, KBToDefinition,
+};
 use crate::key_state::KeyState;
-use crate::state::{FromEvent, FromModifier, ModifierState, ToEvent};
+use crate::state::{
+    Condition, DeviceIdentifiers, FromEvent, FromModifier, ModifierState, MouseKeyState,
+    Parameters, ToEvent, Variables,
+};
 use crate::util::event_time_now;
 use evdev_rs::enums::EventCode;
+use evdev_rs::enums::EV_KEY;
 use evdev_rs::{InputEvent, TimeVal};
 use std::collections::HashSet;
+use std::path::PathBuf;
+use std::process::Command;
 
 #[derive(Debug)]
 pub struct ComplexManipulator {
     pub description: Option<String>,
     pub from_event: FromEvent,
     pub to_events: Vec<ToEvent>,
+    pub to_if_alone: Vec<ToEvent>,
+    pub to_if_held_down: Vec<ToEvent>,
+    pub to_after_key_up: Vec<ToEvent>,
+    pub conditions: Vec<Condition>,
+    pub parameters: Parameters,
 }
 
 impl ComplexManipulator {
-    pub fn new(manipulator: &KBManipulator) -> ComplexManipulator {
+    /// Creates a `ComplexManipulator`, `parameters` are the profile's parameters
+    /// which are overridden by any set on the manipulator itself.
+    pub fn new(
+        manipulator: &KBManipulator,
+        parameters: &Parameters,
+    ) -> Result<ComplexManipulator, Vec<ConfigProblem>> {
         match manipulator.kind {
             KBManipulatorKind::Basic => {
                 let description = manipulator.description.clone();
                 let from_event = FromEvent::from_config(&manipulator.from);
-                let mut to_events = vec![];
-                if let Some(to_config_list) = &manipulator.to {
-                    for to_event_config in to_config_list {
-                        to_events.push(ToEvent::from_config(to_event_config));
-                    }
-                }
+                let to_events = to_events_from_config(&manipulator.to);
+                let to_if_alone = to_events_from_config(&manipulator.to_if_alone);
+                let to_if_held_down = to_events_from_config(&manipulator.to_if_held_down);
+                let to_after_key_up = to_events_from_config(&manipulator.to_after_key_up);
+                let conditions = collect_results(
+                    manipulator
+                        .conditions
+                        .iter()
+                        .flatten()
+                        .map(Condition::from_config),
+                );
+                let parameters = match &manipulator.parameters {
+                    Some(kb_parameters) => parameters.merge(kb_parameters),
+                    None => *parameters,
+                };
 
-                ComplexManipulator {
-                    description,
+                match (
                     from_event,
                     to_events,
+                    to_if_alone,
+                    to_if_held_down,
+                    to_after_key_up,
+                    conditions,
+                ) {
+                    (
+                        Ok(from_event),
+                        Ok(to_events),
+                        Ok(to_if_alone),
+                        Ok(to_if_held_down),
+                        Ok(to_after_key_up),
+                        Ok(conditions),
+                    ) => Ok(ComplexManipulator {
+                        description,
+                        from_event,
+                        to_events,
+                        to_if_alone,
+                        to_if_held_down,
+                        to_after_key_up,
+                        conditions,
+                        parameters,
+                    }),
+                    (
+                        from_event,
+                        to_events,
+                        to_if_alone,
+                        to_if_held_down,
+                        to_after_key_up,
+                        conditions,
+                    ) => Err(from_event
+                        .err()
+                        .into_iter()
+                        .chain(to_events.err())
+                        .chain(to_if_alone.err())
+                        .chain(to_if_held_down.err())
+                        .chain(to_after_key_up.err())
+                        .chain(conditions.err())
+                        .flatten()
+                        .collect()),
                 }
             }
         }
     }
 
+    /// Creates the manipulators for every rule, or returns the problems with all
+    /// of them along with the rule and manipulator they were found in.
     pub fn manipulators_from_config(
         kb_complex_modifications: &KBComplexModifications,
-    ) -> Vec<ComplexManipulator> {
-        kb_complex_modifications
+    ) -> Result<Vec<ComplexManipulator>, Vec<ConfigError>> {
+        let parameters = match &kb_complex_modifications.parameters {
+            Some(kb_parameters) => Parameters::default().merge(kb_parameters),
+            None => Parameters::default(),
+        };
+
+        let results = kb_complex_modifications
             .rules
             .iter()
-            .flat_map(|rule| rule.manipulators.iter().map(|m| ComplexManipulator::new(m)))
-            .collect()
+            .enumerate()
+            .flat_map(|(i, rule)| {
+                rule.manipulators
+                    .iter()
+                    .enumerate()
+                    .map(move |(j, m)| (i, rule, j, m))
+            })
+            .map(|(i, rule, j, m)| {
+                ComplexManipulator::new(m, &parameters).map_err(|problems| {
+                    let (file, path) = manipulator_location(i, rule, j);
+                    problems
+                        .into_iter()
+                        .map(|problem| ConfigError {
+                            file: file.clone(),
+                            rule: Some(i),
+                            description: m.description.clone(),
+                            ..ConfigError::new(path.clone(), problem)
+                        })
+                        .collect()
+                })
+            });
+
+        collect_results(results)
     }
 
     pub fn matches(&self, mod_state: &ModifierState, event: &InputEvent) -> bool {
@@ -50,64 +144,203 @@ impl ComplexManipulator {
             return false;
         }
 
-        if let Some(ref from_key) = self.from_event.key {
-            if let EventCode::EV_KEY(ref ev_key) = event.event_code {
-                if from_key == ev_key {
-                    return true;
-                }
-            }
+        if let EventCode::EV_KEY(ref ev_key) = event.event_code {
+            return self.from_event.matches_key(ev_key);
         }
 
         false
     }
 
+    /// Whether `pressed` (in the order they were pressed) could be the start of the
+    /// manipulator's `simultaneous` keys, or are all of them.
+    pub fn matches_simultaneous(&self, mod_state: &ModifierState, pressed: &[EV_KEY]) -> bool {
+        match &self.from_event.simultaneous {
+            Some(simultaneous) => {
+                mod_state.matches(&self.from_event.modifiers) && simultaneous.starts_with(pressed)
+            }
+            None => false,
+        }
+    }
+
+    /// Check whether all of the manipulator's conditions are met.
+    pub fn conditions_met(&self, variables: &Variables, device: &DeviceIdentifiers) -> bool {
+        self.conditions
+            .iter()
+            .all(|condition| condition.is_met(variables, device))
+    }
+
     pub fn apply(
         &self,
         mod_state: &ModifierState,
+        mouse_key_state: &mut MouseKeyState,
         event: &InputEvent,
         output_queue: &mut Vec<InputEvent>,
     ) {
+        let from_key = match &event.event_code {
+            EventCode::EV_KEY(key) => key,
+            _ => return,
+        };
+
         let now = event_time_now();
         let key_state = KeyState::from(event.value);
 
-        for to_event in &self.to_events {
+        // Clear current mandatory modifiers.
+        if key_state == KeyState::Pressed {
+            output_queue.extend(self.cancel_mandatory_from_modifiers(&now, mod_state));
+        }
+
+        self.emit_to_events(
+            &self.to_events,
+            mouse_key_state,
+            from_key,
+            &now,
+            key_state,
+            output_queue,
+        );
+    }
+
+    /// Emits the `to_if_alone` events, which are sent when the key is released
+    /// before `to_if_alone_timeout`, without any other keys being pressed.
+    pub fn apply_to_if_alone(
+        &self,
+        mouse_key_state: &mut MouseKeyState,
+        from_key: &EV_KEY,
+        output_queue: &mut Vec<InputEvent>,
+    ) {
+        let now = event_time_now();
+        for &key_state in &[KeyState::Pressed, KeyState::Released] {
+            self.emit_to_events(
+                &self.to_if_alone,
+                mouse_key_state,
+                from_key,
+                &now,
+                key_state,
+                output_queue,
+            );
+        }
+    }
+
+    /// Emits the `to_after_key_up` events, which are sent after the key is released.
+    pub fn apply_to_after_key_up(
+        &self,
+        mouse_key_state: &mut MouseKeyState,
+        from_key: &EV_KEY,
+        output_queue: &mut Vec<InputEvent>,
+    ) {
+        let now = event_time_now();
+        for &key_state in &[KeyState::Pressed, KeyState::Released] {
+            self.emit_to_events(
+                &self.to_after_key_up,
+                mouse_key_state,
+                from_key,
+                &now,
+                key_state,
+                output_queue,
+            );
+        }
+    }
+
+    /// Emits the `simultaneous_options.to_after_key_up` events, which are sent after
+    /// all of the manipulator's `simultaneous` keys are released.
+    pub fn apply_simultaneous_to_after_key_up(
+        &self,
+        mouse_key_state: &mut MouseKeyState,
+        from_key: &EV_KEY,
+        output_queue: &mut Vec<InputEvent>,
+    ) {
+        let to_after_key_up = match &self.from_event.simultaneous {
+            Some(simultaneous) => &simultaneous.to_after_key_up,
+            None => return,
+        };
+
+        let now = event_time_now();
+        for &key_state in &[KeyState::Pressed, KeyState::Released] {
+            self.emit_to_events(
+                to_after_key_up,
+                mouse_key_state,
+                from_key,
+                &now,
+                key_state,
+                output_queue,
+            );
+        }
+    }
+
+    /// Emits the `to_if_held_down` events, which are pressed when the key is held
+    /// for `to_if_held_down_threshold` (without any other keys being pressed),
+    /// and are released when the key is released.
+    pub fn apply_to_if_held_down(
+        &self,
+        mouse_key_state: &mut MouseKeyState,
+        from_key: &EV_KEY,
+        key_state: KeyState,
+        output_queue: &mut Vec<InputEvent>,
+    ) {
+        let now = event_time_now();
+        self.emit_to_events(
+            &self.to_if_held_down,
+            mouse_key_state,
+            from_key,
+            &now,
+            key_state,
+            output_queue,
+        );
+    }
+
+    fn emit_to_events(
+        &self,
+        to_events: &[ToEvent],
+        mouse_key_state: &mut MouseKeyState,
+        from_key: &EV_KEY,
+        now: &TimeVal,
+        key_state: KeyState,
+        output_queue: &mut Vec<InputEvent>,
+    ) {
+        for to_event in to_events {
             match key_state {
                 KeyState::Pressed => {
-                    // Clear current mandatory modifiers.
-                    output_queue.extend(self.cancel_mandatory_from_modifiers(&now, mod_state));
-
                     // Wrap the emitted key event in modifiers from the "to_event" definition.
                     output_queue.extend(self.get_to_event_modifiers(
-                        &now,
-                        &to_event,
+                        now,
+                        to_event,
                         KeyState::Pressed,
                     ));
 
                     // If there's a "to" key event, send the release modifiers with it.
-                    if let Some(event) = to_event.key_event(&now, key_state) {
+                    if let Some(event) = to_event.key_event(from_key, now, key_state) {
                         output_queue.push(event);
                         output_queue.extend(self.get_to_event_modifiers(
-                            &now,
-                            &to_event,
+                            now,
+                            to_event,
                             KeyState::Released,
                         ));
                     }
+
+                    // Start moving the pointer if there's a mouse key (it's stopped when the key is released).
+                    if let Some(mouse_key) = &to_event.mouse_key {
+                        mouse_key_state.press(from_key.clone(), mouse_key.clone());
+                    }
+
+                    // Call shell command if one is defined.
+                    if let Some(shell_cmd) = &to_event.shell_command {
+                        run_shell_command(shell_cmd);
+                    }
                 }
                 KeyState::Released => {
                     // If there's a "to" key event, just release it (already manipulated).
                     // Otherwise, send the release modifiers.
-                    if let Some(event) = to_event.key_event(&now, key_state) {
+                    if let Some(event) = to_event.key_event(from_key, now, key_state) {
                         output_queue.push(event);
                     } else {
                         output_queue.extend(self.get_to_event_modifiers(
-                            &now,
-                            &to_event,
+                            now,
+                            to_event,
                             KeyState::Released,
                         ));
                     }
                 }
                 KeyState::Autorepeat => {
-                    if let Some(event) = to_event.key_event(&now, key_state) {
+                    if let Some(event) = to_event.key_event(from_key, now, key_state) {
                         if to_event.repeat {
                             output_queue.push(event);
                         }
@@ -115,11 +348,6 @@ impl ComplexManipulator {
                 }
                 _ => {}
             }
-
-            // Call shell command if one is defined.
-            if let Some(shell_cmd) = &to_event.shell_command {
-                run_shell_command(&shell_cmd);
-            }
         }
     }
 
@@ -143,7 +371,7 @@ impl ComplexManipulator {
             if mod_state.is_active(*from_modifier) {
                 for key in mod_state.keys_for_modifier(*from_modifier) {
                     let code = EventCode::EV_KEY(key);
-                    let event = InputEvent::new(&now, &code, KeyState::Released.into());
+                    let event = InputEvent::new(now, &code, KeyState::Released.into());
                     events.push(event);
                     emitted_modifiers.insert(from_modifier);
                 }
@@ -165,18 +393,55 @@ impl ComplexManipulator {
             .filter_map(|modifier| {
                 modifier
                     .as_key()
-                    .map(|key| InputEvent::new(&now, &EventCode::EV_KEY(key), key_state.into()))
+                    .map(|key| InputEvent::new(now, &EventCode::EV_KEY(key), key_state.into()))
             })
             .collect()
     }
 }
 
+/// The file and path of the `j`th manipulator in the `i`th rule, which is in the
+/// file it was included from (if it was).
+pub(crate) fn manipulator_location(
+    i: usize,
+    rule: &KBComplexRule,
+    j: usize,
+) -> (Option<PathBuf>, String) {
+    rule_field_location(i, rule, "manipulators", j)
+}
+
+/// The file and path of the `j`th item in the `field` list of the `i`th rule, eg:
+/// its manipulators or tests, which is in the file it was included from (if it was).
+pub(crate) fn rule_field_location(
+    i: usize,
+    rule: &KBComplexRule,
+    field: &str,
+    j: usize,
+) -> (Option<PathBuf>, String) {
+    match &rule.source {
+        Some(source) => (
+            Some(source.path.clone()),
+            format!("rules[{}].{}[{}]", source.index, field, j),
+        ),
+        None => (
+            None,
+            format!("complex_modifications.rules[{}].{}[{}]", i, field, j),
+        ),
+    }
+}
+
+fn to_events_from_config(
+    kb_to_list: &Option<Vec<KBToDefinition>>,
+) -> Result<Vec<ToEvent>, Vec<ConfigProblem>> {
+    collect_results(kb_to_list.iter().flatten().map(ToEvent::from_config))
+}
+
+/// Runs a `shell_command` in the background. Like Karabiner, it's run by the shell,
+/// so it can have arguments, quotes, pipes and so on.
 fn run_shell_command(shell_cmd: &str) {
-    use std::process::Command;
     use std::thread;
 
     let shell_cmd = shell_cmd.to_string();
-    thread::spawn(move || match Command::new(&shell_cmd).status() {
+    thread::spawn(move || match shell_command(&shell_cmd).status() {
         Ok(status) => {
             if !status.success() {
                 eprintln!(
@@ -189,3 +454,25 @@ fn run_shell_command(shell_cmd: &str) {
         Err(e) => eprintln!("{:?}", e),
     });
 }
+
+fn shell_command(shell_cmd: &str) -> Command {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(shell_cmd);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::shell_command;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn shell_commands_are_run_with_arguments() {
+        let output = shell_command("printf '%s|' one 'two words' | tr '|' ','")
+            .output()
+            .expect("failed to run shell command");
+
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "one,two words,");
+    }
+}
@@ -0,0 +1,79 @@
+use crate::error::ConfigProblem;
+use crate::karabiner::{KBCondition, KBConditionKind, KBDeviceIdentifiers};
+use crate::state::DeviceIdentifiers;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// The variables set by `set_variable` to-events, by name.
+pub type Variables = HashMap<String, Value>;
+
+/// A condition that must be met for a manipulator to apply.
+///
+/// https://pqrs.org/osx/karabiner/json.html#condition-definition
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// Met if the variable is (or isn't) set to `value`.
+    Variable {
+        name: String,
+        value: Value,
+        equal: bool,
+    },
+    /// Met if the input device matches (or doesn't match) any of `identifiers`.
+    Device {
+        identifiers: Vec<KBDeviceIdentifiers>,
+        matching: bool,
+    },
+    /// There's no way to tell which application is focused, so no application is
+    /// ever frontmost: `frontmost_application_if` conditions are never met, and
+    /// `frontmost_application_unless` conditions always are.
+    FrontmostApplication { matching: bool },
+}
+
+impl Condition {
+    pub fn from_config(kb_condition: &KBCondition) -> Result<Condition, Vec<ConfigProblem>> {
+        let variable = |equal| match (&kb_condition.name, &kb_condition.value) {
+            (Some(name), Some(value)) => Ok(Condition::Variable {
+                name: name.clone(),
+                value: value.clone(),
+                equal,
+            }),
+            _ => Err(vec![ConfigProblem::MissingVariable]),
+        };
+
+        let device = |matching| Condition::Device {
+            identifiers: kb_condition.identifiers.clone().unwrap_or_default(),
+            matching,
+        };
+
+        match kb_condition.kind {
+            KBConditionKind::VariableIf => variable(true),
+            KBConditionKind::VariableUnless => variable(false),
+            KBConditionKind::DeviceIf => Ok(device(true)),
+            KBConditionKind::DeviceUnless => Ok(device(false)),
+            KBConditionKind::FrontmostApplicationIf => {
+                Ok(Condition::FrontmostApplication { matching: true })
+            }
+            KBConditionKind::FrontmostApplicationUnless => {
+                Ok(Condition::FrontmostApplication { matching: false })
+            }
+        }
+    }
+
+    pub fn is_met(&self, variables: &Variables, device: &DeviceIdentifiers) -> bool {
+        match self {
+            Condition::Variable { name, value, equal } => {
+                // Variables that haven't been set yet are 0.
+                let current = variables
+                    .get(name)
+                    .cloned()
+                    .unwrap_or_else(|| Value::from(0));
+                (current == *value) == *equal
+            }
+            Condition::Device {
+                identifiers,
+                matching,
+            } => identifiers.iter().any(|i| device.matches(i)) == *matching,
+            Condition::FrontmostApplication { matching } => !matching,
+        }
+    }
+}
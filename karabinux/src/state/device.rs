@@ -0,0 +1,31 @@
+use crate::karabiner::KBDeviceIdentifiers;
+
+/// Describes the input device that events are read from, so the profile's
+/// `devices` settings can be matched against it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeviceIdentifiers {
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub is_keyboard: bool,
+    pub is_pointing_device: bool,
+    /// Whether this is a laptop's own keyboard, see `disable_built_in_keyboard_if_exists`.
+    pub is_built_in_keyboard: bool,
+}
+
+impl DeviceIdentifiers {
+    /// Check whether this device is matched by the given identifiers.
+    pub fn matches(&self, kb_identifiers: &KBDeviceIdentifiers) -> bool {
+        kb_identifiers
+            .vendor_id
+            .is_none_or(|id| id == self.vendor_id)
+            && kb_identifiers
+                .product_id
+                .is_none_or(|id| id == self.product_id)
+            && kb_identifiers
+                .is_keyboard
+                .is_none_or(|is| is == self.is_keyboard)
+            && kb_identifiers
+                .is_pointing_device
+                .is_none_or(|is| is == self.is_pointing_device)
+    }
+}
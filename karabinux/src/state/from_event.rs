@@ -1,27 +1,69 @@
-use crate::karabiner::{FromKBKeyCode, KBFromDefinition};
-use crate::state::FromModifiers;
+use crate::error::ConfigProblem;
+use crate::karabiner::{FromKBKeyCode, KBFromDefinition, KeyKind};
+use crate::state::{FromModifiers, Simultaneous};
 use evdev_rs::enums::EV_KEY;
 
 #[derive(Debug)]
 pub struct FromEvent {
     pub key: Option<EV_KEY>,
+    pub any: Option<KeyKind>,
     pub modifiers: FromModifiers,
+    pub simultaneous: Option<Simultaneous>,
 }
 
 impl FromEvent {
-    pub fn from_config(kb_from: &KBFromDefinition) -> FromEvent {
-        let key = if let Some(key_code) = &kb_from.key_code {
-            Some(EV_KEY::from_kb_key_code(&key_code).unwrap())
-        } else {
-            None
-        };
+    pub fn from_config(kb_from: &KBFromDefinition) -> Result<FromEvent, Vec<ConfigProblem>> {
+        let mut problems = vec![];
+
+        let key = kb_from.key_code.as_ref().and_then(|key_code| {
+            let key = EV_KEY::from_kb_key_code(key_code);
+            if key.is_none() {
+                problems.push(ConfigProblem::UnknownKeyCode(key_code.to_string()));
+            }
 
-        let modifiers = if let Some(from_modifiers) = &kb_from.modifiers {
-            FromModifiers::from_config(&from_modifiers)
-        } else {
-            FromModifiers::default()
+            key
+        });
+
+        let any = kb_from.any;
+
+        let modifiers = match &kb_from.modifiers {
+            Some(from_modifiers) => {
+                FromModifiers::from_config(from_modifiers).unwrap_or_else(|e| {
+                    problems.extend(e);
+                    FromModifiers::default()
+                })
+            }
+            None => FromModifiers::default(),
         };
 
-        FromEvent { key, modifiers }
+        let simultaneous = Simultaneous::from_config(kb_from).unwrap_or_else(|e| {
+            problems.extend(e);
+            None
+        });
+
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+
+        Ok(FromEvent {
+            key,
+            any,
+            modifiers,
+            simultaneous,
+        })
+    }
+
+    /// Check whether the given key is matched by either `key_code` or `any`, keys
+    /// in `simultaneous` are matched by the `StateManager` instead.
+    pub fn matches_key(&self, key: &EV_KEY) -> bool {
+        if let Some(ref from_key) = self.key {
+            return from_key == key;
+        }
+
+        if let Some(any) = self.any {
+            return KeyKind::from_key(key) == any;
+        }
+
+        false
     }
 }
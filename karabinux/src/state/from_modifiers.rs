@@ -1,3 +1,4 @@
+use crate::error::ConfigProblem;
 use crate::karabiner::{FromKBKeyCode, KBFromModifiers, Modifier};
 use std::collections::hash_map::Iter;
 use std::collections::HashMap;
@@ -9,33 +10,42 @@ pub enum FromModifier {
     Mandatory,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct FromModifiers {
     inner: HashMap<Modifier, FromModifier>,
 }
 
 impl FromModifiers {
-    pub fn from_config(kb_from_modifiers: &KBFromModifiers) -> FromModifiers {
+    pub fn from_config(
+        kb_from_modifiers: &KBFromModifiers,
+    ) -> Result<FromModifiers, Vec<ConfigProblem>> {
         let mut inner = HashMap::new();
-        let mut parse_key_code = |key_code: &str, fm: FromModifier| {
-            if let Some(modifier) = Modifier::from_kb_key_code(&key_code) {
-                inner.insert(modifier, fm);
-            }
-        };
+        let mut problems = vec![];
+        let mut parse_key_code =
+            |key_code: &str, fm: FromModifier| match Modifier::from_kb_key_code(key_code) {
+                Some(modifier) => {
+                    inner.insert(modifier, fm);
+                }
+                None => problems.push(ConfigProblem::UnknownModifier(key_code.to_string())),
+            };
 
         if let Some(mandatory_key_codes) = &kb_from_modifiers.mandatory {
             for key_code in mandatory_key_codes {
-                parse_key_code(&key_code, FromModifier::Mandatory);
+                parse_key_code(key_code, FromModifier::Mandatory);
             }
         }
 
         if let Some(optional_key_codes) = &kb_from_modifiers.optional {
             for key_code in optional_key_codes {
-                parse_key_code(&key_code, FromModifier::Optional);
+                parse_key_code(key_code, FromModifier::Optional);
             }
         }
 
-        FromModifiers { inner }
+        if problems.is_empty() {
+            Ok(FromModifiers { inner })
+        } else {
+            Err(problems)
+        }
     }
 
     pub fn get(&self, key: Modifier) -> Option<&FromModifier> {
@@ -59,11 +69,3 @@ impl<'a> IntoIterator for &'a FromModifiers {
         self.inner.iter()
     }
 }
-
-impl Default for FromModifiers {
-    fn default() -> FromModifiers {
-        FromModifiers {
-            inner: HashMap::new(),
-        }
-    }
-}
@@ -1,83 +1,772 @@
-use crate::karabiner::KBProfile;
-use crate::state::{ComplexManipulator, ModifierState, SimpleManipulator};
-use evdev_rs::enums::EventCode;
-use evdev_rs::InputEvent;
+use crate::error::{collect_results, ConfigError, Error};
+use crate::karabiner::{KBKeyUpWhen, KBProfile, KBSimpleModification, Modifier, VK_NONE};
+use crate::key_state::KeyState;
+use crate::state::{
+    ComplexManipulator, DeviceIdentifiers, ModifierState, MouseKeyState, SimpleManipulator,
+    ToEvent, Variables,
+};
+use crate::util::duration_between;
+use evdev_rs::enums::{EventCode, EV_KEY};
+use evdev_rs::{InputEvent, TimeVal};
+
+/// A key that was pressed and matched a complex manipulator. The same manipulator
+/// handles the key until it's released, even if it no longer matches (eg: if its
+/// modifiers were released first).
+#[derive(Debug)]
+struct ManipulatedKey {
+    /// The key that was matched, or the first of a manipulator's `simultaneous` keys.
+    key: EV_KEY,
+    /// The keys that haven't been released yet, which are all of a manipulator's
+    /// `simultaneous` keys when it has them.
+    held_keys: Vec<EV_KEY>,
+    /// The keys that have been released, in order.
+    released_keys: Vec<EV_KEY>,
+    /// The manipulator's `to` events have been released.
+    released: bool,
+    manipulator: usize,
+    pressed_at: TimeVal,
+    /// Another key was pressed while this one was held.
+    interrupted: bool,
+    /// The `to_if_held_down` events have been sent.
+    held_down: bool,
+}
 
 #[derive(Debug)]
 pub struct StateManager {
     modifier_state: ModifierState,
+    mouse_key_state: MouseKeyState,
+    manipulated_keys: Vec<ManipulatedKey>,
+    /// Key presses which may be part of a manipulator's `simultaneous` keys, which
+    /// are held back until it's known whether they are.
+    pending_keys: Vec<InputEvent>,
     simple_manipulators: Vec<SimpleManipulator>,
     complex_manipulators: Vec<ComplexManipulator>,
+    fn_function_keys: Vec<SimpleManipulator>,
+    /// Keys that were changed by `fn_function_keys` when they were pressed.
+    fn_function_keys_pressed: Vec<(EV_KEY, EV_KEY)>,
+    /// The device's events are passed through unchanged.
+    ignored: bool,
+    /// The device's events are dropped.
+    disabled: bool,
+    /// Keys that have been pressed (and not yet released) by the output events.
+    pressed_keys: Vec<EV_KEY>,
+    /// A profile that was selected by a `select_profile` to-event.
+    selected_profile: Option<String>,
+    /// The device events are read from, for `device_if` conditions.
+    device: DeviceIdentifiers,
+    /// Variables set by `set_variable` to-events, for `variable_if` conditions.
+    variables: Variables,
 }
 
 impl StateManager {
-    pub fn from_profile(kb_profile: &KBProfile) -> StateManager {
-        let simple_manipulators = kb_profile
-            .simple_modifications
-            .iter()
-            .map(|sm| SimpleManipulator::from_config(sm))
+    pub fn from_profile(kb_profile: &KBProfile) -> Result<StateManager, Error> {
+        StateManager::from_profile_for_device(kb_profile, &DeviceIdentifiers::default(), &[])
+    }
+
+    /// Creates a state for events read from `device`, which applies the profile's
+    /// `devices` settings that match it. `connected_devices` are the other input
+    /// devices, which may disable the built-in keyboard.
+    pub fn from_profile_for_device(
+        kb_profile: &KBProfile,
+        device: &DeviceIdentifiers,
+        connected_devices: &[DeviceIdentifiers],
+    ) -> Result<StateManager, Error> {
+        // Every section is checked (even for other devices), so all problems are reported at once.
+        let mut errors = vec![];
+
+        let profile_simple_manipulators = ok_or_extend(
+            simple_manipulators_from_config(
+                &kb_profile.simple_modifications,
+                "simple_modifications",
+            ),
+            &mut errors,
+        );
+
+        let profile_fn_function_keys = ok_or_extend(
+            simple_manipulators_from_config(
+                kb_profile.fn_function_keys.as_deref().unwrap_or_default(),
+                "fn_function_keys",
+            ),
+            &mut errors,
+        );
+
+        let complex_manipulators = ok_or_extend(
+            ComplexManipulator::manipulators_from_config(&kb_profile.complex_modifications),
+            &mut errors,
+        );
+
+        let mut device_simple_manipulators = vec![];
+        let mut device_fn_function_keys = vec![];
+        let kb_devices = kb_profile.devices.as_deref().unwrap_or_default();
+        for (i, kb_device) in kb_devices.iter().enumerate() {
+            let simple_manipulators = ok_or_extend(
+                simple_manipulators_from_config(
+                    kb_device
+                        .simple_modifications
+                        .as_deref()
+                        .unwrap_or_default(),
+                    &format!("devices[{}].simple_modifications", i),
+                ),
+                &mut errors,
+            );
+
+            let fn_function_keys = ok_or_extend(
+                simple_manipulators_from_config(
+                    kb_device.fn_function_keys.as_deref().unwrap_or_default(),
+                    &format!("devices[{}].fn_function_keys", i),
+                ),
+                &mut errors,
+            );
+
+            if device.matches(&kb_device.identifiers) {
+                device_simple_manipulators.extend(simple_manipulators);
+                device_fn_function_keys.extend(fn_function_keys);
+            }
+        }
+
+        if !errors.is_empty() {
+            for error in &mut errors {
+                error.profile = Some(kb_profile.name.clone());
+            }
+
+            return Err(Error::Config(errors));
+        }
+
+        // Device settings come first, so they take precedence over the profile's.
+        let simple_manipulators = device_simple_manipulators
+            .into_iter()
+            .chain(profile_simple_manipulators)
             .collect();
 
-        let complex_manipulators =
-            ComplexManipulator::manipulators_from_config(&kb_profile.complex_modifications);
+        let fn_function_keys = device_fn_function_keys
+            .into_iter()
+            .chain(profile_fn_function_keys)
+            .collect();
+
+        let ignored = kb_devices
+            .iter()
+            .filter(|d| device.matches(&d.identifiers))
+            .any(|d| d.ignore == Some(true));
+        let disabled = device.is_built_in_keyboard
+            && kb_devices
+                .iter()
+                .filter(|d| d.disable_built_in_keyboard_if_exists == Some(true))
+                .any(|d| connected_devices.iter().any(|c| c.matches(&d.identifiers)));
 
-        StateManager {
+        Ok(StateManager {
             modifier_state: ModifierState::new(),
+            mouse_key_state: MouseKeyState::new(),
+            manipulated_keys: vec![],
+            pending_keys: vec![],
             simple_manipulators,
             complex_manipulators,
-        }
+            fn_function_keys,
+            fn_function_keys_pressed: vec![],
+            ignored,
+            disabled,
+            pressed_keys: vec![],
+            selected_profile: None,
+            device: device.clone(),
+            variables: Variables::new(),
+        })
     }
 
     // https://pqrs.org/osx/karabiner/document.html#event-modification-chaining
     pub fn get_mapped_events(&mut self, mut ev: InputEvent) -> Vec<InputEvent> {
+        if self.disabled {
+            return vec![];
+        }
+
+        if self.ignored {
+            let events = vec![ev];
+            self.update_pressed_keys(&events);
+            return events;
+        }
+
         // Perform simple remapping of keys first.
         self.apply_simple_modifications(&mut ev);
 
+        // Keys that were remapped to `vk_none` are disabled.
+        if ev.event_code == EventCode::EV_KEY(VK_NONE) {
+            return vec![];
+        }
+
+        // Hold back key presses that may be part of a simultaneous manipulator.
+        let mut events = vec![];
+        if KeyState::from(ev.value) == KeyState::Pressed {
+            if !self.pending_keys.is_empty() {
+                self.pending_keys.push(ev.clone());
+                if let Some(events) = self.apply_pending_keys(&ev.time) {
+                    return events;
+                }
+
+                // The key wasn't pressed along with the pending keys, so it's handled on its own.
+                self.pending_keys.pop();
+                events.extend(self.release_pending_keys());
+            }
+
+            if self.starts_simultaneous(&ev) {
+                self.pending_keys.push(ev.clone());
+                if let Some(pending_events) = self.apply_pending_keys(&ev.time) {
+                    events.extend(pending_events);
+                    return events;
+                }
+
+                self.pending_keys.pop();
+            }
+        } else if !self.pending_keys.is_empty() {
+            events.extend(self.release_pending_keys());
+        }
+
+        events.extend(self.process_event(&ev));
+        events
+    }
+
+    /// Returns the name of the profile selected by a `select_profile` to-event,
+    /// if one was sent since this was last called.
+    pub fn take_selected_profile(&mut self) -> Option<String> {
+        self.selected_profile.take()
+    }
+
+    /// Returns the current value of a variable set by a `set_variable` to-event.
+    pub fn variable(&self, name: &str) -> Option<&serde_json::Value> {
+        self.variables.get(name)
+    }
+
+    /// The simple modifications for the device, in the order they're checked.
+    pub fn simple_manipulators(&self) -> &[SimpleManipulator] {
+        &self.simple_manipulators
+    }
+
+    /// The `fn_function_keys` for the device, in the order they're checked.
+    pub fn fn_function_keys(&self) -> &[SimpleManipulator] {
+        &self.fn_function_keys
+    }
+
+    /// The complex modifications of every rule, in the order they're checked.
+    pub fn complex_manipulators(&self) -> &[ComplexManipulator] {
+        &self.complex_manipulators
+    }
+
+    /// Returns events to release every key that's currently pressed, so nothing
+    /// gets stuck when this state is replaced (eg: when switching profiles).
+    pub fn release_all_keys(&mut self, now: &TimeVal) -> Vec<InputEvent> {
+        self.mouse_key_state = MouseKeyState::new();
+        self.pending_keys.clear();
+        self.pressed_keys
+            .drain(..)
+            .rev()
+            .map(|key| InputEvent::new(now, &EventCode::EV_KEY(key), KeyState::Released.into()))
+            .collect()
+    }
+
+    /// Returns the events that depend on time passing rather than on input, ie:
+    /// pending keys which weren't pressed simultaneously, `to_if_held_down` events
+    /// and pointer events for held `mouse_key` events.
+    /// This should be called every `TICK_INTERVAL_MS` milliseconds.
+    pub fn get_timeout_events(&mut self, now: &TimeVal) -> Vec<InputEvent> {
+        // Pending keys are sent once no more keys could be pressed along with them.
+        let mut pending_events = vec![];
+        if !self.pending_keys.is_empty() && self.simultaneous_manipulator(now, false).is_none() {
+            pending_events = self.release_pending_keys();
+        }
+
+        let mut events = vec![];
+        for manipulated_key in &mut self.manipulated_keys {
+            let cm = &self.complex_manipulators[manipulated_key.manipulator];
+            if manipulated_key.interrupted
+                || manipulated_key.released
+                || manipulated_key.held_down
+                || cm.to_if_held_down.is_empty()
+            {
+                continue;
+            }
+
+            let held_for = duration_between(&manipulated_key.pressed_at, now);
+            if held_for >= cm.parameters.to_if_held_down_threshold {
+                manipulated_key.held_down = true;
+                cm.apply_to_if_held_down(
+                    &mut self.mouse_key_state,
+                    &manipulated_key.key,
+                    KeyState::Pressed,
+                    &mut events,
+                );
+                select_profile(&mut self.selected_profile, &cm.to_if_held_down);
+                set_variables(&mut self.variables, &cm.to_if_held_down);
+            }
+        }
+
+        let events = self.apply_fn_function_keys(events);
+        self.update_pressed_keys(&events);
+        pending_events.extend(events);
+        pending_events.extend(self.mouse_key_state.tick());
+        pending_events
+    }
+
+    /// Applies the complex manipulators (and everything after them) to an event,
+    /// once it's known whether it's part of a simultaneous manipulator.
+    fn process_event(&mut self, ev: &InputEvent) -> Vec<InputEvent> {
+        // Pressing any other key means held keys are no longer alone.
+        if KeyState::from(ev.value) == KeyState::Pressed {
+            self.interrupt_manipulated_keys();
+        }
+
         // Process our complex manipulators, and get the transformed events.
-        let events = self.apply_complex_modifications(&ev);
+        let mut events = self.apply_complex_modifications(ev);
+
+        // Send the released `to` events, and any `to_if_alone` or `to_if_held_down`
+        // events, for released keys.
+        events.extend(self.release_manipulated_key(ev));
+
+        // Function keys are changed last, so they also apply to complex manipulators' output.
+        let events = self.apply_fn_function_keys(events);
 
         // Update our modifier state.
-        self.update_modifiers(&ev);
+        self.update_modifiers(ev);
+
+        // Stop any mouse keys that were started by this key.
+        self.update_mouse_keys(ev);
+
+        // Keep track of pressed keys, so they can be released if the state is replaced.
+        self.update_pressed_keys(&events);
 
         // Return the transformed events.
         events
     }
 
+    /// Whether a key press is held back as the start of a simultaneous manipulator,
+    /// which is the case if it's the first manipulator the key matches.
+    fn starts_simultaneous(&self, ev: &InputEvent) -> bool {
+        let key = match &ev.event_code {
+            EventCode::EV_KEY(key) => key,
+            _ => return false,
+        };
+
+        let cm = self.complex_manipulators.iter().find(|cm| {
+            (cm.matches(&self.modifier_state, ev)
+                || cm.matches_simultaneous(&self.modifier_state, std::slice::from_ref(key)))
+                && cm.conditions_met(&self.variables, &self.device)
+        });
+
+        cm.is_some_and(|cm| cm.from_event.simultaneous.is_some())
+    }
+
+    /// The first manipulator whose `simultaneous` keys the pending keys could be
+    /// the start of (or are all of, if `complete`), if they were pressed within its
+    /// `simultaneous_threshold` of `now`.
+    fn simultaneous_manipulator(&self, now: &TimeVal, complete: bool) -> Option<usize> {
+        let keys = pending_keys(&self.pending_keys);
+        let elapsed = duration_between(&self.pending_keys.first()?.time, now);
+        self.complex_manipulators.iter().position(|cm| {
+            elapsed < cm.parameters.simultaneous_threshold
+                && cm.matches_simultaneous(&self.modifier_state, &keys)
+                && cm.conditions_met(&self.variables, &self.device)
+                && (!complete
+                    || cm
+                        .from_event
+                        .simultaneous
+                        .as_ref()
+                        .is_some_and(|s| s.is_complete(&keys)))
+        })
+    }
+
+    /// Applies the first manipulator the pending keys match, if they're all of its
+    /// keys. Returns `None` if none of them match, otherwise the pending keys are
+    /// held back until more of the manipulator's keys are pressed.
+    fn apply_pending_keys(&mut self, now: &TimeVal) -> Option<Vec<InputEvent>> {
+        let i = self.simultaneous_manipulator(now, false)?;
+        let keys = pending_keys(&self.pending_keys);
+        let simultaneous = self.complex_manipulators[i]
+            .from_event
+            .simultaneous
+            .as_ref();
+        if simultaneous.is_some_and(|s| s.is_complete(&keys)) {
+            let pending_keys = std::mem::take(&mut self.pending_keys);
+            Some(self.apply_simultaneous(i, &pending_keys))
+        } else {
+            Some(vec![])
+        }
+    }
+
+    /// Stops holding back the pending keys, applying a manipulator if they're all
+    /// of its keys (even though they could've been the start of another's), and
+    /// otherwise processing them as if they weren't pressed simultaneously.
+    fn release_pending_keys(&mut self) -> Vec<InputEvent> {
+        let now = match self.pending_keys.last() {
+            Some(ev) => ev.time.clone(),
+            None => return vec![],
+        };
+
+        let complete = self.simultaneous_manipulator(&now, true);
+        let pending_keys = std::mem::take(&mut self.pending_keys);
+        match complete {
+            Some(i) => self.apply_simultaneous(i, &pending_keys),
+            None => {
+                let mut events = vec![];
+                for ev in &pending_keys {
+                    events.extend(self.process_event(ev));
+                }
+
+                events
+            }
+        }
+    }
+
+    /// Applies a simultaneous manipulator to the presses of its keys.
+    fn apply_simultaneous(&mut self, i: usize, presses: &[InputEvent]) -> Vec<InputEvent> {
+        self.interrupt_manipulated_keys();
+
+        let keys = pending_keys(presses);
+        self.manipulated_keys.push(ManipulatedKey {
+            key: keys[0].clone(),
+            held_keys: keys,
+            released_keys: vec![],
+            released: false,
+            manipulator: i,
+            pressed_at: presses[presses.len() - 1].time.clone(),
+            interrupted: false,
+            held_down: false,
+        });
+
+        let mut events = vec![];
+        let cm = &self.complex_manipulators[i];
+        cm.apply(
+            &self.modifier_state,
+            &mut self.mouse_key_state,
+            &presses[0],
+            &mut events,
+        );
+        select_profile(&mut self.selected_profile, &cm.to_events);
+        set_variables(&mut self.variables, &cm.to_events);
+
+        let events = self.apply_fn_function_keys(events);
+        for ev in presses {
+            self.update_modifiers(ev);
+        }
+
+        self.update_pressed_keys(&events);
+        events
+    }
+
+    fn update_pressed_keys(&mut self, events: &[InputEvent]) {
+        for ev in events {
+            if let EventCode::EV_KEY(key) = &ev.event_code {
+                match KeyState::from(ev.value) {
+                    KeyState::Pressed if !self.pressed_keys.contains(key) => {
+                        self.pressed_keys.push(key.clone());
+                    }
+                    KeyState::Released => self.pressed_keys.retain(|k| k != key),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn interrupt_manipulated_keys(&mut self) {
+        for manipulated_key in &mut self.manipulated_keys {
+            manipulated_key.interrupted = true;
+        }
+    }
+
+    fn release_manipulated_key(&mut self, ev: &InputEvent) -> Vec<InputEvent> {
+        let mut events = vec![];
+        let key = match &ev.event_code {
+            EventCode::EV_KEY(key) if KeyState::from(ev.value) == KeyState::Released => key,
+            _ => return events,
+        };
+
+        let position = match self
+            .manipulated_keys
+            .iter()
+            .position(|m| m.held_keys.contains(key))
+        {
+            Some(position) => position,
+            None => return events,
+        };
+
+        let manipulated_key = &mut self.manipulated_keys[position];
+        manipulated_key.held_keys.retain(|k| k != key);
+        manipulated_key.released_keys.push(key.clone());
+
+        // The `to` events are released along with any (or all) of a manipulator's
+        // simultaneous keys, depending on its `key_up_when`.
+        let cm = &self.complex_manipulators[manipulated_key.manipulator];
+        let simultaneous = cm.from_event.simultaneous.as_ref();
+        let release = !manipulated_key.released
+            && (manipulated_key.held_keys.is_empty()
+                || simultaneous.is_none_or(|s| s.key_up_when == KBKeyUpWhen::Any));
+
+        if release {
+            manipulated_key.released = true;
+            let from_key = manipulated_key.key.clone();
+            let released = InputEvent {
+                event_code: EventCode::EV_KEY(from_key.clone()),
+                ..ev.clone()
+            };
+            cm.apply(
+                &self.modifier_state,
+                &mut self.mouse_key_state,
+                &released,
+                &mut events,
+            );
+            self.mouse_key_state.release(&from_key);
+
+            let held_for = duration_between(&manipulated_key.pressed_at, &ev.time);
+            if manipulated_key.held_down {
+                cm.apply_to_if_held_down(
+                    &mut self.mouse_key_state,
+                    &from_key,
+                    KeyState::Released,
+                    &mut events,
+                );
+            } else if !manipulated_key.interrupted && held_for < cm.parameters.to_if_alone_timeout {
+                cm.apply_to_if_alone(&mut self.mouse_key_state, &from_key, &mut events);
+                select_profile(&mut self.selected_profile, &cm.to_if_alone);
+                set_variables(&mut self.variables, &cm.to_if_alone);
+            }
+
+            cm.apply_to_after_key_up(&mut self.mouse_key_state, &from_key, &mut events);
+            select_profile(&mut self.selected_profile, &cm.to_after_key_up);
+            set_variables(&mut self.variables, &cm.to_after_key_up);
+        }
+
+        if manipulated_key.held_keys.is_empty() {
+            let manipulated_key = self.manipulated_keys.remove(position);
+            if let Some(simultaneous) =
+                simultaneous.filter(|s| s.released_in_order(&manipulated_key.released_keys))
+            {
+                cm.apply_simultaneous_to_after_key_up(
+                    &mut self.mouse_key_state,
+                    &manipulated_key.key,
+                    &mut events,
+                );
+                select_profile(&mut self.selected_profile, &simultaneous.to_after_key_up);
+                set_variables(&mut self.variables, &simultaneous.to_after_key_up);
+            }
+        }
+
+        events
+    }
+
+    fn update_mouse_keys(&mut self, ev: &InputEvent) {
+        if let EventCode::EV_KEY(key) = &ev.event_code {
+            if KeyState::from(ev.value) == KeyState::Released {
+                self.mouse_key_state.release(key);
+            }
+        }
+    }
+
     fn update_modifiers(&mut self, ev: &InputEvent) {
         self.modifier_state.update(ev);
     }
 
     fn apply_simple_modifications(&self, ev: &mut InputEvent) {
-        for sm in &self.simple_manipulators {
-            if ev.event_code == EventCode::EV_KEY(sm.from.clone()) {
-                ev.event_code = EventCode::EV_KEY(sm.to.clone());
+        // Only the first simple manipulator for a key applies, so keys can be swapped.
+        let sm = self
+            .simple_manipulators
+            .iter()
+            .find(|sm| ev.event_code == EventCode::EV_KEY(sm.from.clone()));
+
+        if let Some(sm) = sm {
+            ev.event_code = EventCode::EV_KEY(sm.to.clone());
+        }
+    }
+
+    /// Holding `fn` sends the function keys themselves rather than their
+    /// `fn_function_keys` mapping, like on a Mac.
+    fn apply_fn_function_keys(&mut self, mut events: Vec<InputEvent>) -> Vec<InputEvent> {
+        for ev in &mut events {
+            let key = match &ev.event_code {
+                EventCode::EV_KEY(key) => key.clone(),
+                _ => continue,
+            };
+
+            let to = match KeyState::from(ev.value) {
+                KeyState::Pressed if !self.modifier_state.is_active(Modifier::Fn) => {
+                    let to = self
+                        .fn_function_keys
+                        .iter()
+                        .find(|fk| fk.from == key)
+                        .map(|fk| fk.to.clone());
+
+                    if let Some(to) = &to {
+                        self.fn_function_keys_pressed.push((key, to.clone()));
+                    }
+
+                    to
+                }
+
+                // Release keys as whatever they were pressed as, even if `fn` changed since.
+                KeyState::Released => {
+                    let position = self
+                        .fn_function_keys_pressed
+                        .iter()
+                        .position(|(from, _)| *from == key);
+
+                    position.map(|i| self.fn_function_keys_pressed.remove(i).1)
+                }
+
+                _ => self
+                    .fn_function_keys_pressed
+                    .iter()
+                    .find(|(from, _)| *from == key)
+                    .map(|(_, to)| to.clone()),
+            };
+
+            if let Some(to) = to {
+                ev.event_code = EventCode::EV_KEY(to);
             }
         }
+
+        events
     }
 
-    fn apply_complex_modifications(&self, ev: &InputEvent) -> Vec<InputEvent> {
+    fn apply_complex_modifications(&mut self, ev: &InputEvent) -> Vec<InputEvent> {
         let mut output_queue = vec![];
 
-        let mut applied_manipulator = false;
-        for cm in &self.complex_manipulators {
-            if cm.matches(&self.modifier_state, &ev) {
-                cm.apply(&self.modifier_state, &ev, &mut output_queue);
+        let key = match &ev.event_code {
+            EventCode::EV_KEY(key) => key,
+            _ => return vec![ev.clone()],
+        };
+
+        let manipulator = match KeyState::from(ev.value) {
+            // Only apply the first complex manipulator that matches.
+            KeyState::Pressed => {
+                self.manipulated_keys.retain(|m| !m.held_keys.contains(key));
+                let position = self.complex_manipulators.iter().position(|cm| {
+                    cm.matches(&self.modifier_state, ev)
+                        && cm.conditions_met(&self.variables, &self.device)
+                });
 
-                // Only apply the first complex manipulator that matches.
-                applied_manipulator = true;
-                break;
+                if let Some(i) = position {
+                    self.manipulated_keys.push(ManipulatedKey {
+                        key: key.clone(),
+                        held_keys: vec![key.clone()],
+                        released_keys: vec![],
+                        released: false,
+                        manipulator: i,
+                        pressed_at: ev.time.clone(),
+                        interrupted: false,
+                        held_down: false,
+                    });
+                }
+
+                position.map(|i| (i, ev.clone()))
             }
-        }
 
-        // If no complex manipulators were applied, then just return the event.
-        if !applied_manipulator {
-            output_queue.push(ev.clone());
+            // Released keys are handled by `release_manipulated_key`.
+            KeyState::Released
+                if self
+                    .manipulated_keys
+                    .iter()
+                    .any(|m| m.held_keys.contains(key)) =>
+            {
+                return vec![]
+            }
+
+            // Otherwise use the manipulator that handled the key when it was pressed, until
+            // its `to` events are released.
+            _ => match self
+                .manipulated_keys
+                .iter()
+                .find(|m| m.held_keys.contains(key))
+            {
+                Some(m) if m.released => return vec![],
+                Some(m) => Some((
+                    m.manipulator,
+                    InputEvent {
+                        event_code: EventCode::EV_KEY(m.key.clone()),
+                        ..ev.clone()
+                    },
+                )),
+                None => None,
+            },
+        };
+
+        match manipulator {
+            Some((i, ev)) => {
+                let cm = &self.complex_manipulators[i];
+                cm.apply(
+                    &self.modifier_state,
+                    &mut self.mouse_key_state,
+                    &ev,
+                    &mut output_queue,
+                );
+
+                if KeyState::from(ev.value) == KeyState::Pressed {
+                    select_profile(&mut self.selected_profile, &cm.to_events);
+                    set_variables(&mut self.variables, &cm.to_events);
+                }
+            }
+
+            // If no complex manipulators were applied, then just return the event.
+            None => output_queue.push(ev.clone()),
         }
 
         output_queue
     }
 }
 
+/// Creates the simple manipulators in a section of the profile, eg: `simple_modifications`.
+fn simple_manipulators_from_config(
+    kb_simple_modifications: &[KBSimpleModification],
+    path: &str,
+) -> Result<Vec<SimpleManipulator>, Vec<ConfigError>> {
+    let results = kb_simple_modifications
+        .iter()
+        .enumerate()
+        .map(|(i, kb_simple_modification)| {
+            SimpleManipulator::from_config(kb_simple_modification).map_err(|problems| {
+                problems
+                    .into_iter()
+                    .map(|problem| ConfigError::new(format!("{}[{}]", path, i), problem))
+                    .collect()
+            })
+        });
+
+    collect_results(results)
+}
+
+/// Returns the values in `result`, or adds its errors to `errors`.
+fn ok_or_extend<T>(
+    result: Result<Vec<T>, Vec<ConfigError>>,
+    errors: &mut Vec<ConfigError>,
+) -> Vec<T> {
+    result.unwrap_or_else(|e| {
+        errors.extend(e);
+        vec![]
+    })
+}
+
+/// The keys of pending key presses, in the order they were pressed.
+fn pending_keys(presses: &[InputEvent]) -> Vec<EV_KEY> {
+    presses
+        .iter()
+        .filter_map(|ev| match &ev.event_code {
+            EventCode::EV_KEY(key) => Some(key.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Records the last profile selected by `to_events`, if any.
+fn select_profile(selected_profile: &mut Option<String>, to_events: &[ToEvent]) {
+    if let Some(name) = to_events
+        .iter()
+        .rev()
+        .find_map(|t| t.select_profile.clone())
+    {
+        *selected_profile = Some(name);
+    }
+}
+
+/// Sets the variables from any `set_variable` to-events, in order.
+fn set_variables(variables: &mut Variables, to_events: &[ToEvent]) {
+    for (name, value) in to_events.iter().filter_map(|t| t.set_variable.as_ref()) {
+        variables.insert(name.clone(), value.clone());
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -1,15 +1,25 @@
 pub mod complex_manipulator;
+pub mod condition;
+pub mod device;
 pub mod from_event;
 pub mod from_modifiers;
 pub mod manager;
 pub mod modifier_state;
+pub mod mouse_keys;
+pub mod parameters;
 pub mod simple_manipulator;
+pub mod simultaneous;
 pub mod to_event;
 
 pub use complex_manipulator::*;
+pub use condition::*;
+pub use device::*;
 pub use from_event::*;
 pub use from_modifiers::*;
 pub use manager::*;
 pub use modifier_state::*;
+pub use mouse_keys::*;
+pub use parameters::*;
 pub use simple_manipulator::*;
+pub use simultaneous::*;
 pub use to_event::*;
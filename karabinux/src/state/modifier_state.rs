@@ -4,6 +4,7 @@ use crate::state::{FromModifier, FromModifiers};
 use evdev_rs::enums::{EventCode, EV_KEY};
 use evdev_rs::InputEvent;
 use linked_hash_set::LinkedHashSet;
+use std::iter::FromIterator;
 
 #[derive(Debug, Default)]
 pub struct ModifierState {
@@ -28,7 +29,7 @@ impl ModifierState {
         };
 
         if let EventCode::EV_KEY(key) = &ev.event_code {
-            if let Some(modifier) = Modifier::from_key(&key) {
+            if let Some(modifier) = Modifier::from_key(key) {
                 if is_active {
                     self.inner.insert(modifier);
                 } else {
@@ -112,6 +113,8 @@ impl ModifierState {
             try_check(Modifier::RightControl);
         }
 
+        try_check(Modifier::Fn);
+
         // No from modifiers specified, but we have a modifier -> do not match.
         if pairs.is_empty() && self.any() {
             return false;
@@ -158,6 +161,16 @@ impl ModifierState {
             || self.shift()
             || self.alt()
             || self.meta()
+            || self.inner.contains(&Modifier::Fn)
+    }
+}
+
+impl FromIterator<Modifier> for ModifierState {
+    /// Creates a state where the given modifiers are active.
+    fn from_iter<I: IntoIterator<Item = Modifier>>(iter: I) -> ModifierState {
+        ModifierState {
+            inner: iter.into_iter().collect(),
+        }
     }
 }
 
@@ -183,7 +196,7 @@ mod tests {
         let mut from_modifiers = FromModifiers::default();
 
         for modifier in Modifier::VARIANTS.iter() {
-            from_modifiers.set(modifier.clone(), FromModifier::Absent);
+            from_modifiers.set(*modifier, FromModifier::Absent);
         }
 
         assert_eq!(empty_state.matches(&from_modifiers), true);
@@ -195,7 +208,7 @@ mod tests {
         let mut from_modifiers = FromModifiers::default();
 
         for modifier in Modifier::VARIANTS.iter() {
-            from_modifiers.set(modifier.clone(), FromModifier::Optional);
+            from_modifiers.set(*modifier, FromModifier::Optional);
         }
 
         assert_eq!(empty_state.matches(&from_modifiers), true);
@@ -207,7 +220,7 @@ mod tests {
         let mut from_modifiers = FromModifiers::default();
 
         for modifier in Modifier::VARIANTS.iter() {
-            from_modifiers.set(modifier.clone(), FromModifier::Mandatory);
+            from_modifiers.set(*modifier, FromModifier::Mandatory);
         }
 
         assert_eq!(empty_state.matches(&from_modifiers), false);
@@ -0,0 +1,128 @@
+use crate::karabiner::KBMouseKey;
+use crate::util::event_time_now;
+use evdev_rs::enums::{EventCode, EV_KEY, EV_REL};
+use evdev_rs::InputEvent;
+
+/// Karabiner's `x` and `y` values are scaled down by this amount to get the
+/// number of pixels the pointer moves each tick (eg: `1536` -> `12px`).
+const POINTER_FACTOR: f64 = 128.0;
+
+/// Karabiner's wheel values are scaled down by this amount to get the number of
+/// wheel "notches" scrolled each tick (eg: `32` -> `0.125` notches).
+const WHEEL_FACTOR: f64 = 256.0;
+
+/// Pointer motion starts at this fraction of its full speed...
+const ACCELERATION_START: f64 = 0.25;
+
+/// ...and reaches full speed after being held for this many ticks.
+const ACCELERATION_TICKS: u32 = 25;
+
+/// A `mouse_key` "to" event, which moves the pointer or scrolls while held.
+///
+/// https://pqrs.org/osx/karabiner/json.html#to-event-definition-mouse-key
+#[derive(Debug, Clone, PartialEq)]
+pub struct MouseKey {
+    pub x: i32,
+    pub y: i32,
+    pub vertical_wheel: i32,
+    pub horizontal_wheel: i32,
+    pub speed_multiplier: f64,
+}
+
+impl MouseKey {
+    pub fn from_config(kb_mouse_key: &KBMouseKey) -> MouseKey {
+        MouseKey {
+            x: kb_mouse_key.x.unwrap_or(0),
+            y: kb_mouse_key.y.unwrap_or(0),
+            vertical_wheel: kb_mouse_key.vertical_wheel.unwrap_or(0),
+            horizontal_wheel: kb_mouse_key.horizontal_wheel.unwrap_or(0),
+            speed_multiplier: kb_mouse_key.speed_multiplier.unwrap_or(1.0),
+        }
+    }
+}
+
+/// Keeps track of the `mouse_key` events that are currently held, and turns
+/// them into relative pointer events each time it is ticked.
+#[derive(Debug, Default)]
+pub struct MouseKeyState {
+    active: Vec<(EV_KEY, MouseKey)>,
+    ticks: u32,
+    remainder: [f64; 4],
+}
+
+impl MouseKeyState {
+    /// Creates a new `MouseKeyState` struct.
+    pub fn new() -> MouseKeyState {
+        MouseKeyState::default()
+    }
+
+    /// Activates the `MouseKey` for as long as `key` is held.
+    pub fn press(&mut self, key: EV_KEY, mouse_key: MouseKey) {
+        self.active.push((key, mouse_key));
+    }
+
+    /// Deactivates all `MouseKey`s that were activated by `key`.
+    pub fn release(&mut self, key: &EV_KEY) {
+        self.active.retain(|(k, _)| k != key);
+    }
+
+    /// Check whether any `MouseKey`s are currently held.
+    pub fn is_active(&self) -> bool {
+        !self.active.is_empty()
+    }
+
+    /// Returns the `EV_REL` events that should be emitted for a single tick.
+    /// This should be called at a regular interval, see `TICK_INTERVAL_MS`.
+    pub fn tick(&mut self) -> Vec<InputEvent> {
+        if !self.is_active() {
+            self.ticks = 0;
+            self.remainder = [0.0; 4];
+            return vec![];
+        }
+
+        let mut totals = [0.0; 4];
+        let mut speed_multiplier = 1.0;
+        for (_, mouse_key) in &self.active {
+            totals[0] += f64::from(mouse_key.x);
+            totals[1] += f64::from(mouse_key.y);
+            // Karabiner's wheel directions are the opposite of Linux's.
+            totals[2] -= f64::from(mouse_key.vertical_wheel);
+            totals[3] -= f64::from(mouse_key.horizontal_wheel);
+            speed_multiplier *= mouse_key.speed_multiplier;
+        }
+
+        let progress =
+            f64::from(self.ticks.min(ACCELERATION_TICKS)) / f64::from(ACCELERATION_TICKS);
+        let acceleration = ACCELERATION_START + (1.0 - ACCELERATION_START) * progress;
+        self.ticks = self.ticks.saturating_add(1);
+
+        let now = event_time_now();
+        let codes = [
+            EV_REL::REL_X,
+            EV_REL::REL_Y,
+            EV_REL::REL_WHEEL,
+            EV_REL::REL_HWHEEL,
+        ];
+        let mut events = vec![];
+        for (i, code) in codes.iter().enumerate() {
+            // Only the pointer accelerates, scrolling stays at a constant speed.
+            let delta = if i < 2 {
+                totals[i] / POINTER_FACTOR * acceleration
+            } else {
+                totals[i] / WHEEL_FACTOR
+            };
+
+            // Keep track of fractional movement so slow speeds still move.
+            let value = delta * speed_multiplier + self.remainder[i];
+            let whole = value.trunc();
+            self.remainder[i] = value - whole;
+
+            if whole != 0.0 {
+                let ev_code = EventCode::EV_REL(code.clone());
+                events.push(InputEvent::new(&now, &ev_code, whole as i32));
+            }
+        }
+
+        events
+    }
+}
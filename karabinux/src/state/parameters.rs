@@ -0,0 +1,73 @@
+use crate::karabiner::KBParameters;
+use std::time::Duration;
+
+/// The timing parameters for a manipulator, with Karabiner's defaults. Parameters
+/// for things karabinux doesn't support (`to_delayed_action_delay_milliseconds` and
+/// `mouse_motion_to_scroll.speed`) are ignored.
+///
+/// https://pqrs.org/osx/karabiner/json.html#complex_modifications-parameters
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Parameters {
+    pub to_if_alone_timeout: Duration,
+    pub to_if_held_down_threshold: Duration,
+    pub simultaneous_threshold: Duration,
+}
+
+impl Parameters {
+    /// Returns a copy of these parameters, overridden by any that are set in
+    /// the given config. This is used to apply a manipulator's parameters on
+    /// top of the profile's parameters.
+    pub fn merge(&self, kb_parameters: &KBParameters) -> Parameters {
+        let millis = |value: Option<u64>, default: Duration| {
+            value.map(Duration::from_millis).unwrap_or(default)
+        };
+
+        Parameters {
+            to_if_alone_timeout: millis(
+                kb_parameters.to_if_alone_timeout_milliseconds,
+                self.to_if_alone_timeout,
+            ),
+            to_if_held_down_threshold: millis(
+                kb_parameters.to_if_held_down_threshold_milliseconds,
+                self.to_if_held_down_threshold,
+            ),
+            simultaneous_threshold: millis(
+                kb_parameters.simultaneous_threshold_milliseconds,
+                self.simultaneous_threshold,
+            ),
+        }
+    }
+}
+
+impl Default for Parameters {
+    fn default() -> Parameters {
+        Parameters {
+            to_if_alone_timeout: Duration::from_millis(1000),
+            to_if_held_down_threshold: Duration::from_millis(500),
+            simultaneous_threshold: Duration::from_millis(50),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::karabiner::KBParameters;
+    use crate::state::Parameters;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    #[test]
+    fn merge_overrides_only_set_parameters() {
+        let kb_parameters = KBParameters {
+            to_if_alone_timeout_milliseconds: Some(200),
+            ..KBParameters::default()
+        };
+
+        let parameters = Parameters::default().merge(&kb_parameters);
+        assert_eq!(parameters.to_if_alone_timeout, Duration::from_millis(200));
+        assert_eq!(
+            parameters.to_if_held_down_threshold,
+            Parameters::default().to_if_held_down_threshold
+        );
+    }
+}
@@ -1,4 +1,5 @@
-use crate::karabiner::{FromKBKeyCode, KBSimpleModification};
+use crate::error::ConfigProblem;
+use crate::karabiner::{FromKBKeyCode, KBSimpleModification, KBSimpleRule};
 use evdev_rs::enums::EV_KEY;
 
 /// A `SimpleManipulator` is just a simple remapping between two keys.
@@ -9,10 +10,24 @@ pub struct SimpleManipulator {
 }
 
 impl SimpleManipulator {
-    pub fn from_config(kb_simple_modification: &KBSimpleModification) -> SimpleManipulator {
-        SimpleManipulator {
-            from: EV_KEY::from_kb_key_code(&kb_simple_modification.from.key_code).unwrap(),
-            to: EV_KEY::from_kb_key_code(&kb_simple_modification.to.key_code).unwrap(),
+    pub fn from_config(
+        kb_simple_modification: &KBSimpleModification,
+    ) -> Result<SimpleManipulator, Vec<ConfigProblem>> {
+        let from = key_from_rule(&kb_simple_modification.from);
+        let to = key_from_rule(&kb_simple_modification.to);
+
+        match (from, to) {
+            (Ok(from), Ok(to)) => Ok(SimpleManipulator { from, to }),
+            (from, to) => Err(from.err().into_iter().chain(to.err()).collect()),
         }
     }
 }
+
+fn key_from_rule(kb_simple_rule: &KBSimpleRule) -> Result<EV_KEY, ConfigProblem> {
+    let key_code = kb_simple_rule
+        .key_code()
+        .ok_or(ConfigProblem::MissingKeyCode)?;
+
+    EV_KEY::from_kb_key_code(key_code)
+        .ok_or_else(|| ConfigProblem::UnknownKeyCode(key_code.to_string()))
+}
@@ -0,0 +1,137 @@
+use crate::error::{collect_results, ConfigProblem};
+use crate::karabiner::{FromKBKeyCode, KBFromDefinition, KBKeyOrder, KBKeyUpWhen};
+use crate::state::ToEvent;
+use evdev_rs::enums::EV_KEY;
+
+/// Keys which must be pressed within `simultaneous_threshold` of each other for a
+/// manipulator to apply, see `from.simultaneous`.
+///
+/// https://pqrs.org/osx/karabiner/json.html#from-event-definition-simultaneous
+#[derive(Debug)]
+pub struct Simultaneous {
+    pub keys: Vec<EV_KEY>,
+    pub key_down_order: KBKeyOrder,
+    pub key_up_order: KBKeyOrder,
+    pub key_up_when: KBKeyUpWhen,
+    /// Sent once all of the keys are released (in `key_up_order`).
+    pub to_after_key_up: Vec<ToEvent>,
+}
+
+impl Simultaneous {
+    pub fn from_config(
+        kb_from: &KBFromDefinition,
+    ) -> Result<Option<Simultaneous>, Vec<ConfigProblem>> {
+        let kb_simultaneous = match &kb_from.simultaneous {
+            Some(kb_simultaneous) => kb_simultaneous,
+            None => return Ok(None),
+        };
+
+        // Only keys can be pressed simultaneously, not eg: `any`.
+        let keys = collect_results(kb_simultaneous.iter().map(|kb_key| {
+            let key_code = kb_key
+                .key_code
+                .as_ref()
+                .ok_or_else(|| vec![ConfigProblem::MissingKeyCode])?;
+            EV_KEY::from_kb_key_code(key_code)
+                .ok_or_else(|| vec![ConfigProblem::UnknownKeyCode(key_code.to_string())])
+        }));
+
+        let options = kb_from.simultaneous_options.as_ref();
+        let to_after_key_up = collect_results(
+            options
+                .and_then(|o| o.to_after_key_up.as_ref())
+                .into_iter()
+                .flatten()
+                .map(ToEvent::from_config),
+        );
+
+        match (keys, to_after_key_up) {
+            (Ok(keys), Ok(to_after_key_up)) => Ok(Some(Simultaneous {
+                keys,
+                key_down_order: options
+                    .and_then(|o| o.key_down_order)
+                    .unwrap_or(KBKeyOrder::Insensitive),
+                key_up_order: options
+                    .and_then(|o| o.key_up_order)
+                    .unwrap_or(KBKeyOrder::Insensitive),
+                key_up_when: options
+                    .and_then(|o| o.key_up_when)
+                    .unwrap_or(KBKeyUpWhen::Any),
+                to_after_key_up,
+            })),
+            (keys, to_after_key_up) => Err(keys
+                .err()
+                .into_iter()
+                .chain(to_after_key_up.err())
+                .flatten()
+                .collect()),
+        }
+    }
+
+    /// Whether `pressed` (in the order they were pressed) could be the start of
+    /// these keys, so more of them may still be pressed.
+    pub fn starts_with(&self, pressed: &[EV_KEY]) -> bool {
+        pressed.len() <= self.keys.len()
+            && pressed.iter().all(|key| self.keys.contains(key))
+            && !pressed
+                .iter()
+                .enumerate()
+                .any(|(i, key)| pressed[..i].contains(key))
+            && self.in_order(self.key_down_order, pressed)
+    }
+
+    /// Whether `pressed` (in the order they were pressed) are all of these keys.
+    pub fn is_complete(&self, pressed: &[EV_KEY]) -> bool {
+        pressed.len() == self.keys.len() && self.starts_with(pressed)
+    }
+
+    /// Whether `released` (all of the keys, in the order they were released) were
+    /// released in `key_up_order`.
+    pub fn released_in_order(&self, released: &[EV_KEY]) -> bool {
+        self.in_order(self.key_up_order, released)
+    }
+
+    fn in_order(&self, order: KBKeyOrder, keys: &[EV_KEY]) -> bool {
+        match order {
+            KBKeyOrder::Insensitive => true,
+            KBKeyOrder::Strict => self.keys.iter().zip(keys).all(|(a, b)| a == b),
+            KBKeyOrder::StrictInverse => self.keys.iter().rev().zip(keys).all(|(a, b)| a == b),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d_and_j(key_down_order: KBKeyOrder) -> Simultaneous {
+        Simultaneous {
+            keys: vec![EV_KEY::KEY_D, EV_KEY::KEY_J],
+            key_down_order,
+            key_up_order: KBKeyOrder::Insensitive,
+            key_up_when: KBKeyUpWhen::Any,
+            to_after_key_up: vec![],
+        }
+    }
+
+    #[test]
+    fn insensitive_keys_can_be_pressed_in_any_order() {
+        let simultaneous = d_and_j(KBKeyOrder::Insensitive);
+        assert!(simultaneous.starts_with(&[EV_KEY::KEY_J]));
+        assert!(simultaneous.is_complete(&[EV_KEY::KEY_J, EV_KEY::KEY_D]));
+        assert!(!simultaneous.starts_with(&[EV_KEY::KEY_K]));
+        assert!(!simultaneous.is_complete(&[EV_KEY::KEY_J, EV_KEY::KEY_J]));
+    }
+
+    #[test]
+    fn strict_keys_must_be_pressed_in_order() {
+        let simultaneous = d_and_j(KBKeyOrder::Strict);
+        assert!(simultaneous.starts_with(&[EV_KEY::KEY_D]));
+        assert!(!simultaneous.starts_with(&[EV_KEY::KEY_J]));
+        assert!(simultaneous.is_complete(&[EV_KEY::KEY_D, EV_KEY::KEY_J]));
+
+        let simultaneous = d_and_j(KBKeyOrder::StrictInverse);
+        assert!(!simultaneous.starts_with(&[EV_KEY::KEY_D]));
+        assert!(simultaneous.is_complete(&[EV_KEY::KEY_J, EV_KEY::KEY_D]));
+    }
+}
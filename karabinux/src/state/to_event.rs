@@ -1,48 +1,92 @@
-use crate::karabiner::{FromKBKeyCode, KBToDefinition, Modifier};
+use crate::error::ConfigProblem;
+use crate::karabiner::{FromKBKeyCode, KBToDefinition, Modifier, VK_NONE};
 use crate::key_state::KeyState;
+use crate::state::MouseKey;
 use evdev_rs::enums::{EventCode, EV_KEY};
 use evdev_rs::{InputEvent, TimeVal};
+use serde_json::Value;
 
 #[derive(Debug)]
 pub struct ToEvent {
     pub key: Option<EV_KEY>,
     pub modifiers: Vec<Modifier>,
     pub shell_command: Option<String>,
+    pub mouse_key: Option<MouseKey>,
     pub repeat: bool,
+    pub forward: bool,
+    pub select_profile: Option<String>,
+    /// The name and value of a variable to set, see `Condition::Variable`.
+    pub set_variable: Option<(String, Value)>,
 }
 
 impl ToEvent {
-    pub fn from_config(kb_to: &KBToDefinition) -> ToEvent {
-        let key = if let Some(key_code) = &kb_to.key_code {
-            Some(EV_KEY::from_kb_key_code(&key_code).unwrap())
-        } else {
-            None
-        };
+    pub fn from_config(kb_to: &KBToDefinition) -> Result<ToEvent, Vec<ConfigProblem>> {
+        let mut problems = vec![];
 
-        let modifiers = if let Some(modifier_key_codes) = &kb_to.modifiers {
-            modifier_key_codes
-                .iter()
-                .map(|kc| Modifier::from_kb_key_code(kc).unwrap())
-                .collect()
-        } else {
-            vec![]
-        };
+        let key = kb_to
+            .key_code
+            .as_ref()
+            .and_then(|key_code| {
+                let key = EV_KEY::from_kb_key_code(key_code);
+                if key.is_none() {
+                    problems.push(ConfigProblem::UnknownKeyCode(key_code.to_string()));
+                }
+
+                key
+            })
+            .filter(|key| *key != VK_NONE);
+
+        let mut modifiers = vec![];
+        for key_code in kb_to.modifiers.iter().flatten() {
+            match Modifier::from_kb_key_code(key_code) {
+                Some(modifier) => modifiers.push(modifier),
+                None => problems.push(ConfigProblem::UnknownModifier(key_code.to_string())),
+            }
+        }
+
+        if !problems.is_empty() {
+            return Err(problems);
+        }
 
         let shell_command = kb_to.shell_command.clone();
+        let mouse_key = kb_to.mouse_key.as_ref().map(MouseKey::from_config);
         let repeat = kb_to.repeat.unwrap_or(true);
+        let forward = kb_to.forward.unwrap_or(false);
+        let select_profile = kb_to.select_profile.clone();
+        let set_variable = kb_to
+            .set_variable
+            .as_ref()
+            .map(|v| (v.name.clone(), v.value.clone()));
 
-        ToEvent {
+        Ok(ToEvent {
             key,
             modifiers,
             shell_command,
+            mouse_key,
             repeat,
-        }
+            forward,
+            select_profile,
+            set_variable,
+        })
     }
 
-    pub fn key_event(&self, time: &TimeVal, key_state: KeyState) -> Option<InputEvent> {
-        if let Some(key) = &self.key {
+    /// Returns the key event to emit, `from_key` is the key that was matched
+    /// by the manipulator (and is emitted when `forward` is set).
+    pub fn key_event(
+        &self,
+        from_key: &EV_KEY,
+        time: &TimeVal,
+        key_state: KeyState,
+    ) -> Option<InputEvent> {
+        let key = if self.forward {
+            Some(from_key)
+        } else {
+            self.key.as_ref()
+        };
+
+        if let Some(key) = key {
             let ev_code = EventCode::EV_KEY(key.clone());
-            Some(InputEvent::new(&time, &ev_code, key_state.into()))
+            Some(InputEvent::new(time, &ev_code, key_state.into()))
         } else {
             None
         }
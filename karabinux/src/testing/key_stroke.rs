@@ -0,0 +1,204 @@
+use crate::error::ConfigProblem;
+use crate::karabiner::FromKBKeyCode;
+use crate::key_state::KeyState;
+use crate::state::StateManager;
+use crate::util::format_key;
+use evdev_rs::enums::{EventCode, EV_KEY};
+use evdev_rs::{InputEvent, TimeVal};
+use std::fmt;
+use std::str::FromStr;
+
+const PRESSED: char = '↓';
+const RELEASED: char = '↑';
+const AUTOREPEAT: char = '↻';
+
+/// A key being pressed or released, written as a `key_code` followed by `↓`
+/// (pressed), `↑` (released) or `↻` (autorepeat), eg: `caps_lock↓`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyStroke {
+    pub key: EV_KEY,
+    pub state: KeyState,
+}
+
+impl KeyStroke {
+    pub fn to_event(&self, time: &TimeVal) -> InputEvent {
+        InputEvent::new(
+            time,
+            &EventCode::EV_KEY(self.key.clone()),
+            self.state.into(),
+        )
+    }
+
+    pub fn from_event(event: &InputEvent) -> Option<KeyStroke> {
+        match &event.event_code {
+            EventCode::EV_KEY(key) => Some(KeyStroke {
+                key: key.clone(),
+                state: KeyState::from(event.value),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for KeyStroke {
+    type Err = ConfigProblem;
+
+    fn from_str(s: &str) -> Result<KeyStroke, ConfigProblem> {
+        let invalid = || ConfigProblem::InvalidKeyStroke(s.to_string());
+        let trimmed = s.trim();
+        let last = trimmed.chars().last().ok_or_else(invalid)?;
+        let state = match last {
+            PRESSED => KeyState::Pressed,
+            RELEASED => KeyState::Released,
+            AUTOREPEAT => KeyState::Autorepeat,
+            _ => return Err(invalid()),
+        };
+
+        let key_code = trimmed[..trimmed.len() - last.len_utf8()].trim_end();
+        let key = EV_KEY::from_kb_key_code(key_code).ok_or_else(invalid)?;
+        Ok(KeyStroke { key, state })
+    }
+}
+
+impl fmt::Display for KeyStroke {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", format_key(&self.key))?;
+        match self.state {
+            KeyState::Pressed => write!(f, "{}", PRESSED),
+            KeyState::Released => write!(f, "{}", RELEASED),
+            KeyState::Autorepeat => write!(f, "{}", AUTOREPEAT),
+            KeyState::Unknown(value) => write!(f, "({})", value),
+        }
+    }
+}
+
+/// A step of a test's input, which is a key stroke or a wait, written as the
+/// number of milliseconds to wait for, eg: `300ms`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputStep {
+    Key(KeyStroke),
+    Wait(i64),
+}
+
+impl FromStr for InputStep {
+    type Err = ConfigProblem;
+
+    fn from_str(s: &str) -> Result<InputStep, ConfigProblem> {
+        let millis = s
+            .trim()
+            .strip_suffix("ms")
+            .and_then(|m| m.parse::<u32>().ok());
+        match millis {
+            Some(millis) => Ok(InputStep::Wait(i64::from(millis))),
+            None => s.parse().map(InputStep::Key),
+        }
+    }
+}
+
+impl fmt::Display for InputStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputStep::Key(key_stroke) => write!(f, "{}", key_stroke),
+            InputStep::Wait(millis) => write!(f, "{}ms", millis),
+        }
+    }
+}
+
+/// Sends the input to the state, starting from the epoch and running its timeouts
+/// after each wait, and returns the key strokes it sends.
+pub fn send_input(state: &mut StateManager, input: &[InputStep]) -> Vec<KeyStroke> {
+    let mut millis = 0;
+    let mut events = vec![];
+    for step in input {
+        let time = |millis: i64| TimeVal::new(millis / 1000, (millis % 1000) * 1000);
+        match step {
+            InputStep::Key(key_stroke) => {
+                events.extend(state.get_mapped_events(key_stroke.to_event(&time(millis))));
+            }
+            InputStep::Wait(wait) => {
+                millis += wait;
+                events.extend(state.get_timeout_events(&time(millis)));
+            }
+        }
+    }
+
+    events.iter().filter_map(KeyStroke::from_event).collect()
+}
+
+/// Writes the key strokes that were expected but not sent with `-`, and those that
+/// were sent but not expected with `+`, one per line.
+pub(crate) fn write_diff(
+    f: &mut fmt::Formatter,
+    expected: &[KeyStroke],
+    actual: &[KeyStroke],
+) -> fmt::Result {
+    // The longest common subsequence of each suffix of `expected` and `actual`.
+    let mut lengths = vec![vec![0; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            lengths[i][j] = if expected[i] == actual[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            write!(f, "\n      {}", expected[i])?;
+            i += 1;
+            j += 1;
+        } else if j == actual.len()
+            || (i < expected.len() && lengths[i + 1][j] >= lengths[i][j + 1])
+        {
+            write!(f, "\n    - {}", expected[i])?;
+            i += 1;
+        } else {
+            write!(f, "\n    + {}", actual[j])?;
+            j += 1;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_strokes_round_trip() {
+        for s in &["caps_lock↓", "escape↑", "a↻", "linux:KEY_PROG1↓"] {
+            let key_stroke: KeyStroke = s.parse().unwrap();
+            assert_eq!(key_stroke.to_string(), *s);
+        }
+    }
+
+    #[test]
+    fn input_steps_are_key_strokes_or_waits() {
+        assert_eq!("300ms".parse(), Ok(InputStep::Wait(300)));
+        assert_eq!(
+            "a↓".parse(),
+            Ok(InputStep::Key(KeyStroke {
+                key: EV_KEY::KEY_A,
+                state: KeyState::Pressed,
+            }))
+        );
+        assert_eq!(
+            "-1ms".parse::<InputStep>(),
+            Err(ConfigProblem::InvalidKeyStroke(String::from("-1ms")))
+        );
+    }
+
+    #[test]
+    fn key_strokes_need_a_state() {
+        for s in &["caps_lock", "", "↓", "not_a_key↑"] {
+            assert_eq!(
+                s.parse::<KeyStroke>(),
+                Err(ConfigProblem::InvalidKeyStroke(s.to_string()))
+            );
+        }
+    }
+}
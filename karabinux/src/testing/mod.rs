@@ -0,0 +1,9 @@
+pub mod key_stroke;
+pub mod rule_tests;
+pub mod test_case;
+pub mod test_result;
+
+pub use key_stroke::*;
+pub use rule_tests::*;
+pub use test_case::*;
+pub use test_result::*;
@@ -0,0 +1,64 @@
+use crate::error::{collect_results, ConfigError, Error};
+use crate::karabiner::{KBComplexRule, KBProfile, KBRuleTest};
+use crate::state::complex_manipulator::rule_field_location;
+use crate::state::StateManager;
+use crate::testing::{parse_test, TestResult};
+
+/// Runs the `tests` of every rule in the profile.
+///
+/// Returns the profile's config errors (including keys in tests that can't be
+/// parsed) if it can't be used.
+pub fn run_rule_tests(kb_profile: &KBProfile) -> Result<Vec<TestResult>, Error> {
+    StateManager::from_profile(kb_profile)?;
+
+    let rules = &kb_profile.complex_modifications.rules;
+    let tests = rules.iter().enumerate().flat_map(|(i, rule)| {
+        rule.tests
+            .iter()
+            .flatten()
+            .enumerate()
+            .map(move |(j, test)| parse_rule_test(kb_profile, i, rule, j, test))
+    });
+
+    let mut results = collect_results(tests).map_err(Error::Config)?;
+    for result in &mut results {
+        result.run(kb_profile)?;
+    }
+
+    Ok(results)
+}
+
+/// Returns the test with its keys parsed, which hasn't been run yet.
+fn parse_rule_test(
+    kb_profile: &KBProfile,
+    i: usize,
+    rule: &KBComplexRule,
+    j: usize,
+    test: &KBRuleTest,
+) -> Result<TestResult, Vec<ConfigError>> {
+    // Tests in included rules are reported in the file they came from.
+    let (file, path) = rule_field_location(i, rule, "tests", j);
+
+    let (input, expected) = parse_test(&path, &test.input, &test.output, |path, problem| {
+        ConfigError {
+            profile: Some(kb_profile.name.clone()),
+            file: file.clone(),
+            rule: Some(i),
+            description: rule.description.clone(),
+            ..ConfigError::new(path, problem)
+        }
+    })?;
+
+    Ok(TestResult {
+        profile: kb_profile.name.clone(),
+        file,
+        path,
+        description: test
+            .description
+            .clone()
+            .or_else(|| rule.description.clone()),
+        input,
+        expected,
+        actual: vec![],
+    })
+}
@@ -0,0 +1,93 @@
+use crate::error::{collect_results, ConfigError, ConfigProblem, Error};
+use crate::karabiner::{deserialize_file, KBConfig};
+use crate::testing::{parse_test, TestResult};
+use serde_derive::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// A file of test cases for a profile, which is JSON, YAML or TOML like a config
+/// file. Eg:
+///
+/// ```yaml
+/// config: karabiner.json
+/// cases:
+///   - description: Hold caps lock for control
+///     input: [caps_lock↓, 300ms, caps_lock↑]
+///     output: [left_control↓, left_control↑]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TestCaseFile {
+    /// The config file to test, relative to this file.
+    pub config: PathBuf,
+    /// The profile to test, instead of the selected profile.
+    pub profile: Option<String>,
+    /// The config's assets directory, relative to this file.
+    pub assets: Option<PathBuf>,
+    pub cases: Vec<TestCase>,
+}
+
+/// Keys to press (and how long to wait between them), and the keys the profile
+/// should send in response. See `KeyStroke` and `InputStep` for how they're written.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TestCase {
+    pub description: Option<String>,
+    pub input: Vec<String>,
+    pub output: Vec<String>,
+}
+
+/// Runs every case in the test case file at `path`, each with a new state.
+///
+/// Returns an error if the file or its config can't be read, or if any of its
+/// keys can't be parsed.
+pub fn run_test_case_file<T: AsRef<Path>>(path: T) -> Result<Vec<TestResult>, Error> {
+    let path = path.as_ref();
+    let test_case_file: TestCaseFile =
+        deserialize_file(path).map_err(|e| Error::File(path.to_path_buf(), Box::new(e)))?;
+
+    // Paths in the file are relative to it.
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let config_path = dir.join(&test_case_file.config);
+    let assets_dir = test_case_file.assets.as_ref().map(|a| dir.join(a));
+    let in_config = |e| Error::File(config_path.clone(), Box::new(e));
+    let kb_config = KBConfig::load(&config_path, assets_dir.as_deref()).map_err(in_config)?;
+
+    let profile_name = test_case_file.profile.as_deref();
+    let kb_profile = kb_config.find_profile(profile_name).ok_or_else(|| {
+        let problem = ConfigProblem::UnknownProfile(test_case_file.profile.clone());
+        Error::Config(vec![ConfigError {
+            file: Some(path.to_path_buf()),
+            ..ConfigError::new(String::from("profile"), problem)
+        }])
+    })?;
+
+    let cases = test_case_file.cases.iter().enumerate().map(|(i, case)| {
+        let path_in_file = format!("cases[{}]", i);
+        let (input, expected) =
+            parse_test(&path_in_file, &case.input, &case.output, |p, problem| {
+                ConfigError {
+                    profile: Some(kb_profile.name.clone()),
+                    file: Some(path.to_path_buf()),
+                    description: case.description.clone(),
+                    ..ConfigError::new(p, problem)
+                }
+            })?;
+
+        Ok(TestResult {
+            profile: kb_profile.name.clone(),
+            file: Some(path.to_path_buf()),
+            path: path_in_file,
+            description: case.description.clone(),
+            input,
+            expected,
+            actual: vec![],
+        })
+    });
+
+    let mut results = collect_results(cases).map_err(Error::Config)?;
+    for result in &mut results {
+        result.run(kb_profile).map_err(in_config)?;
+    }
+
+    Ok(results)
+}
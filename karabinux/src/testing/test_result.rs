@@ -0,0 +1,100 @@
+use crate::error::{collect_results, ConfigError, ConfigProblem, Error};
+use crate::karabiner::KBProfile;
+use crate::state::StateManager;
+use crate::testing::{send_input, write_diff, InputStep, KeyStroke};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// The outcome of a test, from a rule's `tests` or a test case file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestResult {
+    pub profile: String,
+    /// The file the test is in, if it's not in the config file itself.
+    pub file: Option<PathBuf>,
+    /// Eg: "complex_modifications.rules[1].tests[0]" or "cases[0]".
+    pub path: String,
+    pub description: Option<String>,
+    pub input: Vec<InputStep>,
+    pub expected: Vec<KeyStroke>,
+    pub actual: Vec<KeyStroke>,
+}
+
+impl TestResult {
+    /// Sends the input to a new state for the profile, so tests don't affect each other.
+    pub(crate) fn run(&mut self, kb_profile: &KBProfile) -> Result<(), Error> {
+        let mut state = StateManager::from_profile(kb_profile)?;
+        self.actual = send_input(&mut state, &self.input);
+        Ok(())
+    }
+
+    pub fn passed(&self) -> bool {
+        self.expected == self.actual
+    }
+}
+
+impl fmt::Display for TestResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let status = if self.passed() { "ok" } else { "FAILED" };
+        write!(f, "{}: profile \"{}\": ", status, self.profile)?;
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file.display())?;
+        }
+
+        write!(f, "{}", self.path)?;
+        if let Some(description) = &self.description {
+            write!(f, " (\"{}\")", description)?;
+        }
+
+        if !self.passed() {
+            let input: Vec<_> = self.input.iter().map(ToString::to_string).collect();
+            write!(f, "\n    input: {}", input.join(" "))?;
+            write!(f, "\n    output (- expected, + actual):")?;
+            write_diff(f, &self.expected, &self.actual)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a test's `input` and `output`, which are at `path`. `error` creates the
+/// error for a problem at a path, eg: "cases[0].input[1]".
+pub(crate) fn parse_test<F>(
+    path: &str,
+    input: &[String],
+    output: &[String],
+    error: F,
+) -> Result<(Vec<InputStep>, Vec<KeyStroke>), Vec<ConfigError>>
+where
+    F: Fn(String, ConfigProblem) -> ConfigError,
+{
+    let input = parse_all(input, |i, problem| {
+        error(format!("{}.input[{}]", path, i), problem)
+    });
+    let output = parse_all(output, |i, problem| {
+        error(format!("{}.output[{}]", path, i), problem)
+    });
+
+    match (input, output) {
+        (Ok(input), Ok(output)) => Ok((input, output)),
+        (input, output) => Err(input
+            .err()
+            .into_iter()
+            .chain(output.err())
+            .flatten()
+            .collect()),
+    }
+}
+
+fn parse_all<T, F>(values: &[String], error: F) -> Result<Vec<T>, Vec<ConfigError>>
+where
+    T: FromStr<Err = ConfigProblem>,
+    F: Fn(usize, ConfigProblem) -> ConfigError,
+{
+    collect_results(
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| value.parse().map_err(|problem| vec![error(i, problem)])),
+    )
+}
@@ -1,10 +1,11 @@
 use crate::constants::KARABINUX_DEVICE_NAME;
+use crate::karabiner::{ToKBKeyCode, LINUX_KEY_CODE_PREFIX};
 use crate::key_state::KeyState;
-use evdev_rs::enums::{EventCode, EV_SYN};
+use evdev_rs::enums::{EventCode, EV_KEY, EV_SYN};
 use evdev_rs::util::event_code_to_int;
 use evdev_rs::{Device, InputEvent, TimeVal};
 use std::fs::{read_dir, File};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 pub fn find_karabinux_uinput_device() -> Option<Device> {
     for entry in read_dir("/dev/input").expect("failed to read /dev/input directory") {
@@ -37,17 +38,37 @@ pub fn event_time_now() -> TimeVal {
     TimeVal::new(now.as_secs() as i64, i64::from(now.subsec_micros()))
 }
 
+/// Returns the time elapsed between two event times, or zero if `later` is
+/// actually earlier than `earlier`.
+pub fn duration_between(earlier: &TimeVal, later: &TimeVal) -> Duration {
+    let micros = (later.tv_sec - earlier.tv_sec) * 1_000_000 + (later.tv_usec - earlier.tv_usec);
+    if micros > 0 {
+        Duration::from_micros(micros as u64)
+    } else {
+        Duration::from_secs(0)
+    }
+}
+
 pub fn sync_event_now() -> InputEvent {
     let ev_code = EventCode::EV_SYN(EV_SYN::SYN_REPORT);
     InputEvent::new(&event_time_now(), &ev_code, 0)
 }
 
+/// Formats a key with its Karabiner name, falling back to its Linux name (in the
+/// same format that's accepted in configuration files).
+pub fn format_key(key: &EV_KEY) -> String {
+    match key.to_kb_key_code() {
+        Some(name) => name.to_string(),
+        None => format!("{}{:?}", LINUX_KEY_CODE_PREFIX, key),
+    }
+}
+
 pub fn log_event(ev: &InputEvent, log_all_events: bool) {
     match ev.event_code {
         EventCode::EV_KEY(ref key) => {
             eprintln!(
-                "\r  {:<16}{:?}({:?})",
-                format!("{:?}", key),
+                "\r  {:<24}{:?}({:?})",
+                format_key(key),
                 KeyState::from(ev.value),
                 event_code_to_int(&ev.event_code).1
             );
@@ -0,0 +1,668 @@
+use crate::error::Error;
+use crate::karabiner::{kb_key_code_from_linux_name, KBProfile};
+use serde_derive::Deserialize;
+use serde_json::{json, Value};
+use serde_yaml::{Mapping, Value as YamlValue};
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+
+/// xremap's modifier prefixes (eg: `C-a`), and the Karabiner modifiers they're
+/// matched by in a from-event and sent as in a to-event.
+const MODIFIER_PREFIXES: &[(&str, &str, &str)] = &[
+    ("SHIFT", "shift", "left_shift"),
+    ("SHIFT_L", "left_shift", "left_shift"),
+    ("SHIFT_R", "right_shift", "right_shift"),
+    ("C", "control", "left_control"),
+    ("CTRL", "control", "left_control"),
+    ("CONTROL", "control", "left_control"),
+    ("CTRL_L", "left_control", "left_control"),
+    ("CONTROL_L", "left_control", "left_control"),
+    ("CTRL_R", "right_control", "right_control"),
+    ("CONTROL_R", "right_control", "right_control"),
+    ("M", "option", "left_option"),
+    ("ALT", "option", "left_option"),
+    ("ALT_L", "left_option", "left_option"),
+    ("ALT_R", "right_option", "right_option"),
+    ("SUPER", "command", "left_command"),
+    ("WIN", "command", "left_command"),
+    ("WINDOWS", "command", "left_command"),
+    ("SUPER_L", "left_command", "left_command"),
+    ("WIN_L", "left_command", "left_command"),
+    ("SUPER_R", "right_command", "right_command"),
+    ("WIN_R", "right_command", "right_command"),
+];
+
+/// xremap's aliases for modifier keys, and the Linux keys they name.
+const KEY_ALIASES: &[(&str, &str)] = &[
+    ("SHIFT_L", "KEY_LEFTSHIFT"),
+    ("SHIFT_R", "KEY_RIGHTSHIFT"),
+    ("CONTROL_L", "KEY_LEFTCTRL"),
+    ("CTRL_L", "KEY_LEFTCTRL"),
+    ("CONTROL_R", "KEY_RIGHTCTRL"),
+    ("CTRL_R", "KEY_RIGHTCTRL"),
+    ("ALT_L", "KEY_LEFTALT"),
+    ("ALT_R", "KEY_RIGHTALT"),
+    ("SUPER_L", "KEY_LEFTMETA"),
+    ("WIN_L", "KEY_LEFTMETA"),
+    ("SUPER_R", "KEY_RIGHTMETA"),
+    ("WIN_R", "KEY_RIGHTMETA"),
+];
+
+/// Keys which still work as modifiers while a nested `remap` waits for its next key.
+const MODIFIER_KEY_CODES: &[&str] = &[
+    "left_control",
+    "left_shift",
+    "left_option",
+    "left_command",
+    "right_control",
+    "right_shift",
+    "right_option",
+    "right_command",
+];
+
+/// A part of an xremap config that couldn't be imported, or was imported with
+/// different behaviour.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XremapWarning {
+    /// Eg: "keymap[0].remap.C-x" or "modmap[1].application".
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for XremapWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// A profile imported from an xremap config.
+#[derive(Debug, Clone)]
+pub struct XremapImport {
+    pub profile: KBProfile,
+    pub warnings: Vec<XremapWarning>,
+}
+
+#[derive(Debug, Deserialize)]
+struct XremapConfig {
+    #[serde(default)]
+    modmap: Vec<XremapModmap>,
+    #[serde(default)]
+    keymap: Vec<XremapKeymap>,
+    #[serde(flatten)]
+    extra: Mapping,
+}
+
+#[derive(Debug, Deserialize)]
+struct XremapModmap {
+    name: Option<String>,
+    remap: Mapping,
+    application: Option<XremapFilter>,
+    device: Option<YamlValue>,
+    #[serde(flatten)]
+    extra: Mapping,
+}
+
+#[derive(Debug, Deserialize)]
+struct XremapKeymap {
+    name: Option<String>,
+    remap: Mapping,
+    application: Option<XremapFilter>,
+    device: Option<YamlValue>,
+    #[serde(default)]
+    exact_match: bool,
+    #[serde(flatten)]
+    extra: Mapping,
+}
+
+#[derive(Debug, Deserialize)]
+struct XremapFilter {
+    only: Option<OneOrMany>,
+    not: Option<OneOrMany>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    fn to_vec(&self) -> Vec<String> {
+        match self {
+            OneOrMany::One(s) => vec![s.clone()],
+            OneOrMany::Many(v) => v.clone(),
+        }
+    }
+}
+
+/// Reads an xremap `config.yml`, and imports it as a profile named `profile_name`.
+pub fn import_path<P: AsRef<Path>>(path: P, profile_name: &str) -> Result<XremapImport, Error> {
+    let file = File::open(path)?;
+    let value: YamlValue = serde_yaml::from_reader(file)?;
+    import_value(value, profile_name)
+}
+
+/// Imports an xremap config as a profile named `profile_name`.
+pub fn import_str(input: &str, profile_name: &str) -> Result<XremapImport, Error> {
+    let value: YamlValue = serde_yaml::from_str(input)?;
+    import_value(value, profile_name)
+}
+
+/// Converts `modmap` to simple modifications (or complex ones, when they can't be
+/// simple), and `keymap` to complex modifications.
+///
+/// https://github.com/xremap/xremap#configuration
+fn import_value(mut value: YamlValue, profile_name: &str) -> Result<XremapImport, Error> {
+    // xremap configs often share settings with YAML's merge keys (`<<: *anchor`).
+    value.apply_merge()?;
+    let config: XremapConfig = serde_yaml::from_value(value)?;
+
+    let mut importer = Importer::default();
+    for (key, _) in &config.extra {
+        // `shared` is a conventional place for YAML anchors, and isn't used by xremap.
+        if key.as_str() != Some("shared") {
+            importer.warn(
+                &yaml_to_string(key),
+                String::from("unsupported section, it was ignored"),
+            );
+        }
+    }
+
+    let mut simple_modifications = vec![];
+    let mut rules = vec![];
+    for (i, modmap) in config.modmap.iter().enumerate() {
+        let path = format!("modmap[{}]", i);
+        let (simple, manipulators) = importer.modmap(modmap, &path);
+        simple_modifications.extend(simple);
+        if !manipulators.is_empty() {
+            rules.push(rule(modmap.name.as_deref(), &path, manipulators));
+        }
+    }
+
+    for (i, keymap) in config.keymap.iter().enumerate() {
+        let path = format!("keymap[{}]", i);
+        let manipulators = importer.keymap(keymap, &path);
+        if !manipulators.is_empty() {
+            rules.push(rule(keymap.name.as_deref(), &path, manipulators));
+        }
+    }
+
+    let profile = serde_json::from_value(json!({
+        "name": profile_name,
+        "selected": true,
+        "simple_modifications": simple_modifications,
+        "complex_modifications": { "rules": rules },
+    }))?;
+
+    Ok(XremapImport {
+        profile,
+        warnings: importer.warnings,
+    })
+}
+
+#[derive(Default)]
+struct Importer {
+    warnings: Vec<XremapWarning>,
+    /// The number of variables used for nested `remap`s so far.
+    sequences: usize,
+}
+
+impl Importer {
+    fn warn(&mut self, path: &str, message: String) {
+        self.warnings.push(XremapWarning {
+            path: path.to_string(),
+            message,
+        });
+    }
+
+    /// Returns the simple modifications and complex manipulators for a `modmap`.
+    fn modmap(&mut self, modmap: &XremapModmap, path: &str) -> (Vec<Value>, Vec<Value>) {
+        let conditions = self.conditions(
+            modmap.application.as_ref(),
+            modmap.device.as_ref(),
+            &modmap.extra,
+            path,
+        );
+
+        let mut simple_modifications = vec![];
+        let mut manipulators = vec![];
+        for (from, to) in &modmap.remap {
+            let from_name = yaml_to_string(from);
+            let path = format!("{}.remap.{}", path, from_name);
+            let from_key = match self.key_code(&from_name, &path) {
+                Some(key) => key,
+                None => continue,
+            };
+
+            match to {
+                YamlValue::String(to_name) => {
+                    let to_key = match self.key_code(to_name, &path) {
+                        Some(key) => key,
+                        None => continue,
+                    };
+
+                    // Simple modifications can't have conditions.
+                    if conditions.is_empty() {
+                        simple_modifications.push(json!({
+                            "from": { "key_code": from_key },
+                            "to": { "key_code": to_key },
+                        }));
+                    } else {
+                        manipulators.push(manipulator(
+                            json!({ "key_code": from_key, "modifiers": { "optional": ["any"] } }),
+                            vec![json!({ "key_code": to_key })],
+                            &conditions,
+                        ));
+                    }
+                }
+
+                // Multi-purpose keys: `{held: Ctrl_L, alone: Esc, alone_timeout_millis: 500}`.
+                YamlValue::Mapping(map)
+                    if map.contains_key("held") || map.contains_key("alone") =>
+                {
+                    let held = self.modmap_keys(map.get("held"), &format!("{}.held", path));
+                    let alone = self.modmap_keys(map.get("alone"), &format!("{}.alone", path));
+                    let mut manipulator = manipulator(
+                        json!({ "key_code": from_key, "modifiers": { "optional": ["any"] } }),
+                        held,
+                        &conditions,
+                    );
+                    manipulator["to_if_alone"] = json!(alone);
+                    if let Some(timeout) = map.get("alone_timeout_millis").and_then(|t| t.as_u64())
+                    {
+                        manipulator["parameters"] =
+                            json!({ "basic.to_if_alone_timeout_milliseconds": timeout });
+                    }
+
+                    manipulators.push(manipulator);
+                }
+
+                _ => self.warn(
+                    &path,
+                    format!(
+                        "unsupported modmap action {}, it was ignored",
+                        yaml_to_string(to)
+                    ),
+                ),
+            }
+        }
+
+        (simple_modifications, manipulators)
+    }
+
+    /// Returns the keys for a multi-purpose key's `held` or `alone`, which can be a
+    /// key or a list of them.
+    fn modmap_keys(&mut self, value: Option<&YamlValue>, path: &str) -> Vec<Value> {
+        let names = match value {
+            Some(YamlValue::String(name)) => vec![name.clone()],
+            Some(YamlValue::Sequence(names)) => names.iter().map(yaml_to_string).collect(),
+            _ => vec![],
+        };
+
+        names
+            .iter()
+            .filter_map(|name| self.key_code(name, path))
+            .map(|key| json!({ "key_code": key }))
+            .collect()
+    }
+
+    fn keymap(&mut self, keymap: &XremapKeymap, path: &str) -> Vec<Value> {
+        let conditions = self.conditions(
+            keymap.application.as_ref(),
+            keymap.device.as_ref(),
+            &keymap.extra,
+            path,
+        );
+
+        self.remap(&keymap.remap, keymap.exact_match, &conditions, None, path)
+    }
+
+    /// Returns the manipulators for a keymap's `remap`. `sequence` is the variable
+    /// that's set while waiting for the keys in a nested `remap`, which is cleared
+    /// when one of them is pressed.
+    fn remap(
+        &mut self,
+        remap: &Mapping,
+        exact_match: bool,
+        conditions: &[Value],
+        sequence: Option<&str>,
+        path: &str,
+    ) -> Vec<Value> {
+        let mut manipulators = vec![];
+        for (from, to) in remap {
+            let from_name = yaml_to_string(from);
+            let path = format!("{}.remap.{}", path, from_name);
+            let from = match self.key_press(&from_name, &path) {
+                Some((key, modifiers)) => {
+                    let mut from = json!({ "key_code": key });
+                    let mut from_modifiers = serde_json::Map::new();
+                    if !modifiers.is_empty() {
+                        let mandatory: Vec<_> = modifiers.iter().map(|(m, _)| *m).collect();
+                        from_modifiers.insert(String::from("mandatory"), json!(mandatory));
+                    }
+                    // xremap passes other modifiers through, unless `exact_match` is set.
+                    if !exact_match {
+                        from_modifiers.insert(String::from("optional"), json!(["any"]));
+                    }
+                    if !from_modifiers.is_empty() {
+                        from["modifiers"] = Value::Object(from_modifiers);
+                    }
+
+                    from
+                }
+                None => continue,
+            };
+
+            let end_sequence = sequence.map(|name| set_variable(name, 0));
+
+            // A nested `remap` sets a variable, and its keys only apply while it's set.
+            if let Some(nested) = to.get("remap").and_then(|r| r.as_mapping()) {
+                if to.get("timeout_millis").is_some() {
+                    self.warn(
+                        &path,
+                        String::from("timeout_millis isn't supported, it was ignored"),
+                    );
+                }
+
+                self.sequences += 1;
+                let name = format!("xremap_sequence_{}", self.sequences);
+                let nested_conditions = conditions
+                    .iter()
+                    .cloned()
+                    .chain(std::iter::once(variable_if(&name)))
+                    .collect::<Vec<_>>();
+
+                manipulators.extend(self.remap(
+                    nested,
+                    exact_match,
+                    &nested_conditions,
+                    Some(&name),
+                    &path,
+                ));
+
+                // Modifiers don't end the sequence, so they can be held for the next key.
+                for key in MODIFIER_KEY_CODES {
+                    manipulators.push(manipulator(
+                        json!({ "key_code": key, "modifiers": { "optional": ["any"] } }),
+                        vec![json!({ "key_code": key })],
+                        &nested_conditions,
+                    ));
+                }
+
+                // Any other key ends the sequence, and is sent as it is.
+                manipulators.push(manipulator(
+                    json!({ "any": "key_code", "modifiers": { "optional": ["any"] } }),
+                    vec![set_variable(&name, 0), json!({ "forward": true })],
+                    &nested_conditions,
+                ));
+
+                let to = end_sequence
+                    .into_iter()
+                    .chain(std::iter::once(set_variable(&name, 1)))
+                    .collect();
+                manipulators.push(manipulator(from, to, conditions));
+                continue;
+            }
+
+            if let Some(mut to) = self.keymap_action(to, &path) {
+                to.extend(end_sequence);
+                manipulators.push(manipulator(from, to, conditions));
+            }
+        }
+
+        manipulators
+    }
+
+    /// Returns the to-events for a keymap action, which can be a key press, a list
+    /// of them, or a `launch` command.
+    fn keymap_action(&mut self, action: &YamlValue, path: &str) -> Option<Vec<Value>> {
+        match action {
+            YamlValue::String(name) => {
+                let (key, modifiers) = self.key_press(name, path)?;
+                let mut to = json!({ "key_code": key });
+                if !modifiers.is_empty() {
+                    let modifiers: Vec<_> = modifiers.iter().map(|(_, m)| *m).collect();
+                    to["modifiers"] = json!(modifiers);
+                }
+
+                Some(vec![to])
+            }
+            YamlValue::Sequence(actions) => {
+                let mut to = vec![];
+                for action in actions {
+                    to.extend(self.keymap_action(action, path)?);
+                }
+
+                // An empty list disables the key.
+                if to.is_empty() {
+                    to.push(json!({ "key_code": "vk_none" }));
+                }
+
+                Some(to)
+            }
+            YamlValue::Mapping(map) if map.contains_key("launch") => {
+                let args = match map.get("launch") {
+                    Some(YamlValue::Sequence(args)) => {
+                        args.iter().map(yaml_to_string).collect::<Vec<_>>()
+                    }
+                    _ => {
+                        self.warn(path, String::from("launch should be a list of arguments"));
+                        return None;
+                    }
+                };
+
+                Some(vec![json!({ "shell_command": shell_command(&args) })])
+            }
+            _ => {
+                self.warn(
+                    path,
+                    format!(
+                        "unsupported action {}, it was ignored",
+                        yaml_to_string(action)
+                    ),
+                );
+                None
+            }
+        }
+    }
+
+    /// Returns the conditions for an `application` filter, and warns about any
+    /// other filters (which aren't supported).
+    fn conditions(
+        &mut self,
+        application: Option<&XremapFilter>,
+        device: Option<&YamlValue>,
+        extra: &Mapping,
+        path: &str,
+    ) -> Vec<Value> {
+        let mut conditions = vec![];
+        if let Some(application) = application {
+            self.warn(
+                &format!("{}.application", path),
+                String::from(
+                    "application filters are kept, but can't be checked on Linux: `only` never matches and `not` always does",
+                ),
+            );
+
+            let filters = [
+                ("frontmost_application_if", &application.only),
+                ("frontmost_application_unless", &application.not),
+            ];
+            for (kind, names) in &filters {
+                if let Some(names) = names {
+                    let bundle_identifiers: Vec<_> = names
+                        .to_vec()
+                        .iter()
+                        .map(|n| application_regex(n))
+                        .collect();
+                    conditions.push(json!({
+                        "type": kind,
+                        "bundle_identifiers": bundle_identifiers,
+                    }));
+                }
+            }
+        }
+
+        if device.is_some() {
+            self.warn(
+                &format!("{}.device", path),
+                String::from("device filters aren't supported, this applies to every device"),
+            );
+        }
+
+        for (key, _) in extra {
+            self.warn(
+                &format!("{}.{}", path, yaml_to_string(key)),
+                String::from("unsupported option, it was ignored"),
+            );
+        }
+
+        conditions
+    }
+
+    /// Splits a key press like `C-Shift-a` into its key and modifiers, which are
+    /// the (from, to) Karabiner names from `MODIFIER_PREFIXES`.
+    fn key_press(
+        &mut self,
+        name: &str,
+        path: &str,
+    ) -> Option<(String, Vec<(&'static str, &'static str)>)> {
+        let mut modifiers = vec![];
+        let mut rest = name;
+        while let Some(i) = rest.find('-') {
+            let prefix = rest[..i].to_uppercase();
+            match MODIFIER_PREFIXES.iter().find(|(p, _, _)| *p == prefix) {
+                Some((_, from, to)) => {
+                    modifiers.push((*from, *to));
+                    rest = &rest[i + 1..];
+                }
+                None => break,
+            }
+        }
+
+        let key = self.key_code(rest, path)?;
+        Some((key, modifiers))
+    }
+
+    /// Returns the Karabiner key code for an xremap key name, eg: `CapsLock`,
+    /// `KEY_CAPSLOCK` or `Ctrl_L`.
+    fn key_code(&mut self, name: &str, path: &str) -> Option<String> {
+        let upper = name.to_uppercase();
+        let linux_name = match KEY_ALIASES.iter().find(|(alias, _)| *alias == upper) {
+            Some((_, linux_name)) => linux_name.to_string(),
+            None if upper.starts_with("KEY_") || upper.starts_with("BTN_") => upper,
+            None => format!("KEY_{}", upper),
+        };
+
+        match kb_key_code_from_linux_name(&linux_name) {
+            Some(key_code) => Some(key_code),
+            None => {
+                self.warn(path, format!("unknown key \"{}\", it was ignored", name));
+                None
+            }
+        }
+    }
+}
+
+fn rule(name: Option<&str>, path: &str, manipulators: Vec<Value>) -> Value {
+    json!({
+        "description": name.unwrap_or(path),
+        "manipulators": manipulators,
+    })
+}
+
+fn manipulator(from: Value, to: Vec<Value>, conditions: &[Value]) -> Value {
+    let mut manipulator = json!({ "type": "basic", "from": from, "to": to });
+    if !conditions.is_empty() {
+        manipulator["conditions"] = json!(conditions);
+    }
+
+    manipulator
+}
+
+fn set_variable(name: &str, value: i32) -> Value {
+    json!({ "set_variable": { "name": name, "value": value } })
+}
+
+fn variable_if(name: &str) -> Value {
+    json!({ "type": "variable_if", "name": name, "value": 1 })
+}
+
+/// xremap matches application names exactly, unless they're written as `/regex/`.
+fn application_regex(name: &str) -> String {
+    if name.len() > 1 && name.starts_with('/') && name.ends_with('/') {
+        return name[1..name.len() - 1].to_string();
+    }
+
+    let mut regex = String::from("^");
+    for c in name.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            regex.push('\\');
+        }
+        regex.push(c);
+    }
+    regex.push('$');
+    regex
+}
+
+/// Joins the arguments of a `launch` command, quoting them for the shell.
+fn shell_command(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            let is_plain = !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_./=:,@%+".contains(c));
+            if is_plain {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', r"'\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn yaml_to_string(value: &YamlValue) -> String {
+    match value {
+        YamlValue::String(s) => s.clone(),
+        YamlValue::Number(n) => n.to_string(),
+        YamlValue::Bool(b) => b.to_string(),
+        YamlValue::Null => String::from("null"),
+        value => serde_yaml::to_string(value)
+            .unwrap_or_default()
+            .trim()
+            .to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{application_regex, shell_command};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn application_names_match_exactly() {
+        assert_eq!(application_regex("Google-chrome"), "^Google-chrome$");
+        assert_eq!(
+            application_regex("org.gnome.Nautilus"),
+            "^org\\.gnome\\.Nautilus$"
+        );
+        assert_eq!(application_regex("/^Slack/"), "^Slack");
+    }
+
+    #[test]
+    fn launch_arguments_are_quoted() {
+        let args = vec![
+            String::from("bash"),
+            String::from("-c"),
+            String::from("echo 'hi' > /tmp/test"),
+        ];
+        assert_eq!(
+            shell_command(&args),
+            r"bash -c 'echo '\''hi'\'' > /tmp/test'"
+        );
+    }
+}
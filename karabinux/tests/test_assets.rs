@@ -0,0 +1,90 @@
+mod test_util;
+
+use evdev_rs::enums::*;
+use karabinux::error::ConfigProblem;
+use karabinux::karabiner::{KBAssets, KBConfig};
+use karabinux::key_state::KeyState;
+use karabinux::state::StateManager;
+use karabinux::Error;
+use pretty_assertions::assert_eq;
+use test_util::*;
+
+const ASSETS_DIR: &str = "tests/config/assets/complex_modifications";
+const CONF_FILE_PATH: &str = "tests/config/test_assets.json";
+
+#[test]
+fn assets_are_listed_by_title() {
+    let assets = KBAssets::from_dir(ASSETS_DIR).expect("failed to load assets");
+    assert_eq!(assets.titles(), vec!["Caps Lock", "Vim Arrows"]);
+
+    let rules: Vec<_> = assets
+        .rules()
+        .into_iter()
+        .map(|(title, rule)| (title, rule.description.as_deref().unwrap()))
+        .collect();
+    assert_eq!(
+        rules,
+        vec![
+            ("Caps Lock", "Change caps_lock to escape"),
+            ("Vim Arrows", "Change left_option + h to left_arrow"),
+            ("Vim Arrows", "Change left_option + l to right_arrow"),
+        ]
+    );
+}
+
+#[test]
+fn asset_files_are_matched_by_a_glob() {
+    assert_eq!(
+        KBAssets::glob(ASSETS_DIR),
+        "tests/config/assets/complex_modifications/*.json"
+    );
+    assert_eq!(KBAssets::glob("[rules]"), "[[]rules[]]/*.json");
+}
+
+#[test]
+fn resolve_assets_appends_rules_by_title() {
+    let assets = KBAssets::from_dir(ASSETS_DIR).expect("failed to load assets");
+    let mut kb_config = KBConfig::from_path(CONF_FILE_PATH).expect("failed to load config");
+    kb_config.profiles.truncate(1);
+    kb_config
+        .resolve_assets(&assets)
+        .expect("failed to resolve assets");
+
+    let mut state = StateManager::from_profile(&kb_config.profiles[0]).unwrap();
+    test_state(
+        &mut state,
+        vec![
+            (EV_KEY::KEY_A, KeyState::Pressed),
+            (EV_KEY::KEY_A, KeyState::Released),
+            (EV_KEY::KEY_CAPSLOCK, KeyState::Pressed),
+            (EV_KEY::KEY_CAPSLOCK, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_B, KeyState::Pressed),
+            (EV_KEY::KEY_B, KeyState::Released),
+            (EV_KEY::KEY_ESC, KeyState::Pressed),
+            (EV_KEY::KEY_ESC, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn resolve_assets_reports_unknown_titles() {
+    let assets = KBAssets::from_dir(ASSETS_DIR).expect("failed to load assets");
+    let mut kb_config = KBConfig::from_path(CONF_FILE_PATH).expect("failed to load config");
+
+    let errors = match kb_config.resolve_assets(&assets) {
+        Err(Error::Config(errors)) => errors,
+        result => panic!("expected config errors, got: {:?}", result),
+    };
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].profile.as_deref(), Some("Broken profile"));
+    assert_eq!(
+        errors[0].problem,
+        ConfigProblem::UnknownAsset(String::from("Missing Rules"))
+    );
+
+    // Nothing is resolved if any title is missing.
+    assert_eq!(kb_config.profiles[0].complex_modifications.rules.len(), 1);
+}
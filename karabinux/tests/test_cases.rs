@@ -0,0 +1,59 @@
+use karabinux::error::ConfigProblem;
+use karabinux::testing::run_test_case_file;
+use karabinux::Error;
+use pretty_assertions::assert_eq;
+use std::path::PathBuf;
+
+#[test]
+fn test_cases_are_run_with_timing() {
+    let results = run_test_case_file("tests/config/cases/passing.yaml").unwrap();
+
+    let descriptions: Vec<_> = results
+        .iter()
+        .map(|r| (r.description.as_deref().unwrap(), r.passed()))
+        .collect();
+    assert_eq!(
+        descriptions,
+        vec![
+            ("Tap caps lock for escape", true),
+            ("Hold caps lock for control", true),
+            ("Hold tab for the navigation layer", true),
+        ]
+    );
+}
+
+#[test]
+fn failing_test_cases_show_a_diff() {
+    let results = run_test_case_file("tests/config/cases/failing.json").unwrap();
+
+    assert_eq!(
+        results[0].to_string(),
+        [
+            "FAILED: profile \"Passing\": tests/config/cases/failing.json: cases[0] (\"Tap caps lock for escape\")",
+            "    input: caps_lock↓ caps_lock↑",
+            "    output (- expected, + actual):",
+            "    + left_control↓",
+            "    + left_control↑",
+            "      escape↓",
+            "      escape↑",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn test_cases_for_unknown_profiles_are_reported() {
+    match run_test_case_file("tests/config/cases/invalid.toml") {
+        Err(Error::Config(errors)) => {
+            assert_eq!(
+                errors[0].file,
+                Some(PathBuf::from("tests/config/cases/invalid.toml"))
+            );
+            assert_eq!(
+                errors[0].problem,
+                ConfigProblem::UnknownProfile(Some(String::from("Missing")))
+            );
+        }
+        result => panic!("expected config errors, got: {:?}", result),
+    }
+}
@@ -0,0 +1,81 @@
+use karabinux::cheat_sheet::CheatSheet;
+use karabinux::karabiner::KBConfig;
+use karabinux::state::StateManager;
+use pretty_assertions::assert_eq;
+
+fn cheat_sheet(conf_file_name: &str) -> CheatSheet {
+    let kb_config = KBConfig::from_path(format!("tests/config/{}.json", conf_file_name))
+        .expect("failed to load config");
+    let kb_profile = &kb_config.profiles[0];
+    let state = StateManager::from_profile(kb_profile).expect("failed to construct state");
+    CheatSheet::new(kb_profile, &state)
+}
+
+#[test]
+fn cheat_sheet_groups_keys_by_rule() {
+    let markdown = cheat_sheet("test_cheat_sheet").to_markdown();
+    let expected = "# Cheat <sheet>
+
+## Simple modifications
+
+| Keys | Sends |
+| --- | --- |
+| right_command | right_option |
+
+## Function keys
+
+| Keys | Sends |
+| --- | --- |
+| f1 | display_brightness_decrement |
+
+## Caps lock & friends
+
+| Keys | Sends | Description |
+| --- | --- | --- |
+| caps_lock (optional: any) | left_control; escape (alone) | Caps lock to control, or escape when tapped |
+| left_shift + backslash | left_shift + grave_accent_and_tilde |  |
+
+## Rule 2
+
+| Keys | Sends | When |
+| --- | --- | --- |
+| any key_code | nothing | vim = 1 |
+| f1 | switch to profile \"Gaming\" |  |
+";
+
+    assert_eq!(markdown, expected);
+}
+
+#[test]
+fn cheat_sheet_shows_conditions() {
+    let cheat_sheet = cheat_sheet("test_conditions");
+    let when: Vec<_> = cheat_sheet
+        .sections
+        .iter()
+        .flat_map(|s| s.entries.iter().map(|e| e.when.join(", ")))
+        .collect();
+
+    assert_eq!(
+        when,
+        vec![
+            "",
+            "tab_layer = 1",
+            "tab_layer != 1",
+            "device is vendor_id 1 product_id 2",
+            "device isn't vendor_id 1",
+            "frontmost application (never met)",
+            "not frontmost application (always met)",
+        ]
+    );
+}
+
+#[test]
+fn cheat_sheet_html_is_escaped() {
+    let html = cheat_sheet("test_cheat_sheet").to_html();
+
+    assert!(html.contains("<h1>Cheat &lt;sheet&gt;</h1>"));
+    assert!(html.contains("<h2>Caps lock &amp; friends</h2>"));
+    assert!(
+        html.contains("<tr><td>f1</td><td>switch to profile &quot;Gaming&quot;</td><td></td></tr>")
+    );
+}
@@ -0,0 +1,144 @@
+mod test_util;
+
+use evdev_rs::enums::*;
+use karabinux::key_state::KeyState;
+use serde_json::Value;
+use test_util::*;
+
+const CONF_FILE_PATH: &str = "test_conditions";
+
+#[test]
+fn variable_if_applies_while_variable_is_set() {
+    let mut state = create_state(CONF_FILE_PATH);
+    test_state(
+        &mut state,
+        vec![
+            (EV_KEY::KEY_TAB, KeyState::Pressed),
+            (EV_KEY::KEY_H, KeyState::Pressed),
+            (EV_KEY::KEY_H, KeyState::Released),
+            (EV_KEY::KEY_TAB, KeyState::Released),
+            (EV_KEY::KEY_H, KeyState::Pressed),
+            (EV_KEY::KEY_H, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_LEFT, KeyState::Pressed),
+            (EV_KEY::KEY_LEFT, KeyState::Released),
+            (EV_KEY::KEY_H, KeyState::Pressed),
+            (EV_KEY::KEY_H, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn variable_unless_applies_while_variable_is_not_set() {
+    let mut state = create_state(CONF_FILE_PATH);
+    test_state(
+        &mut state,
+        vec![
+            (EV_KEY::KEY_L, KeyState::Pressed),
+            (EV_KEY::KEY_L, KeyState::Released),
+            (EV_KEY::KEY_TAB, KeyState::Pressed),
+            (EV_KEY::KEY_L, KeyState::Pressed),
+            (EV_KEY::KEY_L, KeyState::Released),
+            (EV_KEY::KEY_TAB, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_M, KeyState::Pressed),
+            (EV_KEY::KEY_M, KeyState::Released),
+            (EV_KEY::KEY_L, KeyState::Pressed),
+            (EV_KEY::KEY_L, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn set_variable_is_reset_by_to_after_key_up() {
+    let mut state = create_state(CONF_FILE_PATH);
+    assert_eq!(state.variable("tab_layer"), None);
+
+    send_key(&mut state, EV_KEY::KEY_TAB, KeyState::Pressed);
+    assert_eq!(state.variable("tab_layer"), Some(&Value::from(1)));
+
+    let events = send_key(&mut state, EV_KEY::KEY_TAB, KeyState::Released);
+    assert_eq!(state.variable("tab_layer"), Some(&Value::from(0)));
+    assert_eq!(
+        map_events_to_key_and_state(events),
+        vec![
+            (EV_KEY::KEY_TAB, KeyState::Pressed),
+            (EV_KEY::KEY_TAB, KeyState::Released),
+        ]
+    );
+}
+
+#[test]
+fn device_if_applies_to_matching_device() {
+    let mut state = create_state_for_device(CONF_FILE_PATH, &keyboard(1, 2), &[]);
+    test_state(
+        &mut state,
+        vec![
+            (EV_KEY::KEY_A, KeyState::Pressed),
+            (EV_KEY::KEY_A, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_B, KeyState::Pressed),
+            (EV_KEY::KEY_B, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn device_unless_applies_to_other_devices() {
+    for device in &[keyboard(1, 3), keyboard(4, 2)] {
+        let expected = if device.vendor_id == 1 {
+            vec![
+                (EV_KEY::KEY_A, KeyState::Pressed),
+                (EV_KEY::KEY_A, KeyState::Released),
+            ]
+        } else {
+            vec![
+                (EV_KEY::KEY_C, KeyState::Pressed),
+                (EV_KEY::KEY_C, KeyState::Released),
+            ]
+        };
+
+        let mut state = create_state_for_device(CONF_FILE_PATH, device, &[]);
+        test_state(
+            &mut state,
+            vec![
+                (EV_KEY::KEY_A, KeyState::Pressed),
+                (EV_KEY::KEY_A, KeyState::Released),
+            ],
+            expected,
+        );
+    }
+}
+
+#[test]
+fn frontmost_application_if_conditions_are_never_met() {
+    test_complex_modifications(
+        CONF_FILE_PATH,
+        vec![
+            (EV_KEY::KEY_X, KeyState::Pressed),
+            (EV_KEY::KEY_X, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_X, KeyState::Pressed),
+            (EV_KEY::KEY_X, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn frontmost_application_unless_conditions_are_always_met() {
+    test_complex_modifications(
+        CONF_FILE_PATH,
+        vec![
+            (EV_KEY::KEY_Z, KeyState::Pressed),
+            (EV_KEY::KEY_Z, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_Y, KeyState::Pressed),
+            (EV_KEY::KEY_Y, KeyState::Released),
+        ],
+    );
+}
@@ -0,0 +1,49 @@
+use karabinux::error::ConfigProblem;
+use karabinux::karabiner::KBConfig;
+use karabinux::state::StateManager;
+use karabinux::Error;
+
+#[test]
+fn from_path_fails_for_missing_file() {
+    match KBConfig::from_path("tests/config/missing.json") {
+        Err(Error::Io(_)) => {}
+        result => panic!("expected an IO error, got: {:?}", result),
+    }
+}
+
+#[test]
+fn from_path_reports_error_location() {
+    match KBConfig::from_path("tests/config/invalid.json") {
+        Err(Error::Json(e)) => assert_eq!(e.line(), 6),
+        result => panic!("expected a JSON error, got: {:?}", result),
+    }
+}
+
+#[test]
+fn from_profile_reports_every_problem() {
+    let kb_config =
+        KBConfig::from_path("tests/config/test_config_errors.json").expect("failed to load config");
+
+    let errors = match StateManager::from_profile(&kb_config.profiles[0]) {
+        Err(Error::Config(errors)) => errors,
+        result => panic!("expected config errors, got: {:?}", result.err()),
+    };
+
+    let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            r#"profile "Broken profile": simple_modifications[0]: missing key_code"#,
+            r#"profile "Broken profile": complex_modifications.rules[1].manipulators[0] ("invalid manipulator"): unknown key_code "not_a_key""#,
+            r#"profile "Broken profile": complex_modifications.rules[1].manipulators[0] ("invalid manipulator"): unknown modifier "not_a_modifier""#,
+            r#"profile "Broken profile": complex_modifications.rules[1].manipulators[0] ("invalid manipulator"): unknown key_code "also_not_a_key""#,
+            r#"profile "Broken profile": devices[0].simple_modifications[0]: unknown key_code "device_key""#,
+        ]
+    );
+
+    assert_eq!(errors[1].rule, Some(1));
+    assert_eq!(
+        errors[1].problem,
+        ConfigProblem::UnknownKeyCode(String::from("not_a_key"))
+    );
+}
@@ -0,0 +1,102 @@
+mod test_util;
+
+use evdev_rs::enums::*;
+use karabinux::key_state::KeyState;
+use karabinux::state::DeviceIdentifiers;
+use test_util::*;
+
+const CONF_FILE_PATH: &str = "test_devices";
+
+fn built_in_keyboard() -> DeviceIdentifiers {
+    DeviceIdentifiers {
+        is_built_in_keyboard: true,
+        ..keyboard(7, 8)
+    }
+}
+
+#[test]
+fn unmatched_device_uses_profile_settings() {
+    let mut state = create_state_for_device(CONF_FILE_PATH, &keyboard(9, 9), &[]);
+    test_state(
+        &mut state,
+        vec![
+            (EV_KEY::KEY_A, KeyState::Pressed),
+            (EV_KEY::KEY_A, KeyState::Released),
+            (EV_KEY::KEY_F1, KeyState::Pressed),
+            (EV_KEY::KEY_F1, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_B, KeyState::Pressed),
+            (EV_KEY::KEY_B, KeyState::Released),
+            (EV_KEY::KEY_F1, KeyState::Pressed),
+            (EV_KEY::KEY_F1, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn matched_device_settings_take_precedence() {
+    let mut state = create_state_for_device(CONF_FILE_PATH, &keyboard(1, 2), &[]);
+    test_state(
+        &mut state,
+        vec![
+            (EV_KEY::KEY_A, KeyState::Pressed),
+            (EV_KEY::KEY_A, KeyState::Released),
+            (EV_KEY::KEY_F1, KeyState::Pressed),
+            (EV_KEY::KEY_F1, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_C, KeyState::Pressed),
+            (EV_KEY::KEY_C, KeyState::Released),
+            (EV_KEY::KEY_MUTE, KeyState::Pressed),
+            (EV_KEY::KEY_MUTE, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn ignored_device_is_not_modified() {
+    let mut state = create_state_for_device(CONF_FILE_PATH, &keyboard(3, 4), &[]);
+    test_state(
+        &mut state,
+        vec![
+            (EV_KEY::KEY_A, KeyState::Pressed),
+            (EV_KEY::KEY_A, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_A, KeyState::Pressed),
+            (EV_KEY::KEY_A, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn built_in_keyboard_is_disabled_if_device_exists() {
+    let mut state =
+        create_state_for_device(CONF_FILE_PATH, &built_in_keyboard(), &[keyboard(5, 6)]);
+    test_state(
+        &mut state,
+        vec![
+            (EV_KEY::KEY_A, KeyState::Pressed),
+            (EV_KEY::KEY_A, KeyState::Released),
+        ],
+        vec![],
+    );
+}
+
+#[test]
+fn built_in_keyboard_is_enabled_if_device_does_not_exist() {
+    let mut state =
+        create_state_for_device(CONF_FILE_PATH, &built_in_keyboard(), &[keyboard(1, 2)]);
+    test_state(
+        &mut state,
+        vec![
+            (EV_KEY::KEY_A, KeyState::Pressed),
+            (EV_KEY::KEY_A, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_B, KeyState::Pressed),
+            (EV_KEY::KEY_B, KeyState::Released),
+        ],
+    );
+}
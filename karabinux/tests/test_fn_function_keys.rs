@@ -0,0 +1,79 @@
+mod test_util;
+
+use evdev_rs::enums::*;
+use karabinux::key_state::KeyState;
+use test_util::*;
+
+const CONF_FILE_PATH: &str = "test_fn_function_keys";
+
+#[test]
+fn fn_function_keys_maps_function_keys() {
+    test_complex_modifications(
+        CONF_FILE_PATH,
+        vec![
+            (EV_KEY::KEY_F1, KeyState::Pressed),
+            (EV_KEY::KEY_F1, KeyState::Released),
+            (EV_KEY::KEY_F2, KeyState::Pressed),
+            (EV_KEY::KEY_F2, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_BRIGHTNESSDOWN, KeyState::Pressed),
+            (EV_KEY::KEY_BRIGHTNESSDOWN, KeyState::Released),
+            (EV_KEY::KEY_MUTE, KeyState::Pressed),
+            (EV_KEY::KEY_MUTE, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn fn_function_keys_applies_after_complex_modifications() {
+    test_complex_modifications(
+        CONF_FILE_PATH,
+        vec![
+            (EV_KEY::KEY_A, KeyState::Pressed),
+            (EV_KEY::KEY_A, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_MUTE, KeyState::Pressed),
+            (EV_KEY::KEY_MUTE, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn fn_function_keys_is_toggled_by_fn() {
+    test_complex_modifications(
+        CONF_FILE_PATH,
+        vec![
+            (EV_KEY::KEY_RIGHTCTRL, KeyState::Pressed),
+            (EV_KEY::KEY_F1, KeyState::Pressed),
+            (EV_KEY::KEY_F1, KeyState::Released),
+            (EV_KEY::KEY_RIGHTCTRL, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_FN, KeyState::Pressed),
+            (EV_KEY::KEY_F1, KeyState::Pressed),
+            (EV_KEY::KEY_F1, KeyState::Released),
+            (EV_KEY::KEY_FN, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn fn_function_keys_releases_the_key_that_was_pressed() {
+    test_complex_modifications(
+        CONF_FILE_PATH,
+        vec![
+            (EV_KEY::KEY_F1, KeyState::Pressed),
+            (EV_KEY::KEY_RIGHTCTRL, KeyState::Pressed),
+            (EV_KEY::KEY_F1, KeyState::Released),
+            (EV_KEY::KEY_RIGHTCTRL, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_BRIGHTNESSDOWN, KeyState::Pressed),
+            (EV_KEY::KEY_FN, KeyState::Pressed),
+            (EV_KEY::KEY_BRIGHTNESSDOWN, KeyState::Released),
+            (EV_KEY::KEY_FN, KeyState::Released),
+        ],
+    );
+}
@@ -0,0 +1,71 @@
+mod test_util;
+
+use evdev_rs::enums::*;
+use karabinux::karabiner::KBConfig;
+use karabinux::key_state::KeyState;
+use karabinux::state::StateManager;
+use karabinux::Error;
+use pretty_assertions::assert_eq;
+use test_util::*;
+
+fn load_as_json(path: &str) -> serde_json::Value {
+    let kb_config = KBConfig::from_path(path).expect("failed to load config");
+    serde_json::to_value(&kb_config).unwrap()
+}
+
+#[test]
+fn yaml_config_matches_json_config() {
+    assert_eq!(
+        load_as_json("tests/config/test_formats.yaml"),
+        load_as_json("tests/config/test_formats.json")
+    );
+}
+
+#[test]
+fn toml_config_matches_json_config() {
+    assert_eq!(
+        load_as_json("tests/config/test_formats.toml"),
+        load_as_json("tests/config/test_formats.json")
+    );
+}
+
+#[test]
+fn yaml_config_maps_keys() {
+    let kb_config = KBConfig::from_path("tests/config/test_formats.yaml").unwrap();
+    let mut state = StateManager::from_profile(&kb_config.profiles[0]).unwrap();
+
+    test_state(
+        &mut state,
+        vec![
+            (EV_KEY::KEY_RIGHTMETA, KeyState::Pressed),
+            (EV_KEY::KEY_RIGHTMETA, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_RIGHTALT, KeyState::Pressed),
+            (EV_KEY::KEY_RIGHTALT, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn invalid_yaml_and_toml_are_reported() {
+    let dir = std::env::temp_dir();
+    let yaml = dir.join(format!("karabinux-invalid-{}.yml", std::process::id()));
+    let toml = dir.join(format!("karabinux-invalid-{}.toml", std::process::id()));
+    std::fs::write(&yaml, "profiles: {").unwrap();
+    std::fs::write(&toml, "profiles = [").unwrap();
+
+    let yaml_result = KBConfig::from_path(&yaml);
+    let toml_result = KBConfig::from_path(&toml);
+    std::fs::remove_file(&yaml).unwrap();
+    std::fs::remove_file(&toml).unwrap();
+
+    match yaml_result {
+        Err(Error::Yaml(_)) => {}
+        result => panic!("expected a YAML error, got: {:?}", result),
+    }
+    match toml_result {
+        Err(Error::Toml(_)) => {}
+        result => panic!("expected a TOML error, got: {:?}", result),
+    }
+}
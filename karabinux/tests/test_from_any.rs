@@ -0,0 +1,81 @@
+mod test_util;
+
+use evdev_rs::enums::*;
+use karabinux::key_state::KeyState;
+use test_util::*;
+
+const CONF_FILE_PATH: &str = "test_from_any";
+
+#[test]
+fn any_key_code_forwards_matched_key() {
+    for key in &[EV_KEY::KEY_A, EV_KEY::KEY_1, EV_KEY::KEY_SPACE] {
+        test_complex_modifications(
+            CONF_FILE_PATH,
+            vec![
+                (key.clone(), KeyState::Pressed),
+                (key.clone(), KeyState::Released),
+            ],
+            vec![
+                (EV_KEY::KEY_LEFTSHIFT, KeyState::Pressed),
+                (key.clone(), KeyState::Pressed),
+                (EV_KEY::KEY_LEFTSHIFT, KeyState::Released),
+                (key.clone(), KeyState::Released),
+            ],
+        );
+    }
+}
+
+#[test]
+fn any_key_code_respects_modifiers() {
+    test_complex_modifications(
+        CONF_FILE_PATH,
+        vec![
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Pressed),
+            (EV_KEY::KEY_Q, KeyState::Pressed),
+            (EV_KEY::KEY_Q, KeyState::Released),
+        ],
+        vec![
+            // Modifier keys are key codes too, so they're matched by "any key".
+            (EV_KEY::KEY_LEFTSHIFT, KeyState::Pressed),
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Pressed),
+            (EV_KEY::KEY_LEFTSHIFT, KeyState::Released),
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Released),
+            (EV_KEY::KEY_ESC, KeyState::Pressed),
+            (EV_KEY::KEY_ESC, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn any_consumer_key_code_matches_media_keys() {
+    for key in &[EV_KEY::KEY_VOLUMEUP, EV_KEY::KEY_PLAYPAUSE] {
+        test_complex_modifications(
+            CONF_FILE_PATH,
+            vec![
+                (key.clone(), KeyState::Pressed),
+                (key.clone(), KeyState::Released),
+            ],
+            vec![
+                (EV_KEY::KEY_M, KeyState::Pressed),
+                (EV_KEY::KEY_M, KeyState::Released),
+            ],
+        );
+    }
+}
+
+#[test]
+fn any_pointing_button_matches_mouse_buttons() {
+    for key in &[EV_KEY::BTN_LEFT, EV_KEY::BTN_RIGHT] {
+        test_complex_modifications(
+            CONF_FILE_PATH,
+            vec![
+                (key.clone(), KeyState::Pressed),
+                (key.clone(), KeyState::Released),
+            ],
+            vec![
+                (EV_KEY::KEY_P, KeyState::Pressed),
+                (EV_KEY::KEY_P, KeyState::Released),
+            ],
+        );
+    }
+}
@@ -4,7 +4,7 @@ use evdev_rs::enums::*;
 use karabinux::key_state::KeyState;
 use test_util::*;
 
-const CONF_FILE_PATH: &'static str = "test_from_modifiers";
+const CONF_FILE_PATH: &str = "test_from_modifiers";
 
 #[test]
 fn no_modifiers_maps_key_with_no_modifiers() {
@@ -0,0 +1,224 @@
+mod test_util;
+
+use evdev_rs::enums::*;
+use karabinux::goku;
+use karabinux::karabiner::KBConfig;
+use karabinux::key_state::KeyState;
+use karabinux::state::StateManager;
+use karabinux::Error;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use std::fs::File;
+use test_util::*;
+
+fn goku_state() -> StateManager {
+    let kb_config =
+        KBConfig::from_path("tests/config/test_goku.edn").expect("failed to load config");
+    StateManager::from_profile(&kb_config.profiles[0]).expect("failed to construct state")
+}
+
+#[test]
+fn goku_config_is_translated() {
+    let kb_config =
+        KBConfig::from_path("tests/config/test_goku.edn").expect("failed to load config");
+    let expected: Value =
+        serde_json::from_reader(File::open("tests/config/test_goku.json").unwrap())
+            .expect("failed to parse expected config");
+
+    assert_eq!(serde_json::to_value(&kb_config).unwrap(), expected);
+}
+
+#[test]
+fn goku_layer_is_active_while_held() {
+    let mut state = goku_state();
+    test_state(
+        &mut state,
+        vec![
+            (EV_KEY::KEY_TAB, KeyState::Pressed),
+            (EV_KEY::KEY_H, KeyState::Pressed),
+            (EV_KEY::KEY_H, KeyState::Released),
+            (EV_KEY::KEY_TAB, KeyState::Released),
+            (EV_KEY::KEY_H, KeyState::Pressed),
+            (EV_KEY::KEY_H, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_LEFT, KeyState::Pressed),
+            (EV_KEY::KEY_LEFT, KeyState::Released),
+            (EV_KEY::KEY_H, KeyState::Pressed),
+            (EV_KEY::KEY_H, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn goku_layer_key_is_sent_when_pressed_alone() {
+    let mut state = goku_state();
+    test_state(
+        &mut state,
+        vec![
+            (EV_KEY::KEY_TAB, KeyState::Pressed),
+            (EV_KEY::KEY_TAB, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_TAB, KeyState::Pressed),
+            (EV_KEY::KEY_TAB, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn goku_simlayer_is_active_while_its_key_is_held() {
+    let mut state = goku_state();
+    test_state(
+        &mut state,
+        vec![
+            (EV_KEY::KEY_D, KeyState::Pressed),
+            (EV_KEY::KEY_J, KeyState::Pressed),
+            (EV_KEY::KEY_J, KeyState::Released),
+            (EV_KEY::KEY_J, KeyState::Pressed),
+            (EV_KEY::KEY_J, KeyState::Released),
+            (EV_KEY::KEY_D, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_DOWN, KeyState::Pressed),
+            (EV_KEY::KEY_DOWN, KeyState::Released),
+            (EV_KEY::KEY_DOWN, KeyState::Pressed),
+            (EV_KEY::KEY_DOWN, KeyState::Released),
+        ],
+    );
+
+    assert_eq!(state.variable("vi-mode"), Some(&Value::from(0)));
+}
+
+#[test]
+fn goku_simlayer_key_is_sent_when_not_pressed_simultaneously() {
+    let mut state = goku_state();
+    let mut events = send_key_at(&mut state, EV_KEY::KEY_D, KeyState::Pressed, 0);
+    assert_eq!(events, vec![]);
+
+    // The simlayer threshold is 250ms.
+    events.extend(tick_at(&mut state, 300));
+    events.extend(send_key_at(
+        &mut state,
+        EV_KEY::KEY_J,
+        KeyState::Pressed,
+        300,
+    ));
+    events.extend(send_key_at(
+        &mut state,
+        EV_KEY::KEY_J,
+        KeyState::Released,
+        350,
+    ));
+    events.extend(send_key_at(
+        &mut state,
+        EV_KEY::KEY_D,
+        KeyState::Released,
+        400,
+    ));
+
+    assert_eq!(
+        map_events_to_key_and_state(events),
+        vec![
+            (EV_KEY::KEY_D, KeyState::Pressed),
+            (EV_KEY::KEY_J, KeyState::Pressed),
+            (EV_KEY::KEY_J, KeyState::Released),
+            (EV_KEY::KEY_D, KeyState::Released),
+        ]
+    );
+}
+
+#[test]
+fn goku_simultaneous_keys_are_matched() {
+    test_complex_modifications_goku(
+        vec![
+            (EV_KEY::KEY_J, KeyState::Pressed),
+            (EV_KEY::KEY_K, KeyState::Pressed),
+            (EV_KEY::KEY_K, KeyState::Released),
+            (EV_KEY::KEY_J, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_ESC, KeyState::Pressed),
+            (EV_KEY::KEY_ESC, KeyState::Released),
+        ],
+    );
+
+    // Keys that are released before the others are pressed aren't simultaneous.
+    test_complex_modifications_goku(
+        vec![
+            (EV_KEY::KEY_J, KeyState::Pressed),
+            (EV_KEY::KEY_J, KeyState::Released),
+            (EV_KEY::KEY_K, KeyState::Pressed),
+            (EV_KEY::KEY_K, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_J, KeyState::Pressed),
+            (EV_KEY::KEY_J, KeyState::Released),
+            (EV_KEY::KEY_K, KeyState::Pressed),
+            (EV_KEY::KEY_K, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn goku_application_rules_never_apply() {
+    // There's no way to tell which application is focused, so `:terminal` never is.
+    test_complex_modifications_goku(
+        vec![
+            (EV_KEY::KEY_X, KeyState::Pressed),
+            (EV_KEY::KEY_X, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_X, KeyState::Pressed),
+            (EV_KEY::KEY_X, KeyState::Released),
+        ],
+    );
+}
+
+fn test_complex_modifications_goku(
+    input_events: Vec<(EV_KEY, KeyState)>,
+    expected_events: Vec<(EV_KEY, KeyState)>,
+) {
+    test_state(&mut goku_state(), input_events, expected_events);
+}
+
+#[test]
+fn invalid_edn_reports_error_location() {
+    match goku::config_from_str("{:main [[:a :b]}") {
+        Err(Error::Edn(e)) => {
+            assert_eq!((e.line, e.column), (1, 16));
+            assert_eq!(e.message, "unexpected '}'");
+        }
+        result => panic!("expected an EDN error, got: {:?}", result),
+    }
+}
+
+#[test]
+fn unsupported_goku_config_is_reported() {
+    let errors = vec![
+        ("{:froms {}}", "unsupported section :froms"),
+        (
+            "{:main [{:des \"x\" :rules [[:!Xa :b]]}]}",
+            "main[0].rules[0]: unknown modifier X",
+        ),
+        (
+            "{:main [{:rules [[:a :b nil {:nope 1}]]}]}",
+            "main[0].rules[0]: unsupported option :nope",
+        ),
+        (
+            "{:main [{:rules [[:a {:pkey :button1}]]}]}",
+            "main[0].rules[0]: unsupported to {:pkey :button1}",
+        ),
+        (
+            "{:layers {:tab-mode {:alone :tab}}}",
+            "layers.tab-mode: missing :key",
+        ),
+    ];
+
+    for (input, message) in errors {
+        match goku::config_from_str(input) {
+            Err(Error::Goku(e)) => assert_eq!(e.to_string(), message),
+            result => panic!("expected a Goku error for {}, got: {:?}", input, result),
+        }
+    }
+}
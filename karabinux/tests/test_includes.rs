@@ -0,0 +1,163 @@
+mod test_util;
+
+use evdev_rs::enums::*;
+use karabinux::error::ConfigProblem;
+use karabinux::karabiner::KBConfig;
+use karabinux::key_state::KeyState;
+use karabinux::state::StateManager;
+use karabinux::Error;
+use pretty_assertions::assert_eq;
+use std::fs;
+use std::path::PathBuf;
+use test_util::*;
+
+const CONF_FILE_PATH: &str = "tests/config/test_includes.json";
+
+/// Loads the config with only the named profile, since includes are resolved
+/// for every profile.
+fn config_with_profile(name: &str) -> KBConfig {
+    let mut kb_config = KBConfig::from_path(CONF_FILE_PATH).expect("failed to load config");
+    kb_config.profiles.retain(|p| p.name == name);
+    kb_config
+}
+
+fn canonical(path: &str) -> PathBuf {
+    fs::canonicalize(path).unwrap()
+}
+
+#[test]
+fn includes_are_inserted_after_the_rules_that_include_them() {
+    let mut kb_config = config_with_profile("Default profile");
+    kb_config
+        .resolve_includes(CONF_FILE_PATH)
+        .expect("failed to resolve includes");
+
+    let rules = &kb_config.profiles[0].complex_modifications.rules;
+    let descriptions: Vec<_> = rules
+        .iter()
+        .map(|r| r.description.as_deref().unwrap())
+        .collect();
+    assert_eq!(
+        descriptions,
+        vec![
+            "Change a to b",
+            "Change e to f",
+            "Change g to h",
+            "Change a to c, and x to y",
+            "Change c to d",
+        ]
+    );
+
+    let sources: Vec<_> = rules
+        .iter()
+        .map(|r| r.source.as_ref().map(|s| s.path.clone()))
+        .collect();
+    assert_eq!(
+        sources,
+        vec![
+            None,
+            Some(PathBuf::from("tests/config/includes/extra/1.json")),
+            Some(PathBuf::from("tests/config/includes/extra/2.json")),
+            Some(PathBuf::from("tests/config/includes/base.json")),
+            Some(PathBuf::from("tests/config/includes/nested.yaml")),
+        ]
+    );
+}
+
+#[test]
+fn included_files_are_listed_so_they_can_be_watched() {
+    let mut kb_config = config_with_profile("Default profile");
+    kb_config
+        .resolve_includes(CONF_FILE_PATH)
+        .expect("failed to resolve includes");
+
+    // Globs match files that are added later, too.
+    assert_eq!(
+        kb_config.source_globs,
+        vec![
+            "tests/config/includes/extra/*.json",
+            "tests/config/includes/base.json",
+            "tests/config/includes/nested.yaml",
+        ]
+    );
+}
+
+#[test]
+fn own_rules_take_precedence_over_included_rules() {
+    let mut kb_config = config_with_profile("Default profile");
+    kb_config
+        .resolve_includes(CONF_FILE_PATH)
+        .expect("failed to resolve includes");
+
+    let mut state = StateManager::from_profile(&kb_config.profiles[0]).unwrap();
+    test_state(
+        &mut state,
+        vec![
+            (EV_KEY::KEY_A, KeyState::Pressed),
+            (EV_KEY::KEY_A, KeyState::Released),
+            (EV_KEY::KEY_X, KeyState::Pressed),
+            (EV_KEY::KEY_X, KeyState::Released),
+            (EV_KEY::KEY_C, KeyState::Pressed),
+            (EV_KEY::KEY_C, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_B, KeyState::Pressed),
+            (EV_KEY::KEY_B, KeyState::Released),
+            (EV_KEY::KEY_Y, KeyState::Pressed),
+            (EV_KEY::KEY_Y, KeyState::Released),
+            (EV_KEY::KEY_D, KeyState::Pressed),
+            (EV_KEY::KEY_D, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn includes_that_match_nothing_are_reported() {
+    let mut kb_config = config_with_profile("Missing include");
+    match kb_config.resolve_includes(CONF_FILE_PATH) {
+        Err(Error::Config(errors)) => assert_eq!(
+            errors[0].to_string(),
+            "profile \"Missing include\": includes[0]: no files match include \"includes/missing/*.json\""
+        ),
+        result => panic!("expected config errors, got: {:?}", result),
+    }
+}
+
+#[test]
+fn include_cycles_are_reported() {
+    let mut kb_config = config_with_profile("Include cycle");
+    let errors = match kb_config.resolve_includes(CONF_FILE_PATH) {
+        Err(Error::Config(errors)) => errors,
+        result => panic!("expected config errors, got: {:?}", result),
+    };
+
+    assert_eq!(
+        errors[0].file,
+        Some(PathBuf::from("tests/config/includes/cycle_b.json"))
+    );
+    assert_eq!(errors[0].path, "includes[0]");
+    assert_eq!(
+        errors[0].problem,
+        ConfigProblem::IncludeCycle(vec![
+            canonical("tests/config/includes/cycle_a.json"),
+            canonical("tests/config/includes/cycle_b.json"),
+            canonical("tests/config/includes/cycle_a.json"),
+        ])
+    );
+}
+
+#[test]
+fn problems_in_included_rules_name_their_file() {
+    let mut kb_config = config_with_profile("Bad included rule");
+    kb_config
+        .resolve_includes(CONF_FILE_PATH)
+        .expect("failed to resolve includes");
+
+    match StateManager::from_profile(&kb_config.profiles[0]) {
+        Err(Error::Config(errors)) => assert_eq!(
+            errors[0].to_string(),
+            "profile \"Bad included rule\": tests/config/includes/bad_key.json: rules[1].manipulators[0]: unknown key_code \"not_a_key\""
+        ),
+        result => panic!("expected config errors, got: {:?}", result.err()),
+    }
+}
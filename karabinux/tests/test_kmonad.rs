@@ -0,0 +1,168 @@
+mod test_util;
+
+use evdev_rs::enums::*;
+use karabinux::karabiner::KBConfig;
+use karabinux::key_state::KeyState;
+use karabinux::kmonad;
+use karabinux::state::StateManager;
+use karabinux::Error;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use std::fs::File;
+use test_util::*;
+
+const CONF_FILE_PATH: &str = "tests/config/test_kmonad.kbd";
+
+fn kmonad_state() -> StateManager {
+    let kb_config = KBConfig::from_path(CONF_FILE_PATH).expect("failed to load config");
+    StateManager::from_profile(&kb_config.profiles[0]).expect("failed to construct state")
+}
+
+#[test]
+fn kmonad_config_is_translated() {
+    let kb_config = KBConfig::from_path(CONF_FILE_PATH).expect("failed to load config");
+    let expected: Value =
+        serde_json::from_reader(File::open("tests/config/test_kmonad.json").unwrap())
+            .expect("failed to parse expected config");
+
+    assert_eq!(serde_json::to_value(&kb_config).unwrap(), expected);
+}
+
+#[test]
+fn kmonad_layer_toggle_is_active_while_held() {
+    let mut state = kmonad_state();
+    test_state(
+        &mut state,
+        vec![
+            (EV_KEY::KEY_TAB, KeyState::Pressed),
+            (EV_KEY::KEY_H, KeyState::Pressed),
+            (EV_KEY::KEY_H, KeyState::Released),
+            (EV_KEY::KEY_TAB, KeyState::Released),
+            (EV_KEY::KEY_H, KeyState::Pressed),
+            (EV_KEY::KEY_H, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_LEFT, KeyState::Pressed),
+            (EV_KEY::KEY_LEFT, KeyState::Released),
+            (EV_KEY::KEY_H, KeyState::Pressed),
+            (EV_KEY::KEY_H, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn kmonad_tap_next_sends_tap_when_pressed_alone() {
+    let mut state = kmonad_state();
+    test_state(
+        &mut state,
+        vec![
+            (EV_KEY::KEY_TAB, KeyState::Pressed),
+            (EV_KEY::KEY_TAB, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_TAB, KeyState::Pressed),
+            (EV_KEY::KEY_TAB, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn kmonad_tap_hold_sends_tap_when_released_quickly() {
+    let mut state = kmonad_state();
+    let mut events = send_key_at(&mut state, EV_KEY::KEY_CAPSLOCK, KeyState::Pressed, 0);
+    events.extend(tick_at(&mut state, 100));
+    events.extend(send_key_at(
+        &mut state,
+        EV_KEY::KEY_CAPSLOCK,
+        KeyState::Released,
+        150,
+    ));
+
+    assert_eq!(
+        map_events_to_key_and_state(events),
+        vec![
+            (EV_KEY::KEY_ESC, KeyState::Pressed),
+            (EV_KEY::KEY_ESC, KeyState::Released),
+        ]
+    );
+}
+
+#[test]
+fn kmonad_tap_hold_sends_hold_when_held() {
+    let mut state = kmonad_state();
+    let mut events = send_key_at(&mut state, EV_KEY::KEY_CAPSLOCK, KeyState::Pressed, 0);
+    events.extend(tick_at(&mut state, 250));
+    events.extend(send_key_at(
+        &mut state,
+        EV_KEY::KEY_CAPSLOCK,
+        KeyState::Released,
+        300,
+    ));
+
+    assert_eq!(
+        map_events_to_key_and_state(events),
+        vec![
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Pressed),
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Released),
+        ]
+    );
+}
+
+#[test]
+fn kmonad_layer_switch_replaces_base_layer() {
+    let mut state = kmonad_state();
+    test_state(
+        &mut state,
+        vec![
+            (EV_KEY::KEY_RIGHTALT, KeyState::Pressed),
+            (EV_KEY::KEY_RIGHTALT, KeyState::Released),
+            (EV_KEY::KEY_2, KeyState::Pressed),
+            (EV_KEY::KEY_2, KeyState::Released),
+            (EV_KEY::KEY_RIGHTALT, KeyState::Pressed),
+            (EV_KEY::KEY_RIGHTALT, KeyState::Released),
+            (EV_KEY::KEY_2, KeyState::Pressed),
+            (EV_KEY::KEY_2, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_LEFTSHIFT, KeyState::Pressed),
+            (EV_KEY::KEY_3, KeyState::Pressed),
+            (EV_KEY::KEY_LEFTSHIFT, KeyState::Released),
+            (EV_KEY::KEY_3, KeyState::Released),
+            (EV_KEY::KEY_2, KeyState::Pressed),
+            (EV_KEY::KEY_2, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn unsupported_kmonad_config_is_reported() {
+    let errors = vec![
+        ("(defsrc a", "unterminated list at line 1 column 10"),
+        ("(defsrc a) (defwhat)", "unsupported form (defwhat)"),
+        ("(defsrc nope)", "defsrc: unknown key nope"),
+        ("(defsrc a)", "missing deflayer"),
+        (
+            "(defsrc a b) (deflayer base c)",
+            "deflayer base: has 1 buttons, but defsrc has 2 keys",
+        ),
+        (
+            "(defsrc a) (defalias x @y y @x) (deflayer base @x)",
+            "deflayer base.a: alias @x refers to itself",
+        ),
+        (
+            "(defsrc a) (deflayer base (multi-tap 300 a b))",
+            "deflayer base.a: unsupported button (multi-tap 300 a b)",
+        ),
+        (
+            "(defsrc a) (deflayer base (layer-toggle nope))",
+            "deflayer base.a: expected the name of a layer in (layer-toggle nope)",
+        ),
+    ];
+
+    for (input, message) in errors {
+        match kmonad::profile_from_str(input, "KMonad") {
+            Err(Error::Kmonad(e)) => assert_eq!(e.to_string(), message),
+            result => panic!("expected a KMonad error for {}, got: {:?}", input, result),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use evdev_rs::enums::EV_KEY;
+use karabinux::karabiner::KBConfig;
+use karabinux::lint::{lint_profile, LintProblem};
+use pretty_assertions::assert_eq;
+
+const CONF_FILE_PATH: &str = "tests/config/test_lint.json";
+
+fn lint(profile_name: &str, output_keys: Option<&[EV_KEY]>) -> Vec<String> {
+    let kb_config = KBConfig::from_path(CONF_FILE_PATH).expect("failed to load config");
+    let kb_profile = kb_config.find_profile(Some(profile_name)).unwrap();
+    lint_profile(kb_profile, output_keys)
+        .expect("failed to lint profile")
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
+#[test]
+fn lint_reports_unreachable_manipulators_and_simple_modifications() {
+    assert_eq!(
+        lint("Lint", None),
+        vec![
+            "profile \"Lint\": complex_modifications.rules[1].manipulators[0] (\"Change shift+a to c\"): never applies, since complex_modifications.rules[0].manipulators[0] always matches first",
+            "profile \"Lint\": complex_modifications.rules[1].manipulators[1] (\"Change a to d in mode 1\"): never applies, since complex_modifications.rules[0].manipulators[0] always matches first",
+            "profile \"Lint\": complex_modifications.rules[2].manipulators[4] (\"Change right_shift+b to f13\"): never applies, since complex_modifications.rules[2].manipulators[3] always matches first",
+            "profile \"Lint\": complex_modifications.rules[3].manipulators[1] (\"Change c to d in mode 2\"): never applies, since complex_modifications.rules[3].manipulators[0] always matches first",
+            "profile \"Lint\": simple_modifications[1]: never applies, since simple_modifications[0] changes the same key",
+            "profile \"Lint\": simple_modifications[2]: sends a key changed by simple_modifications[3], which isn't applied to it",
+        ]
+    );
+}
+
+#[test]
+fn lint_reports_keys_the_output_device_cant_send() {
+    let output_keys = [
+        EV_KEY::KEY_B,
+        EV_KEY::KEY_C,
+        EV_KEY::KEY_D,
+        EV_KEY::KEY_E,
+        EV_KEY::KEY_F,
+        EV_KEY::KEY_W,
+        EV_KEY::KEY_X,
+        EV_KEY::KEY_Y,
+        EV_KEY::KEY_ESC,
+        EV_KEY::KEY_LEFTCTRL,
+    ];
+
+    let kb_config = KBConfig::from_path(CONF_FILE_PATH).expect("failed to load config");
+    let kb_profile = kb_config.find_profile(Some("Lint")).unwrap();
+    let unsupported: Vec<_> = lint_profile(kb_profile, Some(&output_keys))
+        .expect("failed to lint profile")
+        .into_iter()
+        .filter(|w| matches!(w.problem, LintProblem::UnsupportedKey(_)))
+        .map(|w| (w.path, w.problem))
+        .collect();
+
+    assert_eq!(
+        unsupported,
+        vec![(
+            String::from("complex_modifications.rules[2].manipulators[4]"),
+            LintProblem::UnsupportedKey(String::from("f13"))
+        )]
+    );
+}
+
+#[test]
+fn lint_reports_parameters_karabinux_ignores() {
+    assert_eq!(
+        lint("Parameters", None),
+        vec![
+            "profile \"Parameters\": complex_modifications.parameters: sets \"basic.to_delayed_action_delay_milliseconds\", which karabinux ignores",
+            "profile \"Parameters\": complex_modifications.rules[0].manipulators[0].parameters (\"Change a to b\"): sets \"mouse_motion_to_scroll.speed\", which karabinux ignores",
+        ]
+    );
+}
+
+#[test]
+fn lint_allows_conditions_modifiers_and_swapped_keys() {
+    assert_eq!(lint("Clean", None), Vec::<String>::new());
+}
@@ -0,0 +1,73 @@
+mod test_util;
+
+use evdev_rs::enums::*;
+use evdev_rs::{InputEvent, TimeVal};
+use karabinux::key_state::KeyState;
+use pretty_assertions::assert_eq;
+use test_util::*;
+
+const CONF_FILE_PATH: &str = "test_mouse_key";
+
+fn map_events_to_rel_and_value(events: Vec<InputEvent>) -> Vec<(EV_REL, i32)> {
+    events
+        .iter()
+        .map(|ev| match &ev.event_code {
+            EventCode::EV_REL(rel) => (rel.clone(), ev.value),
+            _ => panic!("Expected event to be a relative event, got {:?}", ev),
+        })
+        .collect()
+}
+
+#[test]
+fn mouse_key_does_not_emit_key_events() {
+    let mut state = create_state(CONF_FILE_PATH);
+    assert_eq!(send_key_press(&mut state, EV_KEY::KEY_L), vec![]);
+}
+
+#[test]
+fn mouse_key_does_not_move_when_released() {
+    let mut state = create_state(CONF_FILE_PATH);
+    assert_eq!(state.get_timeout_events(&TimeVal::new(0, 0)), vec![]);
+
+    send_key_press(&mut state, EV_KEY::KEY_L);
+    assert_eq!(state.get_timeout_events(&TimeVal::new(0, 0)), vec![]);
+}
+
+#[test]
+fn mouse_key_moves_while_held_and_accelerates() {
+    let mut state = create_state(CONF_FILE_PATH);
+    send_key(&mut state, EV_KEY::KEY_L, KeyState::Pressed);
+
+    let first = map_events_to_rel_and_value(state.get_timeout_events(&TimeVal::new(0, 0)));
+    assert_eq!(first, vec![(EV_REL::REL_X, 3)]);
+
+    for _ in 0..100 {
+        state.get_timeout_events(&TimeVal::new(0, 0));
+    }
+
+    let last = map_events_to_rel_and_value(state.get_timeout_events(&TimeVal::new(0, 0)));
+    assert_eq!(last, vec![(EV_REL::REL_X, 12)]);
+
+    send_key(&mut state, EV_KEY::KEY_L, KeyState::Released);
+    assert_eq!(state.get_timeout_events(&TimeVal::new(0, 0)), vec![]);
+}
+
+#[test]
+fn mouse_key_combines_held_keys() {
+    let mut state = create_state(CONF_FILE_PATH);
+    send_key(&mut state, EV_KEY::KEY_L, KeyState::Pressed);
+    send_key(&mut state, EV_KEY::KEY_K, KeyState::Pressed);
+    send_key(&mut state, EV_KEY::KEY_F, KeyState::Pressed);
+
+    let events = map_events_to_rel_and_value(state.get_timeout_events(&TimeVal::new(0, 0)));
+    assert_eq!(events, vec![(EV_REL::REL_X, 6), (EV_REL::REL_Y, -6)]);
+}
+
+#[test]
+fn mouse_key_scrolls_in_the_linux_direction() {
+    let mut state = create_state(CONF_FILE_PATH);
+    send_key(&mut state, EV_KEY::KEY_J, KeyState::Pressed);
+
+    let events = map_events_to_rel_and_value(state.get_timeout_events(&TimeVal::new(0, 0)));
+    assert_eq!(events, vec![(EV_REL::REL_WHEEL, -1)]);
+}
@@ -0,0 +1,164 @@
+mod test_util;
+
+use evdev_rs::enums::*;
+use karabinux::key_state::KeyState;
+use pretty_assertions::assert_eq;
+use test_util::*;
+
+const CONF_FILE_PATH: &str = "test_parameters";
+
+#[test]
+fn to_if_alone_is_sent_when_released_before_timeout() {
+    let mut state = create_state(CONF_FILE_PATH);
+    let mut events = send_key_at(&mut state, EV_KEY::KEY_CAPSLOCK, KeyState::Pressed, 0);
+    events.extend(send_key_at(
+        &mut state,
+        EV_KEY::KEY_CAPSLOCK,
+        KeyState::Released,
+        250,
+    ));
+
+    assert_eq!(
+        map_events_to_key_and_state(events),
+        vec![
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Pressed),
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Released),
+            (EV_KEY::KEY_ESC, KeyState::Pressed),
+            (EV_KEY::KEY_ESC, KeyState::Released),
+        ]
+    );
+}
+
+#[test]
+fn to_if_alone_is_not_sent_after_profile_timeout() {
+    let mut state = create_state(CONF_FILE_PATH);
+    let mut events = send_key_at(&mut state, EV_KEY::KEY_CAPSLOCK, KeyState::Pressed, 0);
+    events.extend(send_key_at(
+        &mut state,
+        EV_KEY::KEY_CAPSLOCK,
+        KeyState::Released,
+        350,
+    ));
+
+    assert_eq!(
+        map_events_to_key_and_state(events),
+        vec![
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Pressed),
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Released),
+        ]
+    );
+}
+
+#[test]
+fn to_if_alone_is_not_sent_if_another_key_is_pressed() {
+    let mut state = create_state(CONF_FILE_PATH);
+    let mut events = send_key_at(&mut state, EV_KEY::KEY_CAPSLOCK, KeyState::Pressed, 0);
+    events.extend(send_key_at(
+        &mut state,
+        EV_KEY::KEY_A,
+        KeyState::Pressed,
+        10,
+    ));
+    events.extend(send_key_at(
+        &mut state,
+        EV_KEY::KEY_A,
+        KeyState::Released,
+        20,
+    ));
+    events.extend(send_key_at(
+        &mut state,
+        EV_KEY::KEY_CAPSLOCK,
+        KeyState::Released,
+        30,
+    ));
+
+    assert_eq!(
+        map_events_to_key_and_state(events),
+        vec![
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Pressed),
+            (EV_KEY::KEY_A, KeyState::Pressed),
+            (EV_KEY::KEY_A, KeyState::Released),
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Released),
+        ]
+    );
+}
+
+#[test]
+fn manipulator_parameters_override_profile_parameters() {
+    let mut state = create_state(CONF_FILE_PATH);
+    let mut events = send_key_at(&mut state, EV_KEY::KEY_TAB, KeyState::Pressed, 0);
+    events.extend(send_key_at(
+        &mut state,
+        EV_KEY::KEY_TAB,
+        KeyState::Released,
+        150,
+    ));
+    events.extend(send_key_at(
+        &mut state,
+        EV_KEY::KEY_TAB,
+        KeyState::Pressed,
+        1000,
+    ));
+    events.extend(send_key_at(
+        &mut state,
+        EV_KEY::KEY_TAB,
+        KeyState::Released,
+        1050,
+    ));
+
+    assert_eq!(
+        map_events_to_key_and_state(events),
+        vec![
+            (EV_KEY::KEY_LEFTMETA, KeyState::Pressed),
+            (EV_KEY::KEY_LEFTMETA, KeyState::Released),
+            (EV_KEY::KEY_LEFTMETA, KeyState::Pressed),
+            (EV_KEY::KEY_LEFTMETA, KeyState::Released),
+            (EV_KEY::KEY_TAB, KeyState::Pressed),
+            (EV_KEY::KEY_TAB, KeyState::Released),
+        ]
+    );
+}
+
+#[test]
+fn to_if_held_down_is_sent_after_threshold() {
+    let mut state = create_state(CONF_FILE_PATH);
+    let mut events = send_key_at(&mut state, EV_KEY::KEY_SPACE, KeyState::Pressed, 0);
+    assert_eq!(tick_at(&mut state, 100), vec![]);
+    events.extend(tick_at(&mut state, 250));
+    assert_eq!(tick_at(&mut state, 300), vec![]);
+    events.extend(send_key_at(
+        &mut state,
+        EV_KEY::KEY_SPACE,
+        KeyState::Released,
+        400,
+    ));
+
+    assert_eq!(
+        map_events_to_key_and_state(events),
+        vec![
+            (EV_KEY::KEY_LEFTSHIFT, KeyState::Pressed),
+            (EV_KEY::KEY_LEFTSHIFT, KeyState::Released),
+        ]
+    );
+}
+
+#[test]
+fn to_if_held_down_is_not_sent_when_tapped() {
+    let mut state = create_state(CONF_FILE_PATH);
+    let mut events = send_key_at(&mut state, EV_KEY::KEY_SPACE, KeyState::Pressed, 0);
+    events.extend(send_key_at(
+        &mut state,
+        EV_KEY::KEY_SPACE,
+        KeyState::Released,
+        50,
+    ));
+    events.extend(tick_at(&mut state, 300));
+
+    assert_eq!(
+        map_events_to_key_and_state(events),
+        vec![
+            (EV_KEY::KEY_SPACE, KeyState::Pressed),
+            (EV_KEY::KEY_SPACE, KeyState::Released),
+        ]
+    );
+}
@@ -0,0 +1,27 @@
+use karabinux::karabiner::KBConfig;
+
+const CONF_FILE_PATH: &str = "tests/config/test_profiles.json";
+
+#[test]
+fn find_profile_defaults_to_selected_profile() {
+    let kb_config = KBConfig::from_path(CONF_FILE_PATH).expect("failed to construct config");
+    let profile = kb_config
+        .find_profile(None)
+        .expect("failed to find profile");
+    assert_eq!(profile.name, "Gaming");
+}
+
+#[test]
+fn find_profile_by_name() {
+    let kb_config = KBConfig::from_path(CONF_FILE_PATH).expect("failed to construct config");
+    let profile = kb_config
+        .find_profile(Some("Coding"))
+        .expect("failed to find profile");
+    assert_eq!(profile.name, "Coding");
+}
+
+#[test]
+fn find_profile_missing_name() {
+    let kb_config = KBConfig::from_path(CONF_FILE_PATH).expect("failed to construct config");
+    assert!(kb_config.find_profile(Some("Missing")).is_none());
+}
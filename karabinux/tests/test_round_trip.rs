@@ -0,0 +1,53 @@
+use karabinux::karabiner::KBConfig;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use std::fs::{self, File};
+
+/// Fixtures that are intentionally invalid, and can't be loaded.
+const INVALID_FIXTURES: &[&str] = &["invalid.json"];
+
+#[test]
+fn fixtures_round_trip_without_losing_fields() {
+    let mut count = 0;
+    for entry in fs::read_dir("tests/config").expect("failed to read fixtures") {
+        let path = entry.expect("failed to read fixture").path();
+        let file_name = path.file_name().unwrap().to_str().unwrap();
+        if path.extension().is_none_or(|ext| ext != "json") || INVALID_FIXTURES.contains(&file_name)
+        {
+            continue;
+        }
+
+        let expected: Value =
+            serde_json::from_reader(File::open(&path).unwrap()).expect("failed to parse fixture");
+        let kb_config = KBConfig::from_path(&path).expect("failed to load fixture");
+
+        let mut written = vec![];
+        kb_config
+            .to_writer(&mut written)
+            .expect("failed to write config");
+        let actual: Value =
+            serde_json::from_slice(&written).expect("failed to parse written config");
+
+        assert_eq!(actual, expected, "{} did not round trip", file_name);
+        count += 1;
+    }
+
+    assert!(count > 0, "no fixtures were found");
+}
+
+#[test]
+fn save_writes_a_loadable_config() {
+    let kb_config = KBConfig::from_path("tests/config/default.json").expect("failed to load");
+    let path = std::env::temp_dir().join(format!("karabinux-save-{}.json", std::process::id()));
+
+    kb_config.save(&path).expect("failed to save config");
+    let saved = fs::read_to_string(&path).expect("failed to read saved config");
+    let reloaded = KBConfig::from_path(&path).expect("failed to load saved config");
+    fs::remove_file(&path).unwrap();
+
+    assert!(saved.starts_with("{\n    \"profiles\": ["));
+    assert_eq!(
+        serde_json::to_value(&reloaded).unwrap(),
+        serde_json::to_value(&kb_config).unwrap()
+    );
+}
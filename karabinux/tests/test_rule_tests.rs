@@ -0,0 +1,70 @@
+use karabinux::error::ConfigProblem;
+use karabinux::karabiner::KBConfig;
+use karabinux::testing::run_rule_tests;
+use karabinux::Error;
+use pretty_assertions::assert_eq;
+
+const CONF_FILE_PATH: &str = "tests/config/test_rule_tests.json";
+
+fn config() -> KBConfig {
+    KBConfig::from_path(CONF_FILE_PATH).expect("failed to load config")
+}
+
+#[test]
+fn rule_tests_pass_when_the_output_matches() {
+    let kb_config = config();
+    let results = run_rule_tests(kb_config.find_profile(Some("Passing")).unwrap()).unwrap();
+
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|r| r.passed()));
+    assert_eq!(
+        results[1].to_string(),
+        "ok: profile \"Passing\": complex_modifications.rules[0].tests[1] (\"Control+c\")"
+    );
+}
+
+#[test]
+fn rule_tests_show_a_diff_when_the_output_differs() {
+    let kb_config = config();
+    let results = run_rule_tests(kb_config.find_profile(Some("Failing")).unwrap()).unwrap();
+
+    assert_eq!(results.len(), 1);
+    assert!(!results[0].passed());
+    assert_eq!(
+        results[0].to_string(),
+        [
+            "FAILED: profile \"Failing\": complex_modifications.rules[0].tests[0] (\"Change a to b\")",
+            "    input: a↓ a↑",
+            "    output (- expected, + actual):",
+            "    - c↓",
+            "    - c↑",
+            "    + b↓",
+            "    + b↑",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn rule_tests_with_invalid_keys_are_reported() {
+    let kb_config = config();
+    let errors = match run_rule_tests(kb_config.find_profile(Some("Invalid")).unwrap()) {
+        Err(Error::Config(errors)) => errors,
+        result => panic!("expected config errors, got: {:?}", result),
+    };
+
+    let problems: Vec<_> = errors.into_iter().map(|e| (e.path, e.problem)).collect();
+    assert_eq!(
+        problems,
+        vec![
+            (
+                String::from("complex_modifications.rules[0].tests[0].input[1]"),
+                ConfigProblem::InvalidKeyStroke(String::from("a"))
+            ),
+            (
+                String::from("complex_modifications.rules[0].tests[0].output[0]"),
+                ConfigProblem::InvalidKeyStroke(String::from("not_a_key↓"))
+            ),
+        ]
+    );
+}
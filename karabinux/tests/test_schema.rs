@@ -0,0 +1,62 @@
+use karabinux::karabiner::{config_schema, KB_KEY_CODES, KB_MODIFIERS};
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use std::fs::File;
+
+const SCHEMA_PATH: &str = "../karabinux.schema.json";
+
+#[test]
+fn schema_file_is_up_to_date() {
+    let committed: Value = serde_json::from_reader(File::open(SCHEMA_PATH).unwrap())
+        .expect("failed to parse schema file");
+
+    assert_eq!(
+        committed,
+        config_schema(),
+        "karabinux.schema.json is out of date, run `just schema` to update it"
+    );
+}
+
+#[test]
+fn schema_lists_every_key_code_and_modifier() {
+    let schema = config_schema();
+    let definitions = &schema["definitions"];
+
+    let key_codes = definitions["KeyCode"]["anyOf"][0]["enum"]
+        .as_array()
+        .unwrap();
+    assert_eq!(key_codes.len(), KB_KEY_CODES.len());
+    for (name, _) in KB_KEY_CODES {
+        assert!(key_codes.contains(&Value::from(*name)), "missing {}", name);
+    }
+
+    let modifiers = definitions["Modifier"]["enum"].as_array().unwrap();
+    assert_eq!(modifiers.len(), KB_MODIFIERS.len());
+    for (name, _) in KB_MODIFIERS {
+        assert!(modifiers.contains(&Value::from(*name)), "missing {}", name);
+    }
+}
+
+#[test]
+fn schema_leaves_out_unsupported_fields() {
+    let schema = config_schema();
+    let definitions = &schema["definitions"];
+
+    let parameters = definitions["KBParameters"]["properties"]
+        .as_object()
+        .unwrap();
+    assert!(parameters.contains_key("basic.to_if_alone_timeout_milliseconds"));
+    assert!(parameters.contains_key("basic.simultaneous_threshold_milliseconds"));
+    assert!(!parameters.contains_key("basic.to_delayed_action_delay_milliseconds"));
+    assert!(!parameters.contains_key("mouse_motion_to_scroll.speed"));
+
+    let from = definitions["KBFromDefinition"]["properties"]
+        .as_object()
+        .unwrap();
+    assert!(from.contains_key("simultaneous"));
+    assert!(from.contains_key("simultaneous_options"));
+    let simultaneous_options = definitions["KBSimultaneousOptions"]["properties"]
+        .as_object()
+        .unwrap();
+    assert!(!simultaneous_options.contains_key("detect_key_down_uninterruptedly"));
+}
@@ -0,0 +1,38 @@
+mod test_util;
+
+use evdev_rs::enums::*;
+use evdev_rs::TimeVal;
+use karabinux::key_state::KeyState;
+use pretty_assertions::assert_eq;
+use test_util::*;
+
+const CONF_FILE_PATH: &str = "test_select_profile";
+
+#[test]
+fn select_profile_to_event_selects_profile() {
+    let mut state = create_state(CONF_FILE_PATH);
+    assert_eq!(send_key_press(&mut state, EV_KEY::KEY_F12), vec![]);
+    assert_eq!(state.take_selected_profile(), Some(String::from("Gaming")));
+    assert_eq!(state.take_selected_profile(), None);
+}
+
+#[test]
+fn release_all_keys_releases_pressed_keys() {
+    let mut state = create_state(CONF_FILE_PATH);
+    send_key(&mut state, EV_KEY::KEY_CAPSLOCK, KeyState::Pressed);
+    send_key(&mut state, EV_KEY::KEY_A, KeyState::Pressed);
+    send_key(&mut state, EV_KEY::KEY_B, KeyState::Pressed);
+    send_key(&mut state, EV_KEY::KEY_B, KeyState::Released);
+
+    let events = state.release_all_keys(&TimeVal::new(0, 0));
+    assert_eq!(
+        map_events_to_key_and_state(events),
+        vec![
+            (EV_KEY::KEY_A, KeyState::Released),
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Released),
+        ]
+    );
+
+    let events = state.release_all_keys(&TimeVal::new(0, 0));
+    assert_eq!(map_events_to_key_and_state(events), vec![]);
+}
@@ -0,0 +1,34 @@
+mod test_util;
+
+use evdev_rs::enums::*;
+use karabinux::key_state::KeyState;
+use pretty_assertions::assert_eq;
+use std::time::{Duration, Instant};
+use std::{env, fs, process, thread};
+use test_util::*;
+
+const CONF_FILE_PATH: &str = "test_shell_command";
+
+#[test]
+fn shell_commands_are_run_by_the_shell() {
+    let output = env::temp_dir().join(format!("karabinux-shell-command-{}", process::id()));
+    let _ = fs::remove_file(&output);
+    env::set_var("KARABINUX_TEST_OUTPUT", &output);
+
+    let mut state = create_state(CONF_FILE_PATH);
+    send_key(&mut state, EV_KEY::KEY_A, KeyState::Pressed);
+    send_key(&mut state, EV_KEY::KEY_A, KeyState::Released);
+
+    // Commands are run in the background, so wait for it to finish.
+    let start = Instant::now();
+    let contents = loop {
+        match fs::read_to_string(&output) {
+            Ok(contents) if !contents.is_empty() => break contents,
+            _ if start.elapsed() > Duration::from_secs(5) => panic!("shell command wasn't run"),
+            _ => thread::sleep(Duration::from_millis(10)),
+        }
+    };
+    fs::remove_file(&output).unwrap();
+
+    assert_eq!(contents, "one,two words,");
+}
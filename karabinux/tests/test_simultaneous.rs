@@ -0,0 +1,140 @@
+mod test_util;
+
+use evdev_rs::enums::*;
+use karabinux::key_state::KeyState;
+use pretty_assertions::assert_eq;
+use test_util::*;
+
+const CONF_FILE_PATH: &str = "test_simultaneous";
+
+#[test]
+fn keys_pressed_together_are_matched() {
+    test_complex_modifications(
+        CONF_FILE_PATH,
+        vec![
+            (EV_KEY::KEY_J, KeyState::Pressed),
+            (EV_KEY::KEY_K, KeyState::Pressed),
+            (EV_KEY::KEY_K, KeyState::Released),
+            (EV_KEY::KEY_J, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_ESC, KeyState::Pressed),
+            (EV_KEY::KEY_ESC, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn keys_released_before_the_others_are_pressed_are_sent() {
+    test_complex_modifications(
+        CONF_FILE_PATH,
+        vec![
+            (EV_KEY::KEY_J, KeyState::Pressed),
+            (EV_KEY::KEY_J, KeyState::Released),
+            (EV_KEY::KEY_K, KeyState::Pressed),
+            (EV_KEY::KEY_K, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_J, KeyState::Pressed),
+            (EV_KEY::KEY_J, KeyState::Released),
+            (EV_KEY::KEY_K, KeyState::Pressed),
+            (EV_KEY::KEY_K, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn keys_are_sent_when_another_key_is_pressed() {
+    test_complex_modifications(
+        CONF_FILE_PATH,
+        vec![
+            (EV_KEY::KEY_J, KeyState::Pressed),
+            (EV_KEY::KEY_A, KeyState::Pressed),
+            (EV_KEY::KEY_A, KeyState::Released),
+            (EV_KEY::KEY_J, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_J, KeyState::Pressed),
+            (EV_KEY::KEY_A, KeyState::Pressed),
+            (EV_KEY::KEY_A, KeyState::Released),
+            (EV_KEY::KEY_J, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn keys_are_sent_after_the_threshold() {
+    let mut state = create_state(CONF_FILE_PATH);
+    let mut events = send_key_at(&mut state, EV_KEY::KEY_J, KeyState::Pressed, 0);
+    assert_eq!(events, vec![]);
+
+    events.extend(tick_at(&mut state, 150));
+    events.extend(send_key_at(
+        &mut state,
+        EV_KEY::KEY_K,
+        KeyState::Pressed,
+        200,
+    ));
+    events.extend(send_key_at(
+        &mut state,
+        EV_KEY::KEY_K,
+        KeyState::Released,
+        250,
+    ));
+    events.extend(send_key_at(
+        &mut state,
+        EV_KEY::KEY_J,
+        KeyState::Released,
+        300,
+    ));
+
+    assert_eq!(
+        map_events_to_key_and_state(events),
+        vec![
+            (EV_KEY::KEY_J, KeyState::Pressed),
+            (EV_KEY::KEY_K, KeyState::Pressed),
+            (EV_KEY::KEY_K, KeyState::Released),
+            (EV_KEY::KEY_J, KeyState::Released),
+        ]
+    );
+}
+
+#[test]
+fn strict_keys_must_be_pressed_in_order() {
+    test_complex_modifications(
+        CONF_FILE_PATH,
+        vec![
+            (EV_KEY::KEY_F, KeyState::Pressed),
+            (EV_KEY::KEY_D, KeyState::Pressed),
+            (EV_KEY::KEY_D, KeyState::Released),
+            (EV_KEY::KEY_F, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_F, KeyState::Pressed),
+            (EV_KEY::KEY_D, KeyState::Pressed),
+            (EV_KEY::KEY_D, KeyState::Released),
+            (EV_KEY::KEY_F, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn to_is_released_when_all_keys_are_and_then_to_after_key_up_is_sent() {
+    let mut state = create_state(CONF_FILE_PATH);
+    let mut events = send_key(&mut state, EV_KEY::KEY_D, KeyState::Pressed);
+    events.extend(send_key(&mut state, EV_KEY::KEY_F, KeyState::Pressed));
+    events.extend(send_key(&mut state, EV_KEY::KEY_D, KeyState::Released));
+    assert_eq!(
+        map_events_to_key_and_state(events),
+        vec![(EV_KEY::KEY_LEFTSHIFT, KeyState::Pressed)]
+    );
+
+    assert_eq!(
+        map_events_to_key_and_state(send_key(&mut state, EV_KEY::KEY_F, KeyState::Released)),
+        vec![
+            (EV_KEY::KEY_LEFTSHIFT, KeyState::Released),
+            (EV_KEY::KEY_X, KeyState::Pressed),
+            (EV_KEY::KEY_X, KeyState::Released),
+        ]
+    );
+}
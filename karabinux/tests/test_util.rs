@@ -5,9 +5,10 @@ use evdev_rs::util::event_code_to_int;
 use evdev_rs::{InputEvent, TimeVal};
 use karabinux::karabiner::KBConfig;
 use karabinux::key_state::KeyState;
-use karabinux::state::StateManager;
+use karabinux::state::{DeviceIdentifiers, StateManager};
 use pretty_assertions::assert_eq;
 
+#[allow(unused_imports)]
 pub use karabinux::constants::ALL_MODIFIER_KEYS;
 
 /// Create a state from the config file with the same name as the test.
@@ -16,7 +17,30 @@ pub fn create_state(conf_file_name: &str) -> StateManager {
     let conf_file_path = &format!("tests/config/{}.json", conf_file_name);
 
     let kb_config = KBConfig::from_path(conf_file_path).expect("failed to construct config");
-    StateManager::from_profile(&kb_config.profiles[0])
+    StateManager::from_profile(&kb_config.profiles[0]).expect("failed to construct state")
+}
+
+/// Same as `create_state`, but for events read from `device`.
+pub fn create_state_for_device(
+    conf_file_name: &str,
+    device: &DeviceIdentifiers,
+    connected_devices: &[DeviceIdentifiers],
+) -> StateManager {
+    let conf_file_path = &format!("tests/config/{}.json", conf_file_name);
+
+    let kb_config = KBConfig::from_path(conf_file_path).expect("failed to construct config");
+    StateManager::from_profile_for_device(&kb_config.profiles[0], device, connected_devices)
+        .expect("failed to construct state")
+}
+
+/// A keyboard with the given ids, for use with `create_state_for_device`.
+pub fn keyboard(vendor_id: u16, product_id: u16) -> DeviceIdentifiers {
+    DeviceIdentifiers {
+        vendor_id,
+        product_id,
+        is_keyboard: true,
+        ..DeviceIdentifiers::default()
+    }
 }
 
 pub fn create_key_event(key: EV_KEY, value: KeyState) -> InputEvent {
@@ -27,10 +51,30 @@ pub fn create_key_event(key: EV_KEY, value: KeyState) -> InputEvent {
 }
 
 pub fn send_key(state: &mut StateManager, key: EV_KEY, value: KeyState) -> Vec<InputEvent> {
-    let key_event = create_key_event(key.clone(), value.into());
+    let key_event = create_key_event(key.clone(), value);
+    state.get_mapped_events(key_event)
+}
+
+/// Same as `send_key`, but the event happens `millis` milliseconds after the epoch.
+pub fn send_key_at(
+    state: &mut StateManager,
+    key: EV_KEY,
+    value: KeyState,
+    millis: i64,
+) -> Vec<InputEvent> {
+    let key_event = InputEventBuilder::new()
+        .time(millis / 1000, (millis % 1000) * 1000)
+        .code(EventCode::EV_KEY(key))
+        .val(value.into())
+        .build();
     state.get_mapped_events(key_event)
 }
 
+/// Runs the state's timeouts as if it were `millis` milliseconds after the epoch.
+pub fn tick_at(state: &mut StateManager, millis: i64) -> Vec<InputEvent> {
+    state.get_timeout_events(&TimeVal::new(millis / 1000, (millis % 1000) * 1000))
+}
+
 pub fn send_key_press(state: &mut StateManager, key: EV_KEY) -> Vec<InputEvent> {
     let mut events = send_key(state, key.clone(), KeyState::Pressed);
     events.extend(send_key(state, key.clone(), KeyState::Released));
@@ -53,10 +97,18 @@ pub fn test_complex_modifications(
     expected_events: Vec<(EV_KEY, KeyState)>,
 ) {
     let mut state = create_state(conf_file_name);
+    test_state(&mut state, input_events, expected_events);
+}
+
+pub fn test_state(
+    state: &mut StateManager,
+    input_events: Vec<(EV_KEY, KeyState)>,
+    expected_events: Vec<(EV_KEY, KeyState)>,
+) {
     let mut events = vec![];
 
     for (key, key_state) in input_events {
-        events.extend(send_key(&mut state, key.clone(), key_state));
+        events.extend(send_key(state, key.clone(), key_state));
     }
 
     assert_eq!(map_events_to_key_and_state(events), expected_events);
@@ -68,6 +120,12 @@ pub struct InputEventBuilder {
     value: i32,
 }
 
+impl Default for InputEventBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl InputEventBuilder {
     pub fn new() -> InputEventBuilder {
         InputEventBuilder {
@@ -98,7 +156,7 @@ impl InputEventBuilder {
             time: self.time.clone(),
             event_code: self.code.clone(),
             event_type: int_to_event_type(ev_type).unwrap(),
-            value: self.value.clone(),
+            value: self.value,
         }
     }
 }
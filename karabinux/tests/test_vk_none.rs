@@ -0,0 +1,31 @@
+mod test_util;
+
+use evdev_rs::enums::*;
+use karabinux::key_state::KeyState;
+use test_util::*;
+
+const CONF_FILE_PATH: &str = "test_vk_none";
+
+#[test]
+fn vk_none_in_simple_modifications_disables_key() {
+    test_complex_modifications(
+        CONF_FILE_PATH,
+        vec![
+            (EV_KEY::KEY_CAPSLOCK, KeyState::Pressed),
+            (EV_KEY::KEY_CAPSLOCK, KeyState::Released),
+        ],
+        vec![],
+    );
+}
+
+#[test]
+fn vk_none_in_complex_modifications_emits_nothing() {
+    test_complex_modifications(
+        CONF_FILE_PATH,
+        vec![
+            (EV_KEY::KEY_A, KeyState::Pressed),
+            (EV_KEY::KEY_A, KeyState::Released),
+        ],
+        vec![],
+    );
+}
@@ -0,0 +1,148 @@
+mod test_util;
+
+use evdev_rs::enums::*;
+use karabinux::key_state::KeyState;
+use karabinux::state::StateManager;
+use karabinux::xremap;
+use karabinux::Error;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use std::fs::File;
+use test_util::*;
+
+const CONF_FILE_PATH: &str = "tests/config/test_xremap.yml";
+
+fn xremap_state() -> StateManager {
+    let import = xremap::import_path(CONF_FILE_PATH, "xremap").expect("failed to import config");
+    StateManager::from_profile(&import.profile).expect("failed to construct state")
+}
+
+#[test]
+fn xremap_config_is_imported() {
+    let import = xremap::import_path(CONF_FILE_PATH, "xremap").expect("failed to import config");
+    let expected: Value =
+        serde_json::from_reader(File::open("tests/config/test_xremap.json").unwrap())
+            .expect("failed to parse expected config");
+
+    assert_eq!(
+        serde_json::to_value(&import.profile).unwrap(),
+        expected["profiles"][0]
+    );
+}
+
+#[test]
+fn xremap_unsupported_constructs_are_warned_about() {
+    let import = xremap::import_path(CONF_FILE_PATH, "xremap").expect("failed to import config");
+    let warnings: Vec<_> = import.warnings.iter().map(|w| w.to_string()).collect();
+
+    assert_eq!(
+        warnings,
+        vec![
+            "virtual_modifiers: unsupported section, it was ignored",
+            "modmap[2].application: application filters are kept, but can't be checked on Linux: `only` never matches and `not` always does",
+            "keymap[0].application: application filters are kept, but can't be checked on Linux: `only` never matches and `not` always does",
+            "keymap[2].device: device filters aren't supported, this applies to every device",
+            "keymap[2].remap.Super-1: unsupported action set_mark: true, it was ignored",
+            "keymap[2].remap.Super-2: unknown key \"NotAKey\", it was ignored",
+        ]
+    );
+}
+
+#[test]
+fn xremap_modmap_is_a_simple_modification() {
+    let mut state = xremap_state();
+    test_state(
+        &mut state,
+        vec![
+            (EV_KEY::KEY_CAPSLOCK, KeyState::Pressed),
+            (EV_KEY::KEY_CAPSLOCK, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_ESC, KeyState::Pressed),
+            (EV_KEY::KEY_ESC, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn xremap_nested_remap_applies_to_the_next_key() {
+    let mut state = xremap_state();
+    test_state(
+        &mut state,
+        vec![
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Pressed),
+            (EV_KEY::KEY_X, KeyState::Pressed),
+            (EV_KEY::KEY_X, KeyState::Released),
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Released),
+            (EV_KEY::KEY_H, KeyState::Pressed),
+            (EV_KEY::KEY_H, KeyState::Released),
+            (EV_KEY::KEY_H, KeyState::Pressed),
+            (EV_KEY::KEY_H, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Pressed),
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Released),
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Released),
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Pressed),
+            (EV_KEY::KEY_A, KeyState::Pressed),
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Released),
+            (EV_KEY::KEY_A, KeyState::Released),
+            (EV_KEY::KEY_H, KeyState::Pressed),
+            (EV_KEY::KEY_H, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn xremap_nested_remap_forwards_other_keys() {
+    let mut state = xremap_state();
+    test_state(
+        &mut state,
+        vec![
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Pressed),
+            (EV_KEY::KEY_X, KeyState::Pressed),
+            (EV_KEY::KEY_X, KeyState::Released),
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Released),
+            (EV_KEY::KEY_J, KeyState::Pressed),
+            (EV_KEY::KEY_J, KeyState::Released),
+            (EV_KEY::KEY_H, KeyState::Pressed),
+            (EV_KEY::KEY_H, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Pressed),
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Released),
+            (EV_KEY::KEY_LEFTCTRL, KeyState::Released),
+            (EV_KEY::KEY_J, KeyState::Pressed),
+            (EV_KEY::KEY_J, KeyState::Released),
+            (EV_KEY::KEY_H, KeyState::Pressed),
+            (EV_KEY::KEY_H, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn xremap_empty_action_disables_key() {
+    let mut state = xremap_state();
+    test_state(
+        &mut state,
+        vec![
+            (EV_KEY::KEY_LEFTMETA, KeyState::Pressed),
+            (EV_KEY::KEY_M, KeyState::Pressed),
+            (EV_KEY::KEY_M, KeyState::Released),
+            (EV_KEY::KEY_LEFTMETA, KeyState::Released),
+        ],
+        vec![
+            (EV_KEY::KEY_LEFTMETA, KeyState::Pressed),
+            (EV_KEY::KEY_LEFTMETA, KeyState::Released),
+            (EV_KEY::KEY_LEFTMETA, KeyState::Released),
+        ],
+    );
+}
+
+#[test]
+fn invalid_xremap_config_is_reported() {
+    match xremap::import_str("modmap: [", "xremap") {
+        Err(Error::Yaml(_)) => {}
+        result => panic!("expected a YAML error, got: {:?}", result),
+    }
+}
@@ -1,4 +1,5 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use structopt::clap::{Error, ErrorKind};
 use structopt::StructOpt;
 
 /// Arguments for Karabinux.
@@ -14,11 +15,106 @@ pub struct Args {
     #[structopt(short = "v", long = "viewer")]
     pub viewer: bool,
 
-    /// An input device from /dev/input/*/*.
+    /// An input device from /dev/input/*/*. Required unless running a subcommand.
     #[structopt(short = "d", long = "device", parse(from_os_str))]
-    pub device: PathBuf,
+    pub device: Option<PathBuf>,
 
-    /// Path to a Karabiner config file.
-    #[structopt(short = "c", long = "config", parse(from_os_str))]
-    pub config: PathBuf,
+    /// Path to a Karabiner config file (JSON, YAML, TOML, a Goku `.edn` file or a
+    /// KMonad `.kbd` keymap).
+    /// Defaults to the first that exists of `$XDG_CONFIG_HOME/karabinux/karabinux.json`,
+    /// `~/.config/karabiner/karabiner.json` and `/etc/karabinux/karabinux.json`.
+    #[structopt(short = "c", long = "config", global = true, parse(from_os_str))]
+    pub config: Option<PathBuf>,
+
+    /// Directory of complex modification rule files, which profiles can use by
+    /// title. Defaults to `assets/complex_modifications` next to the config.
+    #[structopt(long = "assets", global = true, parse(from_os_str))]
+    pub assets: Option<PathBuf>,
+
+    /// Name of the profile to use, instead of the selected profile.
+    #[structopt(short = "p", long = "profile", global = true)]
+    pub profile: Option<String>,
+
+    /// Create a named pipe at this path, and switch to any profile whose name is
+    /// written to it (eg: `echo Gaming > /path/to/pipe`).
+    #[structopt(long = "profile-pipe", parse(from_os_str))]
+    pub profile_pipe: Option<PathBuf>,
+
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Args {
+    /// The input device, which is only missing when running a subcommand.
+    pub fn device_path(&self) -> &Path {
+        self.device.as_deref().expect("--device is required")
+    }
+
+    /// Exits with a usage error if no input device was given.
+    pub fn require_device(&self) {
+        if self.device.is_none() {
+            let message =
+                "The following required arguments were not provided:\n    --device <device>";
+            Error::with_description(message, ErrorKind::MissingRequiredArgument).exit();
+        }
+    }
+}
+
+/// Tools which run instead of remapping a device.
+#[derive(StructOpt, Debug, Clone)]
+pub enum Command {
+    /// Convert an xremap YAML config to a Karabiner config, printing a warning for
+    /// anything that can't be converted.
+    #[structopt(name = "import-xremap")]
+    ImportXremap {
+        /// Path to the xremap config file.
+        #[structopt(parse(from_os_str))]
+        path: PathBuf,
+
+        /// Name of the imported profile.
+        #[structopt(long = "profile-name", default_value = "xremap")]
+        profile_name: String,
+
+        /// Write the Karabiner config to this file, instead of printing it.
+        #[structopt(short = "o", long = "output", parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+
+    /// Report manipulators that can never apply, simple modifications that don't
+    /// do what they look like, and (with `--device`) keys the device can't send.
+    /// Checks every profile, unless `--profile` is given.
+    #[structopt(name = "lint")]
+    Lint,
+
+    /// Run the `tests` of each rule (or the cases in test case files), and print the
+    /// difference between the expected and actual output of those that fail.
+    /// Checks every profile, unless `--profile` is given.
+    #[structopt(name = "test")]
+    Test {
+        /// Test case files to run instead, which name the config they test.
+        #[structopt(parse(from_os_str))]
+        files: Vec<PathBuf>,
+    },
+
+    /// Print a cheat sheet of what the profile's keys do, grouped by rule. Uses the
+    /// settings for `--device` if it's given.
+    #[structopt(name = "cheat-sheet")]
+    CheatSheet {
+        /// Print a standalone HTML page instead of Markdown.
+        #[structopt(long = "html")]
+        html: bool,
+
+        /// Write the cheat sheet to this file, instead of printing it.
+        #[structopt(short = "o", long = "output", parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+
+    /// Print a JSON Schema for config files, for editors to validate and complete
+    /// them with.
+    #[structopt(name = "schema")]
+    Schema {
+        /// Write the schema to this file, instead of printing it.
+        #[structopt(short = "o", long = "output", parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
 }
@@ -0,0 +1,174 @@
+use crate::args::{Args, Command};
+use crate::device;
+use crate::uinput::output_device_config;
+use crate::{find_config_path, find_profile_or_exit, load_config_or_exit};
+use karabinux::cheat_sheet::CheatSheet;
+use karabinux::karabiner::{config_schema, KBConfig, KBProfile};
+use karabinux::lint::lint_profile;
+use karabinux::state::StateManager;
+use karabinux::testing::{run_rule_tests, run_test_case_file};
+use karabinux::xremap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Runs a subcommand, exiting if it fails.
+pub fn run(args: &Args, command: &Command) {
+    match command {
+        Command::ImportXremap {
+            path,
+            profile_name,
+            output,
+        } => import_xremap(path, profile_name, output.as_deref()),
+        Command::Lint => lint(args),
+        Command::Test { files } => test(args, files),
+        Command::CheatSheet { html, output } => cheat_sheet(args, *html, output.as_deref()),
+        Command::Schema { output } => schema(output.as_deref()),
+    }
+}
+
+fn import_xremap(path: &Path, profile_name: &str, output: Option<&Path>) {
+    let import = xremap::import_path(path, profile_name).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path.display(), e);
+        process::exit(1);
+    });
+
+    for warning in &import.warnings {
+        eprintln!("warning: {}", warning);
+    }
+
+    let kb_config = KBConfig::from_profiles(vec![import.profile]);
+    let result = match output {
+        Some(output) => kb_config.save(output),
+        None => kb_config.to_writer(io::stdout()).map(|_| println!()),
+    };
+
+    if let Err(e) = result {
+        eprintln!("Failed to write config: {}", e);
+        process::exit(1);
+    }
+}
+
+fn lint(args: &Args) {
+    let config_path = find_config_path(args);
+    let kb_config = load_config_or_exit(&config_path, args);
+    let profiles = profiles_to_check(&kb_config, args);
+
+    // The output device has the same keys as the input device.
+    let output_keys = args
+        .device
+        .as_deref()
+        .map(|path| output_device_config(path).events.ev_key);
+
+    let mut found_warnings = false;
+    for profile in profiles {
+        let warnings = lint_profile(profile, output_keys.as_deref()).unwrap_or_else(|e| {
+            eprintln!("{}: {}", config_path.display(), e);
+            process::exit(1);
+        });
+
+        for warning in &warnings {
+            println!("{}: {}", config_path.display(), warning);
+        }
+        found_warnings |= !warnings.is_empty();
+    }
+
+    if found_warnings {
+        process::exit(1);
+    }
+}
+
+fn test(args: &Args, files: &[PathBuf]) {
+    let mut results = vec![];
+    if files.is_empty() {
+        let config_path = find_config_path(args);
+        let kb_config = load_config_or_exit(&config_path, args);
+        for profile in profiles_to_check(&kb_config, args) {
+            results.extend(run_rule_tests(profile).unwrap_or_else(|e| {
+                eprintln!("{}: {}", config_path.display(), e);
+                process::exit(1);
+            }));
+        }
+    } else {
+        for file in files {
+            results.extend(run_test_case_file(file).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                process::exit(1);
+            }));
+        }
+    }
+
+    for result in &results {
+        println!("{}", result);
+    }
+
+    let failed = results.iter().filter(|r| !r.passed()).count();
+    println!("\n{} passed, {} failed", results.len() - failed, failed);
+    if failed > 0 {
+        process::exit(1);
+    }
+}
+
+/// The profile given by `--profile`, or every profile.
+fn profiles_to_check<'a>(kb_config: &'a KBConfig, args: &Args) -> Vec<&'a KBProfile> {
+    match &args.profile {
+        Some(_) => vec![find_profile_or_exit(kb_config, args)],
+        None => kb_config.profiles.iter().collect(),
+    }
+}
+
+fn cheat_sheet(args: &Args, html: bool, output: Option<&Path>) {
+    let config_path = find_config_path(args);
+    let kb_config = load_config_or_exit(&config_path, args);
+    let profile = find_profile_or_exit(&kb_config, args);
+
+    let state = match args.device.as_deref() {
+        Some(path) => StateManager::from_profile_for_device(
+            profile,
+            &device::device_identifiers(path),
+            &device::connected_device_identifiers(path),
+        ),
+        None => StateManager::from_profile(profile),
+    };
+    let state = state.unwrap_or_else(|e| {
+        eprintln!("{}: {}", config_path.display(), e);
+        process::exit(1);
+    });
+
+    let cheat_sheet = CheatSheet::new(profile, &state);
+    let contents = if html {
+        cheat_sheet.to_html()
+    } else {
+        cheat_sheet.to_markdown()
+    };
+
+    let result = match output {
+        Some(output) => fs::write(output, contents),
+        None => {
+            print!("{}", contents);
+            Ok(())
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("Failed to write cheat sheet: {}", e);
+        process::exit(1);
+    }
+}
+
+fn schema(output: Option<&Path>) {
+    let schema = config_schema();
+    let result = match output {
+        Some(output) => fs::write(output, format!("{:#}\n", schema)),
+        None => {
+            println!("{:#}", schema);
+            Ok(())
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("Failed to write schema: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,109 @@
+use std::env;
+use std::ffi::{CStr, CString, OsStr};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::PathBuf;
+
+/// Finds the first config file that exists, see `config_candidates`.
+pub fn find_config() -> Option<PathBuf> {
+    config_candidates().into_iter().find(|path| path.is_file())
+}
+
+/// Where to look for a config file when `--config` isn't given, in order.
+pub fn config_candidates() -> Vec<PathBuf> {
+    candidates(
+        env::var_os("XDG_CONFIG_HOME").map(PathBuf::from),
+        user_home(),
+    )
+}
+
+fn candidates(xdg_config_home: Option<PathBuf>, home: Option<PathBuf>) -> Vec<PathBuf> {
+    // Relative paths in `XDG_CONFIG_HOME` are invalid, and should be ignored.
+    let config_home = xdg_config_home
+        .filter(|path| path.is_absolute())
+        .or_else(|| home.as_ref().map(|home| home.join(".config")));
+
+    let mut paths = vec![];
+    if let Some(config_home) = config_home {
+        paths.push(config_home.join("karabinux").join("karabinux.json"));
+    }
+
+    if let Some(home) = home {
+        paths.push(
+            home.join(".config")
+                .join("karabiner")
+                .join("karabiner.json"),
+        );
+    }
+
+    paths.push(PathBuf::from("/etc/karabinux/karabinux.json"));
+    paths
+}
+
+/// The home directory of the user, or of the user who ran karabinux with `sudo`
+/// (rather than root's).
+fn user_home() -> Option<PathBuf> {
+    let is_root = unsafe { libc::geteuid() } == 0;
+    if let Some(sudo_user) = env::var_os("SUDO_USER") {
+        if is_root {
+            if let Some(home) = home_of(&sudo_user) {
+                return Some(home);
+            }
+        }
+    }
+
+    env::var_os("HOME").map(PathBuf::from)
+}
+
+fn home_of(user: &OsStr) -> Option<PathBuf> {
+    let user = CString::new(user.as_bytes()).ok()?;
+    unsafe {
+        let passwd = libc::getpwnam(user.as_ptr());
+        if passwd.is_null() || (*passwd).pw_dir.is_null() {
+            return None;
+        }
+
+        let dir = CStr::from_ptr((*passwd).pw_dir).to_bytes().to_vec();
+        Some(PathBuf::from(std::ffi::OsString::from_vec(dir)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::candidates;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    #[test]
+    fn candidates_use_xdg_config_home() {
+        assert_eq!(
+            candidates(Some(PathBuf::from("/xdg")), Some(PathBuf::from("/home/me"))),
+            vec![
+                PathBuf::from("/xdg/karabinux/karabinux.json"),
+                PathBuf::from("/home/me/.config/karabiner/karabiner.json"),
+                PathBuf::from("/etc/karabinux/karabinux.json"),
+            ]
+        );
+    }
+
+    #[test]
+    fn candidates_default_to_home_config() {
+        for xdg_config_home in [None, Some(PathBuf::from("relative"))] {
+            assert_eq!(
+                candidates(xdg_config_home, Some(PathBuf::from("/home/me"))),
+                vec![
+                    PathBuf::from("/home/me/.config/karabinux/karabinux.json"),
+                    PathBuf::from("/home/me/.config/karabiner/karabiner.json"),
+                    PathBuf::from("/etc/karabinux/karabinux.json"),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn candidates_without_home() {
+        assert_eq!(
+            candidates(None, None),
+            vec![PathBuf::from("/etc/karabinux/karabinux.json")]
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use glob::{MatchOptions, Pattern};
+use inotify::{Inotify, WatchDescriptor, WatchMask};
+use karabinux::event::Event;
+use karabinux::karabiner::KBConfig;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::thread;
+
+pub fn init_config_watcher(i_tx: Sender<Event>, path: PathBuf, assets_dir: Option<PathBuf>) {
+    thread::spawn(move || config_watcher(i_tx, path, assets_dir));
+}
+
+// Watcher thread: sends an event each time the config file, or a file that it
+// includes or an asset that it uses, is written.
+fn config_watcher(i_tx: Sender<Event>, path: PathBuf, assets_dir: Option<PathBuf>) {
+    let mut watcher = Watcher {
+        inotify: Inotify::init().expect("failed to initialise inotify"),
+        dirs: HashMap::new(),
+        path,
+        assets_dir,
+        globs: vec![],
+    };
+    watcher.watch_files();
+
+    let mut buffer = [0; 4096];
+    loop {
+        let events = watcher
+            .inotify
+            .read_events_blocking(&mut buffer)
+            .expect("failed to read inotify events");
+
+        let changed = events
+            .into_iter()
+            .any(|e| match (watcher.dirs.get(&e.wd), e.name) {
+                (Some(dirs), Some(name)) => {
+                    dirs.iter().any(|dir| watcher.is_watched(&dir.join(name)))
+                }
+                _ => false,
+            });
+        if changed {
+            i_tx.send(Event::ConfigChanged)
+                .expect("failed to send event");
+
+            // The config may include different files now.
+            watcher.watch_files();
+        }
+    }
+}
+
+struct Watcher {
+    inotify: Inotify,
+    /// The directories being watched. A directory can be watched by more than one
+    /// path, eg: `rules` and `./rules`, which share a watch.
+    dirs: HashMap<WatchDescriptor, Vec<PathBuf>>,
+    path: PathBuf,
+    assets_dir: Option<PathBuf>,
+    /// Globs matching the files the config includes and the assets it uses.
+    globs: Vec<Pattern>,
+}
+
+impl Watcher {
+    /// Watches the config file and the files it reads. If the config can't be
+    /// loaded (eg: while it's being edited), the files it read before are kept.
+    fn watch_files(&mut self) {
+        if let Ok(kb_config) = KBConfig::load(&self.path, self.assets_dir.as_deref()) {
+            self.globs = kb_config
+                .source_globs
+                .iter()
+                .filter_map(|glob| Pattern::new(glob).ok())
+                .collect();
+        }
+
+        // Editors often replace files rather than writing to them, so watch the
+        // directories and check the names of the files that were changed. New
+        // files that match a glob are found by watching the glob's directory.
+        let mut dirs = vec![parent_dir(&self.path).to_path_buf()];
+        for glob in &self.globs {
+            dirs.push(parent_dir(Path::new(glob.as_str())).to_path_buf());
+            let matches = glob::glob(glob.as_str()).into_iter().flatten().flatten();
+            dirs.extend(matches.map(|path| parent_dir(&path).to_path_buf()));
+        }
+
+        for dir in dirs {
+            let watch_dir = if dir == Path::new("") {
+                Path::new(".")
+            } else {
+                &dir
+            };
+            // Directories that don't exist (or are globs themselves) can't be watched.
+            let mask = WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO;
+            if let Ok(wd) = self.inotify.watches().add(watch_dir, mask) {
+                let dirs = self.dirs.entry(wd).or_default();
+                if !dirs.contains(&dir) {
+                    dirs.push(dir);
+                }
+            }
+        }
+    }
+
+    fn is_watched(&self, path: &Path) -> bool {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+
+        path == self.path
+            || self
+                .globs
+                .iter()
+                .any(|glob| glob.matches_path_with(path, options))
+    }
+}
+
+/// The directory a file is in, which is empty for files in the current directory.
+fn parent_dir(path: &Path) -> &Path {
+    path.parent().unwrap_or_else(|| Path::new(""))
+}
@@ -1,19 +1,71 @@
 use crate::args::Args;
+use evdev_rs::enums::{BusType, EventCode, EV_KEY, EV_REL};
 use evdev_rs::{Device, GrabMode, ReadFlag};
+use karabinux::constants::KARABINUX_DEVICE_NAME;
 use karabinux::event::Event;
-use std::fs::File;
+use karabinux::state::DeviceIdentifiers;
+use std::fs::{self, File};
+use std::path::Path;
 use std::process;
 use std::sync::mpsc::Sender;
 use std::thread;
 use std::time::Duration;
 
+/// Reads the identifiers of the input device at `path`.
+pub fn device_identifiers(path: &Path) -> DeviceIdentifiers {
+    let file = File::open(path).expect("failed to open file");
+    let device = Device::new_from_fd(file).expect("failed to create device");
+    identifiers_from_device(&device)
+}
+
+/// Reads the identifiers of all other input devices, skipping any that can't be
+/// opened and the virtual devices created by karabinux.
+pub fn connected_device_identifiers(except: &Path) -> Vec<DeviceIdentifiers> {
+    let entries = match fs::read_dir("/dev/input") {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let except = fs::canonicalize(except).ok();
+    entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            let is_event = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("event"));
+            is_event && fs::canonicalize(path).ok() != except
+        })
+        .filter_map(|path| File::open(path).ok())
+        .filter_map(|file| Device::new_from_fd(file).ok())
+        .filter(|device| {
+            !device
+                .name()
+                .is_some_and(|name| name.starts_with(KARABINUX_DEVICE_NAME))
+        })
+        .map(|device| identifiers_from_device(&device))
+        .collect()
+}
+
+fn identifiers_from_device(device: &Device) -> DeviceIdentifiers {
+    DeviceIdentifiers {
+        vendor_id: device.vendor_id() as u16,
+        product_id: device.product_id() as u16,
+        is_keyboard: device.has(&EventCode::EV_KEY(EV_KEY::KEY_A)),
+        is_pointing_device: device.has(&EventCode::EV_REL(EV_REL::REL_X))
+            || device.has(&EventCode::EV_KEY(EV_KEY::BTN_LEFT)),
+        // Laptop keyboards are usually connected via the i8042 (PS/2) controller.
+        is_built_in_keyboard: device.bustype() == BusType::BUS_I8042 as i32,
+    }
+}
+
 pub fn init_event_reader(i_tx: Sender<Event>, args: Args) {
     thread::spawn(move || event_reader(i_tx, args));
 }
 
 // Reader thread: reads structs from stdin, passes them to a Sender.
 fn event_reader(i_tx: Sender<Event>, args: Args) {
-    let file = File::open(&args.device).expect("failed to open file");
+    let file = File::open(args.device_path()).expect("failed to open file");
     let mut device = Device::new_from_fd(file).expect("failed to create device");
     let read_flags = ReadFlag::NORMAL | ReadFlag::BLOCKING;
 
@@ -79,9 +131,7 @@ fn event_reader(i_tx: Sender<Event>, args: Args) {
                 i_tx.send(Event::KeyEvent(ev))
                     .expect("failed to send event");
             }
-            Err(errno) => match errno {
-                e => panic!("failed to read event from stdin: {:?}", e),
-            },
+            Err(e) => panic!("failed to read event from stdin: {:?}", e),
         }
     }
 }
@@ -1,6 +1,7 @@
 use evdev_rs::enums::{
-    int_to_bus_type, int_to_ev_key, int_to_ev_led, int_to_ev_msc, int_to_ev_syn, int_to_input_prop,
-    BusType, EventCode, EventType, InputProp, EV_KEY, EV_LED, EV_MSC, EV_REP, EV_SYN,
+    int_to_bus_type, int_to_ev_key, int_to_ev_led, int_to_ev_msc, int_to_ev_rel, int_to_ev_syn,
+    int_to_input_prop, BusType, EventCode, EventType, InputProp, EV_KEY, EV_LED, EV_MSC, EV_REL,
+    EV_REP, EV_SYN,
 };
 use evdev_rs::Device;
 use karabinux::constants::KARABINUX_DEVICE_NAME;
@@ -18,11 +19,11 @@ pub fn device_from_config(device_config: &DeviceConfig) -> Device {
     dev.set_name(&name);
 
     if let Some(phys) = &device_config.phys {
-        dev.set_phys(&phys);
+        dev.set_phys(phys);
     }
 
     if let Some(uniq) = &device_config.uniq {
-        dev.set_uniq(&uniq);
+        dev.set_uniq(uniq);
     }
 
     dev.set_bustype(device_config.bustype);
@@ -51,6 +52,16 @@ pub fn device_from_config(device_config: &DeviceConfig) -> Device {
             .expect("failed to enabled EV_KEY events");
     }
 
+    if !device_config.events.ev_rel.is_empty() {
+        dev.enable(&EventType::EV_REL)
+            .expect("failed to enable EV_REL type");
+        for rel in &device_config.events.ev_rel {
+            let event_code = &EventCode::EV_REL(rel.clone());
+            dev.enable(event_code)
+                .expect("failed to enabled EV_REL events");
+        }
+    }
+
     dev.enable(&EventType::EV_MSC)
         .expect("failed to enable EV_MSC type");
     for msc in &device_config.events.ev_msc {
@@ -91,8 +102,11 @@ pub struct DeviceConfig {
     pub product_id: i32,
     pub vendor_id: i32,
     pub bustype: i32,
+    // Only used when debug printing the device configuration.
+    #[allow(dead_code)]
     pub bustype_string: Option<BusType>,
     pub version: i32,
+    #[allow(dead_code)]
     pub driver_version: i32,
     pub input_properties: Vec<InputProp>,
     pub events: DeviceConfigEvents,
@@ -122,12 +136,35 @@ impl DeviceConfig {
             events: DeviceConfigEvents::from_device(device),
         }
     }
+
+    /// Adds the events required to move the pointer and scroll, so `mouse_key`
+    /// events can be emitted from the device.
+    pub fn enable_pointer(&mut self) {
+        for rel in &[
+            EV_REL::REL_X,
+            EV_REL::REL_Y,
+            EV_REL::REL_WHEEL,
+            EV_REL::REL_HWHEEL,
+        ] {
+            if !self.events.ev_rel.contains(rel) {
+                self.events.ev_rel.push(rel.clone());
+            }
+        }
+
+        // Pointer devices aren't recognised without at least one button.
+        for key in &[EV_KEY::BTN_LEFT, EV_KEY::BTN_RIGHT, EV_KEY::BTN_MIDDLE] {
+            if !self.events.ev_key.contains(key) {
+                self.events.ev_key.push(key.clone());
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct DeviceConfigEvents {
     pub ev_syn: Vec<EV_SYN>,
     pub ev_key: Vec<EV_KEY>,
+    pub ev_rel: Vec<EV_REL>,
     pub ev_msc: Vec<EV_MSC>,
     pub ev_led: Vec<EV_LED>,
     pub ev_rep: DeviceConfigRepeatEvents,
@@ -153,6 +190,15 @@ impl DeviceConfigEvents {
             }
         }
 
+        let mut ev_rel = vec![];
+        for i in 0..EventType::get_max(&EventType::EV_REL).unwrap() {
+            if let Some(rel) = int_to_ev_rel(i as u32) {
+                if device.has(&EventCode::EV_REL(rel.clone())) {
+                    ev_rel.push(rel);
+                }
+            }
+        }
+
         let mut ev_msc = vec![];
         for i in 0..EventType::get_max(&EventType::EV_MSC).unwrap() {
             if let Some(msc) = int_to_ev_msc(i as u32) {
@@ -175,6 +221,7 @@ impl DeviceConfigEvents {
         DeviceConfigEvents {
             ev_syn,
             ev_key,
+            ev_rel,
             ev_msc,
             ev_led,
             ev_rep,
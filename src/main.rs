@@ -1,35 +1,75 @@
 mod args;
+mod commands;
+mod config_path;
+mod config_watcher;
 mod device;
 mod device_config;
+mod profile_pipe;
+mod timer;
 mod uinput;
 #[cfg(feature = "viewer")]
 mod viewer;
 
 use args::Args;
 use evdev_rs::enums::EventType;
+use evdev_rs::InputEvent;
 use karabinux::event::Event;
-use karabinux::karabiner::KBConfig;
-use karabinux::state::StateManager;
+use karabinux::karabiner::{KBConfig, KBProfile};
+use karabinux::state::{DeviceIdentifiers, StateManager};
+use karabinux::util::event_time_now;
+use std::path::{Path, PathBuf};
 use std::process;
-use std::sync::mpsc;
+use std::sync::mpsc::{self, Sender};
 use structopt::StructOpt;
 
 fn main() {
     let args = Args::from_args();
+    if let Some(command) = &args.command {
+        commands::run(&args, command);
+        return;
+    }
+    args.require_device();
+
+    // Find and parse the Karabiner configuration file.
+    let config_path = find_config_path(&args);
+    println!("Using config: {}", config_path.display());
 
-    // Parse the Karabiner configuration file.
-    let kb_config = KBConfig::from_path(&args.config).expect("failed to construct config");
-    let selected_profile = kb_config
-        .profiles
-        .iter()
-        .find(|p| p.selected)
-        .expect("failed to find selected profile");
+    let kb_config = load_config_or_exit(&config_path, &args);
+    let selected_profile = find_profile_or_exit(&kb_config, &args);
 
-    let mut state = StateManager::from_profile(&selected_profile);
+    // Apply the profile's settings for the input device.
+    let device = device::device_identifiers(args.device_path());
+    let connected_devices = device::connected_device_identifiers(args.device_path());
+    let state =
+        StateManager::from_profile_for_device(selected_profile, &device, &connected_devices)
+            .unwrap_or_else(|e| {
+                eprintln!("{}: {}", config_path.display(), e);
+                process::exit(1);
+            });
+    let mut session = Session {
+        config_path: config_path.clone(),
+        assets_dir: args.assets.clone(),
+        profile_name: selected_profile.name.clone(),
+        kb_config,
+        device,
+        connected_devices,
+        state,
+    };
 
     // Input channel: reads events from the libevdev device.
     let (i_tx, i_rx) = mpsc::channel();
-    device::init_event_reader(i_tx, args.clone());
+    device::init_event_reader(i_tx.clone(), args.clone());
+
+    // Profile pipe: reads the names of profiles to switch to.
+    if let Some(path) = &args.profile_pipe {
+        profile_pipe::init_profile_reader(i_tx.clone(), path.clone());
+    }
+
+    // Config watcher: reloads the config file when it, or a file it reads, changes.
+    config_watcher::init_config_watcher(i_tx.clone(), config_path, args.assets.clone());
+
+    // Timer: drives events that depend on how long keys are held for.
+    timer::init_timer(i_tx);
 
     // Output channel: writes events to a virtual libevdev uinput device.
     let (o_tx, o_rx) = mpsc::channel();
@@ -49,16 +89,34 @@ fn main() {
 
                     // Handle key events by transforming them via the state.
                     EventType::EV_KEY => {
-                        for event in state.get_mapped_events(ev) {
+                        for event in session.state.get_mapped_events(ev) {
                             o_tx.send(event).unwrap();
                         }
+
+                        if let Some(name) = session.state.take_selected_profile() {
+                            session.select_profile(&name, &o_tx);
+                        }
                     }
 
                     // Ignore anything else.
                     _ => {}
                 }
             }
-            Ok(Event::Timeout) => {}
+            Ok(Event::Timeout) => {
+                for event in session.state.get_timeout_events(&event_time_now()) {
+                    o_tx.send(event).unwrap();
+                }
+
+                if let Some(name) = session.state.take_selected_profile() {
+                    session.select_profile(&name, &o_tx);
+                }
+            }
+            Ok(Event::SelectProfile(name)) => {
+                session.select_profile(&name, &o_tx);
+            }
+            Ok(Event::ConfigChanged) => {
+                session.reload_config(&o_tx);
+            }
             Err(e) => {
                 eprintln!("{:?}", e);
                 process::exit(1);
@@ -66,3 +124,134 @@ fn main() {
         }
     }
 }
+
+/// The config and the state for one of its profiles, which can both be replaced
+/// while running.
+struct Session {
+    config_path: PathBuf,
+    assets_dir: Option<PathBuf>,
+    kb_config: KBConfig,
+    profile_name: String,
+    device: DeviceIdentifiers,
+    connected_devices: Vec<DeviceIdentifiers>,
+    state: StateManager,
+}
+
+impl Session {
+    /// Switches to the named profile.
+    fn select_profile(&mut self, name: &str, o_tx: &Sender<InputEvent>) {
+        let profile = match self.kb_config.find_profile(Some(name)) {
+            Some(profile) => profile,
+            None => return print_missing_profile(&self.kb_config, name),
+        };
+
+        match StateManager::from_profile_for_device(profile, &self.device, &self.connected_devices)
+        {
+            Ok(state) => {
+                self.profile_name = name.to_string();
+                self.replace_state(state, o_tx);
+                println!("Switched to profile: {}", name);
+            }
+            Err(e) => eprintln!("Failed to switch to profile {}: {}", name, e),
+        }
+    }
+
+    /// Re-reads the config file, and keeps the old config if it has any problems.
+    fn reload_config(&mut self, o_tx: &Sender<InputEvent>) {
+        let path = self.config_path.clone();
+        let kb_config = match KBConfig::load(&path, self.assets_dir.as_deref()) {
+            Ok(kb_config) => kb_config,
+            Err(e) => {
+                eprintln!("Failed to reload {}: {}", path.display(), e);
+                return;
+            }
+        };
+
+        // Stay on the same profile, unless it was removed.
+        let profile = match kb_config
+            .find_profile(Some(&self.profile_name))
+            .or_else(|| kb_config.find_profile(None))
+        {
+            Some(profile) => profile,
+            None => {
+                eprintln!(
+                    "Failed to reload {}: no profile is selected",
+                    path.display()
+                );
+                return;
+            }
+        };
+
+        match StateManager::from_profile_for_device(profile, &self.device, &self.connected_devices)
+        {
+            Ok(state) => {
+                self.profile_name = profile.name.clone();
+                self.kb_config = kb_config;
+                self.replace_state(state, o_tx);
+                println!(
+                    "Reloaded {}, using profile: {}",
+                    path.display(),
+                    self.profile_name
+                );
+            }
+            Err(e) => eprintln!("Failed to reload {}: {}", path.display(), e),
+        }
+    }
+
+    fn replace_state(&mut self, state: StateManager, o_tx: &Sender<InputEvent>) {
+        // Release everything the old state pressed, so nothing gets stuck.
+        for event in self.state.release_all_keys(&event_time_now()) {
+            o_tx.send(event).unwrap();
+        }
+
+        self.state = state;
+    }
+}
+
+/// The config file given by `--config`, or the first one found. Exits if there
+/// isn't one.
+fn find_config_path(args: &Args) -> PathBuf {
+    match &args.config {
+        Some(path) => path.clone(),
+        None => config_path::find_config().unwrap_or_else(|| {
+            eprintln!("No config file was found, use --config to choose one.");
+            eprintln!("Searched:");
+            for path in config_path::config_candidates() {
+                eprintln!("  {}", path.display());
+            }
+
+            process::exit(1);
+        }),
+    }
+}
+
+/// Loads the config file, exiting if it has any problems.
+fn load_config_or_exit(path: &Path, args: &Args) -> KBConfig {
+    KBConfig::load(path, args.assets.as_deref()).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path.display(), e);
+        process::exit(1);
+    })
+}
+
+/// The profile given by `--profile`, or the selected profile. Exits if it's missing.
+fn find_profile_or_exit<'a>(kb_config: &'a KBConfig, args: &Args) -> &'a KBProfile {
+    match kb_config.find_profile(args.profile.as_deref()) {
+        Some(profile) => profile,
+        None => {
+            match &args.profile {
+                Some(name) => print_missing_profile(kb_config, name),
+                None => eprintln!("No profile is selected, use --profile to choose one."),
+            }
+
+            process::exit(1);
+        }
+    }
+}
+
+fn print_missing_profile(kb_config: &KBConfig, name: &str) {
+    eprintln!("No profile named \"{}\" was found.", name);
+    eprintln!("Available profiles:");
+    for profile in &kb_config.profiles {
+        eprintln!("  {}", profile.name);
+    }
+}
@@ -0,0 +1,53 @@
+use karabinux::event::Event;
+use std::ffi::CString;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::thread;
+
+pub fn init_profile_reader(i_tx: Sender<Event>, path: PathBuf) {
+    create_pipe(&path);
+    thread::spawn(move || profile_reader(i_tx, path));
+}
+
+fn create_pipe(path: &Path) {
+    if let Ok(metadata) = fs::metadata(path) {
+        if metadata.file_type().is_fifo() {
+            return;
+        }
+
+        panic!("{} exists and is not a named pipe", path.display());
+    }
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).expect("invalid pipe path");
+    if unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) } != 0 {
+        panic!(
+            "failed to create named pipe: {:?}",
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+// Reader thread: reads profile names from the pipe, passes them to a Sender.
+fn profile_reader(i_tx: Sender<Event>, path: PathBuf) {
+    loop {
+        // Opening blocks until something writes to the pipe, and reading stops
+        // once the writer closes it, so it's re-opened for every writer.
+        let file = File::open(&path).expect("failed to open named pipe");
+        for line in BufReader::new(file).lines() {
+            match line {
+                Ok(line) => {
+                    let name = line.trim();
+                    if !name.is_empty() {
+                        i_tx.send(Event::SelectProfile(name.to_string()))
+                            .expect("failed to send event");
+                    }
+                }
+                Err(e) => eprintln!("failed to read from named pipe: {:?}", e),
+            }
+        }
+    }
+}
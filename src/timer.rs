@@ -0,0 +1,20 @@
+use karabinux::constants::TICK_INTERVAL_MS;
+use karabinux::event::Event;
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::Duration;
+
+pub fn init_timer(i_tx: Sender<Event>) {
+    thread::spawn(move || timer(i_tx));
+}
+
+// Timer thread: periodically sends timeouts so held keys can trigger events
+// (`to_if_held_down`), keys that weren't pressed simultaneously can be sent, and
+// held `mouse_key` events can move the pointer.
+fn timer(i_tx: Sender<Event>) {
+    let interval = Duration::from_millis(TICK_INTERVAL_MS);
+    loop {
+        thread::sleep(interval);
+        i_tx.send(Event::Timeout).expect("failed to send timeout");
+    }
+}
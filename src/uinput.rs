@@ -3,6 +3,7 @@ use crate::device_config::{device_from_config, DeviceConfig};
 use evdev_rs::{Device, InputEvent, UInputDevice};
 use karabinux::util::sync_event_now;
 use std::fs::File;
+use std::path::Path;
 use std::sync::mpsc::Receiver;
 use std::thread;
 
@@ -10,14 +11,23 @@ pub fn init_event_emitter(o_rx: Receiver<InputEvent>, args: Args) {
     thread::spawn(move || event_emitter(o_rx, args));
 }
 
-// Writer thread: receives structs from a Receiver, and writes them to stdout.
-fn event_emitter(o_rx: Receiver<InputEvent>, args: Args) {
+/// The configuration of the virtual device that events are written to, which is
+/// a copy of the input device at `path`.
+pub fn output_device_config(path: &Path) -> DeviceConfig {
     // First, create a libevdev device.
-    let file = File::open(&args.device).expect("failed to open file");
+    let file = File::open(path).expect("failed to open file");
     let device = Device::new_from_fd(file).expect("failed to create device");
 
-    // Then, extract its configuration and create a virtual device from it.
-    let config = DeviceConfig::from_device(&device);
+    // Then, extract its configuration for the virtual device.
+    // The virtual device is also a pointer, so it can emit `mouse_key` events.
+    let mut config = DeviceConfig::from_device(&device);
+    config.enable_pointer();
+    config
+}
+
+// Writer thread: receives structs from a Receiver, and writes them to stdout.
+fn event_emitter(o_rx: Receiver<InputEvent>, args: Args) {
+    let config = output_device_config(args.device_path());
     let device = device_from_config(&config);
     let uinput = UInputDevice::create_from_device(&device).expect("failed to create uinput");
 
@@ -6,7 +6,7 @@ use gio::prelude::*;
 use gtk::prelude::*;
 use gtk::{Application, ApplicationWindow, Label, WindowPosition};
 use karabinux::key_state::KeyState;
-use karabinux::util::find_karabinux_uinput_device;
+use karabinux::util::{find_karabinux_uinput_device, format_key};
 use std::sync::mpsc::Receiver;
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -48,7 +48,7 @@ const COLUMNS: [(&str, gtk::Type); 5] = [
 
 fn format_event_code(code: &EventCode) -> String {
     match code {
-        EventCode::EV_KEY(x) => format!("{:?}", x),
+        EventCode::EV_KEY(x) => format_key(x),
         EventCode::EV_MSC(x) => format!("{:?}", x),
         EventCode::EV_SYN(x) => format!("{:?}", x),
         _ => format!("{:?}", code),